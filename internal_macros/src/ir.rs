@@ -0,0 +1,1204 @@
+//! Parsing and expansion of the `ir!` macro, which turns a small, LLVM IR like
+//! language into the equivalent `Context`, `Module` and `Builder` calls.
+
+use std::collections::HashSet;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream, Result, Error};
+use syn::{braced, bracketed, parenthesized, Expr, Ident, LitBool, LitFloat, LitInt, LitStr, Token};
+
+/// The whole input of the macro: `context, module; item*`
+pub(crate) struct IrInput {
+    context: Expr,
+    module: Expr,
+    functions: Vec<IrFunction>,
+}
+
+impl Parse for IrInput {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let context = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let module = input.parse()?;
+        input.parse::<Token![;]>()?;
+
+        let mut functions = Vec::new();
+
+        while !input.is_empty() {
+            functions.push(input.parse()?);
+        }
+
+        if functions.is_empty() {
+            return Err(input.error("expected at least one `fn` or `extern fn`"));
+        }
+
+        Ok(IrInput { context, module, functions })
+    }
+}
+
+/// A `%name`, a `@name` or a block label
+#[derive(Clone)]
+struct IrName {
+    name: String,
+    span: Span,
+}
+
+impl IrName {
+    /// Parses an identifier (keywords included) or an integer, as in `%0`
+    fn parse_bare(input: ParseStream) -> Result<Self> {
+        if input.peek(LitInt) {
+            let lit = input.parse::<LitInt>()?;
+
+            return Ok(IrName { name: lit.base10_digits().to_string(), span: lit.span() });
+        }
+
+        let ident = Ident::parse_any(input)?;
+
+        Ok(IrName { name: ident.to_string(), span: ident.span() })
+    }
+
+    /// Parses a `%name`
+    fn parse_local(input: ParseStream) -> Result<Self> {
+        input.parse::<Token![%]>()?;
+
+        IrName::parse_bare(input)
+    }
+}
+
+enum IrTypeKind {
+    Int(u32),
+    Half,
+    Float,
+    Double,
+    Void,
+    Expr(Box<Expr>),
+    Pointer(Box<IrType>),
+}
+
+struct IrType {
+    kind: IrTypeKind,
+    span: Span,
+}
+
+impl IrType {
+    /// Determines whether the next token looks like the start of a type
+    fn peek(input: ParseStream) -> bool {
+        if input.peek(syn::token::Brace) {
+            return true;
+        }
+
+        let fork = input.fork();
+
+        match Ident::parse_any(&fork) {
+            Ok(ident) => parse_named_type(&ident.to_string()).is_some(),
+            Err(_) => false,
+        }
+    }
+
+    fn is_void(&self) -> bool {
+        matches!(self.kind, IrTypeKind::Void)
+    }
+
+    /// Expands to an expression of type `BasicTypeEnum`
+    fn expand(&self) -> Result<TokenStream> {
+        let span = self.span;
+
+        Ok(match &self.kind {
+            IrTypeKind::Int(bits) => quote_spanned!(span=> ::inkwell::types::BasicTypeEnum::from(__ir_context.custom_width_int_type(#bits))),
+            IrTypeKind::Half => quote_spanned!(span=> ::inkwell::types::BasicTypeEnum::from(__ir_context.f16_type())),
+            IrTypeKind::Float => quote_spanned!(span=> ::inkwell::types::BasicTypeEnum::from(__ir_context.f32_type())),
+            IrTypeKind::Double => quote_spanned!(span=> ::inkwell::types::BasicTypeEnum::from(__ir_context.f64_type())),
+            IrTypeKind::Void => return Err(Error::new(span, "`void` may only be used as a return type")),
+            IrTypeKind::Expr(expr) => quote_spanned!(span=> ::inkwell::types::BasicType::as_basic_type_enum(&(#expr))),
+            IrTypeKind::Pointer(pointee) => {
+                let pointee = pointee.expand()?;
+
+                quote_spanned!(span=> ::inkwell::types::BasicTypeEnum::from(::inkwell::types::BasicType::ptr_type(&#pointee, ::inkwell::AddressSpace::Generic)))
+            },
+        })
+    }
+}
+
+fn parse_named_type(name: &str) -> Option<IrTypeKind> {
+    match name {
+        "half" | "f16" => Some(IrTypeKind::Half),
+        "float" | "f32" => Some(IrTypeKind::Float),
+        "double" | "f64" => Some(IrTypeKind::Double),
+        "void" => Some(IrTypeKind::Void),
+        _ if name.starts_with('i') && name.len() > 1 => {
+            name[1..].parse().ok().filter(|&bits| bits > 0).map(IrTypeKind::Int)
+        },
+        _ => None,
+    }
+}
+
+impl Parse for IrType {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut ty = if input.peek(syn::token::Brace) {
+            let content;
+            let brace = braced!(content in input);
+
+            IrType { kind: IrTypeKind::Expr(Box::new(content.parse()?)), span: brace.span }
+        } else {
+            let ident = Ident::parse_any(input)?;
+            let kind = parse_named_type(&ident.to_string())
+                .ok_or_else(|| Error::new(ident.span(), format!("unknown type `{}`, expected a type such as `i32`, `double`, `i8*` or `{{expr}}`", ident)))?;
+
+            IrType { kind, span: ident.span() }
+        };
+
+        while input.peek(Token![*]) {
+            let star = input.parse::<Token![*]>()?;
+
+            if ty.is_void() {
+                return Err(Error::new(star.span, "pointers to `void` are not supported, use `i8*` instead"));
+            }
+
+            let span = ty.span;
+
+            ty = IrType { kind: IrTypeKind::Pointer(Box::new(ty)), span };
+        }
+
+        Ok(ty)
+    }
+}
+
+enum IrLiteral {
+    /// A zero extended or two's complement bit pattern, and whether it was negative
+    Int(u64, bool),
+    Float(f64),
+    Bool(bool),
+    Null,
+}
+
+enum IrOperand {
+    Local(IrName),
+    Expr(Box<Expr>),
+    Literal { ty: Option<IrType>, value: IrLiteral, span: Span },
+}
+
+impl IrOperand {
+    fn parse_literal(input: ParseStream) -> Result<(IrLiteral, Span)> {
+        if input.peek(LitBool) {
+            let lit = input.parse::<LitBool>()?;
+
+            return Ok((IrLiteral::Bool(lit.value), lit.span));
+        }
+
+        if input.peek(Ident::peek_any) {
+            let ident = Ident::parse_any(input)?;
+
+            if ident == "null" {
+                return Ok((IrLiteral::Null, ident.span()));
+            }
+
+            return Err(Error::new(ident.span(), format!("expected an operand, found `{}`", ident)));
+        }
+
+        let negative = input.peek(Token![-]);
+
+        if negative {
+            input.parse::<Token![-]>()?;
+        }
+
+        if input.peek(LitFloat) {
+            let lit = input.parse::<LitFloat>()?;
+            let value: f64 = lit.base10_parse()?;
+
+            return Ok((IrLiteral::Float(if negative { -value } else { value }), lit.span()));
+        }
+
+        let lit = input.parse::<LitInt>()?;
+
+        if negative {
+            let value: i64 = format!("-{}", lit.base10_digits()).parse().map_err(|_| Error::new(lit.span(), "integer literal out of range for 64 bits"))?;
+
+            Ok((IrLiteral::Int(value as u64, true), lit.span()))
+        } else {
+            Ok((IrLiteral::Int(lit.base10_parse()?, false), lit.span()))
+        }
+    }
+
+    /// Determines whether this operand is a literal without an explicit type
+    fn needs_type_hint(&self) -> bool {
+        match self {
+            IrOperand::Literal { ty: None, value: IrLiteral::Bool(_), .. } => false,
+            IrOperand::Literal { ty: None, .. } => true,
+            _ => false,
+        }
+    }
+
+    fn check(&self, scope: &Scope) -> Result<()> {
+        match self {
+            IrOperand::Local(name) => scope.check_value(name),
+            _ => Ok(()),
+        }
+    }
+
+    /// Expands to an expression of type `BasicValueEnum`. `hint` is an expression of type
+    /// `BasicTypeEnum` or `AnyTypeEnum` used to type untyped literals.
+    fn expand(&self, hint: Option<TokenStream>) -> Result<TokenStream> {
+        match self {
+            IrOperand::Local(name) => {
+                let ident = value_ident(name);
+
+                Ok(quote!(#ident))
+            },
+            IrOperand::Expr(expr) => Ok(quote!(::inkwell::values::BasicValue::as_basic_value_enum(&(#expr)))),
+            IrOperand::Literal { ty, value, span } => {
+                let span = *span;
+                let ty = match (ty, value) {
+                    (_, IrLiteral::Bool(_)) => quote!(__ir_context.bool_type()),
+                    (Some(ty), _) => ty.expand()?,
+                    (None, _) => match hint {
+                        Some(hint) => hint,
+                        None => return Err(Error::new(span, "cannot infer the type of this literal, write it with a type such as `i32 1`")),
+                    },
+                };
+
+                Ok(match value {
+                    IrLiteral::Int(bits, negative) => quote_spanned!(span=> ::inkwell::values::BasicValueEnum::from((#ty).into_int_type().const_int(#bits, #negative))),
+                    IrLiteral::Float(value) => quote_spanned!(span=> ::inkwell::values::BasicValueEnum::from((#ty).into_float_type().const_float(#value))),
+                    IrLiteral::Bool(value) => {
+                        let bits = *value as u64;
+
+                        quote_spanned!(span=> ::inkwell::values::BasicValueEnum::from(#ty.const_int(#bits, false)))
+                    },
+                    IrLiteral::Null => quote_spanned!(span=> ::inkwell::values::BasicValueEnum::from((#ty).into_pointer_type().const_null())),
+                })
+            },
+        }
+    }
+}
+
+impl Parse for IrOperand {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(Token![%]) {
+            return Ok(IrOperand::Local(IrName::parse_local(input)?));
+        }
+
+        if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+
+            return Ok(IrOperand::Expr(Box::new(content.parse()?)));
+        }
+
+        let ty = if IrType::peek(input) {
+            Some(input.parse::<IrType>()?)
+        } else {
+            None
+        };
+        let (value, span) = IrOperand::parse_literal(input)?;
+
+        Ok(IrOperand::Literal { ty, value, span })
+    }
+}
+
+enum IrCallee {
+    Named(IrName),
+    Expr(Box<Expr>),
+}
+
+enum IrInstruction {
+    Binary { opcode: Ident, flags: Vec<Ident>, lhs: IrOperand, rhs: IrOperand },
+    ICmp { predicate: Ident, lhs: IrOperand, rhs: IrOperand },
+    FCmp { predicate: Ident, lhs: IrOperand, rhs: IrOperand },
+    FNeg(IrOperand),
+    Cast { opcode: Ident, value: IrOperand, ty: IrType },
+    Select { condition: IrOperand, then_value: IrOperand, else_value: IrOperand },
+    Alloca(IrType),
+    Load(IrOperand),
+    Store { value: IrOperand, ptr: IrOperand },
+    Phi { ty: IrType, incoming: Vec<(IrOperand, IrName)> },
+    Call { callee: IrCallee, args: Vec<IrOperand> },
+    Ret(Option<IrOperand>),
+    Br(IrName),
+    CondBr { condition: IrOperand, then_block: IrName, else_block: IrName },
+    Unreachable,
+}
+
+const BINARY_OPCODES: &[&str] = &[
+    "add", "sub", "mul", "udiv", "sdiv", "urem", "srem", "shl", "lshr", "ashr", "and", "or", "xor",
+    "fadd", "fsub", "fmul", "fdiv", "frem",
+];
+const CAST_OPCODES: &[&str] = &[
+    "trunc", "zext", "sext", "fptrunc", "fpext", "fptoui", "fptosi", "uitofp", "sitofp", "ptrtoint", "inttoptr", "bitcast",
+];
+const BINARY_FLAGS: &[&str] = &["nsw", "nuw", "exact"];
+
+fn parse_label(input: ParseStream) -> Result<IrName> {
+    let label = Ident::parse_any(input)?;
+
+    if label != "label" {
+        return Err(Error::new(label.span(), "expected `label %name`"));
+    }
+
+    IrName::parse_local(input)
+}
+
+impl IrInstruction {
+    /// Whether or not this instruction may be given a `%name`
+    fn produces_value(&self) -> bool {
+        !matches!(self, IrInstruction::Store { .. } | IrInstruction::Ret(_) | IrInstruction::Br(_) |
+                        IrInstruction::CondBr { .. } | IrInstruction::Unreachable)
+    }
+}
+
+impl Parse for IrInstruction {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let opcode = Ident::parse_any(input)?;
+        let opcode_str = opcode.to_string();
+
+        let instruction = match opcode_str.as_str() {
+            op if BINARY_OPCODES.contains(&op) => {
+                let mut flags = Vec::new();
+
+                while input.peek(Ident::peek_any) {
+                    let fork = input.fork();
+                    let flag = Ident::parse_any(&fork)?;
+
+                    if !BINARY_FLAGS.contains(&flag.to_string().as_str()) {
+                        break;
+                    }
+
+                    flags.push(Ident::parse_any(input)?);
+                }
+
+                let lhs = input.parse()?;
+                input.parse::<Token![,]>()?;
+                let rhs = input.parse()?;
+
+                IrInstruction::Binary { opcode, flags, lhs, rhs }
+            },
+            "icmp" | "fcmp" => {
+                let predicate = Ident::parse_any(input)?;
+                let lhs = input.parse()?;
+                input.parse::<Token![,]>()?;
+                let rhs = input.parse()?;
+
+                if opcode_str == "icmp" {
+                    IrInstruction::ICmp { predicate, lhs, rhs }
+                } else {
+                    IrInstruction::FCmp { predicate, lhs, rhs }
+                }
+            },
+            "fneg" => IrInstruction::FNeg(input.parse()?),
+            op if CAST_OPCODES.contains(&op) => {
+                let value = input.parse()?;
+                let to = Ident::parse_any(input)?;
+
+                if to != "to" {
+                    return Err(Error::new(to.span(), format!("expected `{} <value> to <type>`", op)));
+                }
+
+                IrInstruction::Cast { opcode, value, ty: input.parse()? }
+            },
+            "select" => {
+                let condition = input.parse()?;
+                input.parse::<Token![,]>()?;
+                let then_value = input.parse()?;
+                input.parse::<Token![,]>()?;
+                let else_value = input.parse()?;
+
+                IrInstruction::Select { condition, then_value, else_value }
+            },
+            "alloca" => IrInstruction::Alloca(input.parse()?),
+            "load" => IrInstruction::Load(input.parse()?),
+            "store" => {
+                let value = input.parse()?;
+                input.parse::<Token![,]>()?;
+                let ptr = input.parse()?;
+
+                IrInstruction::Store { value, ptr }
+            },
+            "phi" => {
+                let ty = input.parse()?;
+                let mut incoming = Vec::new();
+
+                loop {
+                    let content;
+                    bracketed!(content in input);
+
+                    let value = content.parse()?;
+                    content.parse::<Token![,]>()?;
+                    let block = IrName::parse_local(&content)?;
+
+                    incoming.push((value, block));
+
+                    if !input.peek(Token![,]) {
+                        break;
+                    }
+
+                    input.parse::<Token![,]>()?;
+                }
+
+                IrInstruction::Phi { ty, incoming }
+            },
+            "call" => {
+                let callee = if input.peek(Token![@]) {
+                    input.parse::<Token![@]>()?;
+
+                    if input.peek(LitStr) {
+                        let lit = input.parse::<LitStr>()?;
+
+                        IrCallee::Named(IrName { name: lit.value(), span: lit.span() })
+                    } else {
+                        IrCallee::Named(IrName::parse_bare(input)?)
+                    }
+                } else {
+                    let content;
+                    braced!(content in input);
+
+                    IrCallee::Expr(Box::new(content.parse()?))
+                };
+
+                let content;
+                parenthesized!(content in input);
+
+                let args = content.parse_terminated::<IrOperand, Token![,]>(IrOperand::parse)?;
+
+                IrInstruction::Call { callee, args: args.into_iter().collect() }
+            },
+            "ret" => {
+                if input.is_empty() || input.peek(Token![;]) || peek_label(input) {
+                    IrInstruction::Ret(None)
+                } else if input.peek(Ident::peek_any) && input.fork().call(Ident::parse_any)? == "void" {
+                    Ident::parse_any(input)?;
+
+                    IrInstruction::Ret(None)
+                } else {
+                    IrInstruction::Ret(Some(input.parse()?))
+                }
+            },
+            "br" => {
+                if input.peek(Ident::peek_any) && input.fork().call(Ident::parse_any)? == "label" {
+                    IrInstruction::Br(parse_label(input)?)
+                } else {
+                    let condition = input.parse()?;
+                    input.parse::<Token![,]>()?;
+                    let then_block = parse_label(input)?;
+                    input.parse::<Token![,]>()?;
+                    let else_block = parse_label(input)?;
+
+                    IrInstruction::CondBr { condition, then_block, else_block }
+                }
+            },
+            "unreachable" => IrInstruction::Unreachable,
+            _ => return Err(Error::new(opcode.span(), format!("unknown instruction `{}`", opcode))),
+        };
+
+        Ok(instruction)
+    }
+}
+
+struct IrStatement {
+    result: Option<IrName>,
+    instruction: IrInstruction,
+}
+
+impl Parse for IrStatement {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let result = if input.peek(Token![%]) {
+            let name = IrName::parse_local(input)?;
+            input.parse::<Token![=]>()?;
+
+            Some(name)
+        } else {
+            None
+        };
+
+        let instruction: IrInstruction = input.parse()?;
+
+        if let Some(result) = &result {
+            if !instruction.produces_value() {
+                return Err(Error::new(result.span, "this instruction does not produce a value"));
+            }
+        }
+
+        // Semicolons between statements are optional
+        if input.peek(Token![;]) {
+            input.parse::<Token![;]>()?;
+        }
+
+        Ok(IrStatement { result, instruction })
+    }
+}
+
+struct IrBlock {
+    label: IrName,
+    statements: Vec<IrStatement>,
+}
+
+struct IrParam {
+    ty: IrType,
+    name: Option<IrName>,
+}
+
+struct IrFunction {
+    name: IrName,
+    params: Vec<IrParam>,
+    is_var_args: bool,
+    return_type: Option<IrType>,
+    /// `None` for `extern fn` declarations
+    body: Option<Vec<IrBlock>>,
+}
+
+/// Determines whether the next tokens are a `label:`
+fn peek_label(input: ParseStream) -> bool {
+    input.peek(Ident::peek_any) && input.peek2(Token![:]) && !input.peek2(Token![::])
+}
+
+impl Parse for IrFunction {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let is_extern = input.peek(Token![extern]);
+
+        if is_extern {
+            input.parse::<Token![extern]>()?;
+        }
+
+        input.parse::<Token![fn]>()?;
+
+        let name = if input.peek(LitStr) {
+            let lit = input.parse::<LitStr>()?;
+
+            IrName { name: lit.value(), span: lit.span() }
+        } else {
+            IrName::parse_bare(input)?
+        };
+
+        let content;
+        parenthesized!(content in input);
+
+        let mut params = Vec::new();
+        let mut is_var_args = false;
+
+        while !content.is_empty() {
+            if content.peek(Token![...]) {
+                content.parse::<Token![...]>()?;
+                is_var_args = true;
+
+                if !content.is_empty() {
+                    return Err(content.error("`...` must be the last parameter"));
+                }
+
+                break;
+            }
+
+            let ty = content.parse()?;
+            let name = if content.peek(Token![%]) {
+                Some(IrName::parse_local(&content)?)
+            } else {
+                None
+            };
+
+            params.push(IrParam { ty, name });
+
+            if content.is_empty() {
+                break;
+            }
+
+            content.parse::<Token![,]>()?;
+        }
+
+        let return_type = if input.peek(Token![->]) {
+            input.parse::<Token![->]>()?;
+
+            Some(input.parse::<IrType>()?).filter(|ty| !ty.is_void())
+        } else {
+            None
+        };
+
+        if is_extern {
+            input.parse::<Token![;]>()?;
+
+            return Ok(IrFunction { name, params, is_var_args, return_type, body: None });
+        }
+
+        let content;
+        braced!(content in input);
+
+        let mut blocks = Vec::new();
+
+        while !content.is_empty() {
+            let label = if peek_label(&content) {
+                let label = IrName::parse_bare(&content)?;
+                content.parse::<Token![:]>()?;
+
+                label
+            } else if blocks.is_empty() {
+                IrName { name: "entry".to_string(), span: content.span() }
+            } else {
+                return Err(content.error("expected a block label"));
+            };
+
+            let mut statements = Vec::new();
+
+            while !content.is_empty() && !peek_label(&content) {
+                statements.push(content.parse()?);
+            }
+
+            blocks.push(IrBlock { label, statements });
+        }
+
+        if blocks.is_empty() {
+            return Err(Error::new(name.span, "function bodies must contain at least one instruction, use `extern fn` for declarations"));
+        }
+
+        Ok(IrFunction { name, params, is_var_args, return_type, body: Some(blocks) })
+    }
+}
+
+fn value_ident(name: &IrName) -> Ident {
+    format_ident!("__ir_value_{}", name.name)
+}
+
+fn block_ident(name: &IrName) -> Ident {
+    format_ident!("__ir_block_{}", name.name)
+}
+
+/// A phi's identifier, type and incoming values, which are added once the whole body is built
+type DeferredPhi<'a> = (Ident, TokenStream, &'a [(IrOperand, IrName)]);
+
+/// Tracks the values and blocks which are defined so far in a function
+struct Scope {
+    values: HashSet<String>,
+    blocks: HashSet<String>,
+}
+
+impl Scope {
+    fn define_value(&mut self, name: &IrName) -> Result<()> {
+        if !self.values.insert(name.name.clone()) {
+            return Err(Error::new(name.span, format!("`%{}` is defined more than once", name.name)));
+        }
+
+        Ok(())
+    }
+
+    fn check_value(&self, name: &IrName) -> Result<()> {
+        if !self.values.contains(&name.name) {
+            return Err(Error::new(name.span, format!("`%{}` is not defined before this use", name.name)));
+        }
+
+        Ok(())
+    }
+
+    fn check_block(&self, name: &IrName) -> Result<()> {
+        if !self.blocks.contains(&name.name) {
+            return Err(Error::new(name.span, format!("no block is labeled `{}`", name.name)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Expands two operands which are typed alike, using each one to type the other if it is an untyped literal
+fn expand_operand_pair(lhs: &IrOperand, rhs: &IrOperand, lhs_ident: &Ident, rhs_ident: &Ident) -> Result<TokenStream> {
+    if lhs.needs_type_hint() {
+        let rhs = rhs.expand(None)?;
+        let lhs = lhs.expand(Some(quote!(#rhs_ident.get_type())))?;
+
+        Ok(quote! {
+            let #rhs_ident = #rhs;
+            let #lhs_ident = #lhs;
+        })
+    } else {
+        let lhs = lhs.expand(None)?;
+        let rhs = rhs.expand(Some(quote!(#lhs_ident.get_type())))?;
+
+        Ok(quote! {
+            let #lhs_ident = #lhs;
+            let #rhs_ident = #rhs;
+        })
+    }
+}
+
+fn int_predicate(predicate: &Ident) -> Result<Ident> {
+    let variant = match predicate.to_string().as_str() {
+        "eq" => "EQ",
+        "ne" => "NE",
+        "ugt" => "UGT",
+        "uge" => "UGE",
+        "ult" => "ULT",
+        "ule" => "ULE",
+        "sgt" => "SGT",
+        "sge" => "SGE",
+        "slt" => "SLT",
+        "sle" => "SLE",
+        _ => return Err(Error::new(predicate.span(), format!("unknown icmp predicate `{}`", predicate))),
+    };
+
+    Ok(Ident::new(variant, predicate.span()))
+}
+
+fn float_predicate(predicate: &Ident) -> Result<Ident> {
+    let variant = match predicate.to_string().as_str() {
+        "oeq" => "OEQ",
+        "ogt" => "OGT",
+        "oge" => "OGE",
+        "olt" => "OLT",
+        "ole" => "OLE",
+        "one" => "ONE",
+        "ord" => "ORD",
+        "uno" => "UNO",
+        "ueq" => "UEQ",
+        "ugt" => "UGT",
+        "uge" => "UGE",
+        "ult" => "ULT",
+        "ule" => "ULE",
+        "une" => "UNE",
+        "true" => "PredicateTrue",
+        "false" => "PredicateFalse",
+        _ => return Err(Error::new(predicate.span(), format!("unknown fcmp predicate `{}`", predicate))),
+    };
+
+    Ok(Ident::new(variant, predicate.span()))
+}
+
+/// Expands a binary operator to an expression producing an `IntValue` or `FloatValue`
+fn expand_binary(opcode: &Ident, flags: &[Ident], name: &str) -> Result<TokenStream> {
+    let opcode_str = opcode.to_string();
+    let flag_strs: Vec<String> = flags.iter().map(|flag| flag.to_string()).collect();
+    let has_flag = |flag: &str| flag_strs.iter().any(|f| f == flag);
+
+    for (flag, flag_str) in flags.iter().zip(&flag_strs) {
+        let allowed = match flag_str.as_str() {
+            "nsw" | "nuw" => matches!(opcode_str.as_str(), "add" | "sub" | "mul"),
+            "exact" => opcode_str == "sdiv",
+            _ => false,
+        };
+
+        if !allowed {
+            return Err(Error::new(flag.span(), format!("`{}` is not supported on `{}`", flag, opcode)));
+        }
+    }
+
+    if has_flag("nsw") && has_flag("nuw") {
+        return Err(Error::new(opcode.span(), "`nsw` and `nuw` cannot be combined"));
+    }
+
+    let int_method = match opcode_str.as_str() {
+        "add" | "sub" | "mul" => {
+            let prefix = if has_flag("nsw") {
+                "nsw_"
+            } else if has_flag("nuw") {
+                "nuw_"
+            } else {
+                ""
+            };
+
+            Some(format!("build_int_{}{}", prefix, opcode_str))
+        },
+        "udiv" => Some("build_int_unsigned_div".to_string()),
+        "sdiv" if has_flag("exact") => Some("build_int_exact_signed_div".to_string()),
+        "sdiv" => Some("build_int_signed_div".to_string()),
+        "urem" => Some("build_int_unsigned_rem".to_string()),
+        "srem" => Some("build_int_signed_rem".to_string()),
+        "shl" => Some("build_left_shift".to_string()),
+        "and" | "or" | "xor" => Some(format!("build_{}", opcode_str)),
+        _ => None,
+    };
+
+    if let Some(method) = int_method {
+        let method = Ident::new(&method, opcode.span());
+
+        return Ok(quote!(__ir_builder.#method(__ir_lhs.into_int_value(), __ir_rhs.into_int_value(), #name)));
+    }
+
+    match opcode_str.as_str() {
+        "lshr" | "ashr" => {
+            let sign_extend = opcode_str == "ashr";
+
+            Ok(quote!(__ir_builder.build_right_shift(__ir_lhs.into_int_value(), __ir_rhs.into_int_value(), #sign_extend, #name)))
+        },
+        _ => {
+            let method = format_ident!("build_float_{}", &opcode_str[1..], span = opcode.span());
+
+            Ok(quote!(__ir_builder.#method(__ir_lhs.into_float_value(), __ir_rhs.into_float_value(), #name)))
+        },
+    }
+}
+
+fn expand_cast(opcode: &Ident, ty: TokenStream, name: &str) -> TokenStream {
+    let (method, value_conversion, type_conversion) = match opcode.to_string().as_str() {
+        "trunc" => ("build_int_truncate", "into_int_value", "into_int_type"),
+        "zext" => ("build_int_z_extend", "into_int_value", "into_int_type"),
+        "sext" => ("build_int_s_extend", "into_int_value", "into_int_type"),
+        "fptrunc" => ("build_float_trunc", "into_float_value", "into_float_type"),
+        "fpext" => ("build_float_ext", "into_float_value", "into_float_type"),
+        "fptoui" => ("build_float_to_unsigned_int", "into_float_value", "into_int_type"),
+        "fptosi" => ("build_float_to_signed_int", "into_float_value", "into_int_type"),
+        "uitofp" => ("build_unsigned_int_to_float", "into_int_value", "into_float_type"),
+        "sitofp" => ("build_signed_int_to_float", "into_int_value", "into_float_type"),
+        "ptrtoint" => ("build_ptr_to_int", "into_pointer_value", "into_int_type"),
+        "inttoptr" => ("build_int_to_ptr", "into_int_value", "into_pointer_type"),
+        _ => return quote!(__ir_builder.build_bitcast(__ir_value, #ty, #name)),
+    };
+    let method = Ident::new(method, opcode.span());
+    let value_conversion = Ident::new(value_conversion, opcode.span());
+    let type_conversion = Ident::new(type_conversion, opcode.span());
+
+    quote!(__ir_builder.#method(__ir_value.#value_conversion(), (#ty).#type_conversion(), #name))
+}
+
+impl IrFunction {
+    /// Expands to a block evaluating to the new `FunctionValue`
+    fn expand(&self) -> Result<TokenStream> {
+        let name = &self.name.name;
+        let param_types = self.params.iter().map(|param| {
+            let ty = param.ty.expand()?;
+
+            Ok(quote!(::inkwell::types::BasicMetadataTypeEnum::from(#ty)))
+        }).collect::<Result<Vec<_>>>()?;
+        let is_var_args = self.is_var_args;
+
+        let (return_type, fn_type) = match &self.return_type {
+            Some(return_type) => {
+                let return_type = return_type.expand()?;
+
+                (
+                    quote!(let __ir_return_type = #return_type;),
+                    quote!(::inkwell::types::BasicType::fn_type(&__ir_return_type, &[#(#param_types),*], #is_var_args)),
+                )
+            },
+            None => (quote!(), quote!(__ir_context.void_type().fn_type(&[#(#param_types),*], #is_var_args))),
+        };
+
+        let body = match &self.body {
+            Some(blocks) => self.expand_body(blocks)?,
+            None => quote!(),
+        };
+
+        Ok(quote! {
+            {
+                #return_type
+                let __ir_function = __ir_module.add_function(#name, #fn_type, None);
+
+                #body
+
+                __ir_function
+            }
+        })
+    }
+
+    fn expand_body(&self, blocks: &[IrBlock]) -> Result<TokenStream> {
+        let mut scope = Scope { values: HashSet::new(), blocks: HashSet::new() };
+        let mut tokens = TokenStream::new();
+
+        for block in blocks {
+            if !scope.blocks.insert(block.label.name.clone()) {
+                return Err(Error::new(block.label.span, format!("block `{}` is defined more than once", block.label.name)));
+            }
+
+            let ident = block_ident(&block.label);
+            let label = &block.label.name;
+
+            tokens.extend(quote!(let #ident = __ir_context.append_basic_block(__ir_function, #label);));
+        }
+
+        for (index, param) in self.params.iter().enumerate() {
+            if let Some(name) = &param.name {
+                scope.define_value(name)?;
+
+                let ident = value_ident(name);
+                let index = index as u32;
+                let param_name = &name.name;
+
+                tokens.extend(quote! {
+                    let #ident = __ir_function.get_nth_param(#index).unwrap();
+                    ::inkwell::values::BasicValue::set_name(&#ident, #param_name);
+                });
+            }
+        }
+
+        let mut phis = Vec::new();
+        let mut deferred_incoming = TokenStream::new();
+
+        for block in blocks {
+            let block_ident = block_ident(&block.label);
+
+            tokens.extend(quote!(__ir_builder.position_at_end(#block_ident);));
+
+            for statement in &block.statements {
+                let statement_tokens = self.expand_statement(statement, &mut scope, &mut phis)?;
+
+                tokens.extend(statement_tokens);
+            }
+        }
+
+        // Phi incoming values may refer to values defined later on, so are only added
+        // once every value has been built
+        for (phi_ident, ty, incoming) in phis {
+            let mut incoming_values = Vec::new();
+
+            for (value, block) in incoming {
+                value.check(&scope)?;
+                scope.check_block(block)?;
+
+                let value = value.expand(Some(ty.clone()))?;
+                let block = block_ident(block);
+
+                incoming_values.push(quote!((&#value as &dyn ::inkwell::values::BasicValue, #block)));
+            }
+
+            deferred_incoming.extend(quote!(#phi_ident.add_incoming(&[#(#incoming_values),*]);));
+        }
+
+        tokens.extend(deferred_incoming);
+
+        Ok(tokens)
+    }
+
+    fn expand_statement<'a>(
+        &self,
+        statement: &'a IrStatement,
+        scope: &mut Scope,
+        phis: &mut Vec<DeferredPhi<'a>>,
+    ) -> Result<TokenStream> {
+        let name = statement.result.as_ref().map(|result| result.name.as_str()).unwrap_or("");
+        let lhs = format_ident!("__ir_lhs");
+        let rhs = format_ident!("__ir_rhs");
+
+        // Expands to an expression convertible to a `BasicValueEnum` or producing an `InstructionValue`
+        let expr = match &statement.instruction {
+            IrInstruction::Binary { opcode, flags, lhs: lhs_operand, rhs: rhs_operand } => {
+                lhs_operand.check(scope)?;
+                rhs_operand.check(scope)?;
+
+                let operands = expand_operand_pair(lhs_operand, rhs_operand, &lhs, &rhs)?;
+                let build = expand_binary(opcode, flags, name)?;
+
+                quote!({ #operands #build })
+            },
+            IrInstruction::ICmp { predicate, lhs: lhs_operand, rhs: rhs_operand } => {
+                lhs_operand.check(scope)?;
+                rhs_operand.check(scope)?;
+
+                let predicate = int_predicate(predicate)?;
+                let operands = expand_operand_pair(lhs_operand, rhs_operand, &lhs, &rhs)?;
+
+                quote!({
+                    #operands
+                    __ir_builder.build_int_compare(::inkwell::IntPredicate::#predicate, __ir_lhs.into_int_value(), __ir_rhs.into_int_value(), #name)
+                })
+            },
+            IrInstruction::FCmp { predicate, lhs: lhs_operand, rhs: rhs_operand } => {
+                lhs_operand.check(scope)?;
+                rhs_operand.check(scope)?;
+
+                let predicate = float_predicate(predicate)?;
+                let operands = expand_operand_pair(lhs_operand, rhs_operand, &lhs, &rhs)?;
+
+                quote!({
+                    #operands
+                    __ir_builder.build_float_compare(::inkwell::FloatPredicate::#predicate, __ir_lhs.into_float_value(), __ir_rhs.into_float_value(), #name)
+                })
+            },
+            IrInstruction::FNeg(value) => {
+                value.check(scope)?;
+
+                let value = value.expand(None)?;
+
+                quote!(__ir_builder.build_float_neg((#value).into_float_value(), #name))
+            },
+            IrInstruction::Cast { opcode, value, ty } => {
+                value.check(scope)?;
+
+                let value = value.expand(None)?;
+                let build = expand_cast(opcode, ty.expand()?, name);
+
+                quote!({
+                    let __ir_value = #value;
+                    #build
+                })
+            },
+            IrInstruction::Select { condition, then_value, else_value } => {
+                condition.check(scope)?;
+                then_value.check(scope)?;
+                else_value.check(scope)?;
+
+                let condition = condition.expand(None)?;
+                let operands = expand_operand_pair(then_value, else_value, &lhs, &rhs)?;
+
+                quote!({
+                    let __ir_condition = #condition;
+                    #operands
+                    __ir_builder.build_select(__ir_condition.into_int_value(), __ir_lhs, __ir_rhs, #name)
+                })
+            },
+            IrInstruction::Alloca(ty) => {
+                let ty = ty.expand()?;
+
+                quote!(__ir_builder.build_alloca(#ty, #name))
+            },
+            IrInstruction::Load(ptr) => {
+                ptr.check(scope)?;
+
+                let ptr = ptr.expand(None)?;
+
+                quote!(__ir_builder.build_load((#ptr).into_pointer_value(), #name))
+            },
+            IrInstruction::Store { value, ptr } => {
+                value.check(scope)?;
+                ptr.check(scope)?;
+
+                let ptr = ptr.expand(None)?;
+                let value = value.expand(Some(quote!(__ir_ptr.get_type().get_element_type())))?;
+
+                quote!({
+                    let __ir_ptr = (#ptr).into_pointer_value();
+                    let __ir_value = #value;
+
+                    __ir_builder.build_store(__ir_ptr, __ir_value)
+                })
+            },
+            IrInstruction::Phi { ty, incoming } => {
+                let phi_ident = format_ident!("__ir_phi_{}", phis.len());
+                let ty = ty.expand()?;
+
+                phis.push((phi_ident.clone(), ty.clone(), incoming.as_slice()));
+
+                let tokens = match &statement.result {
+                    Some(result) => {
+                        scope.define_value(result)?;
+
+                        let ident = value_ident(result);
+
+                        quote! {
+                            let #phi_ident = __ir_builder.build_phi(#ty, #name);
+                            let #ident = #phi_ident.as_basic_value();
+                        }
+                    },
+                    None => quote!(let #phi_ident = __ir_builder.build_phi(#ty, #name);),
+                };
+
+                return Ok(tokens);
+            },
+            IrInstruction::Call { callee, args } => {
+                let callee = match callee {
+                    IrCallee::Named(callee) => {
+                        let callee_name = &callee.name;
+                        let message = format!("call to undefined function @{}", callee_name);
+
+                        quote_spanned!(callee.span=> __ir_module.get_function(#callee_name).expect(#message))
+                    },
+                    IrCallee::Expr(expr) => quote!(#expr),
+                };
+                let args = args.iter().enumerate().map(|(index, arg)| {
+                    arg.check(scope)?;
+
+                    let arg = arg.expand(Some(quote!(__ir_callee.get_type().get_param_types()[#index])))?;
+
+                    Ok(quote!(::inkwell::values::BasicMetadataValueEnum::from(#arg)))
+                }).collect::<Result<Vec<_>>>()?;
+                let call = quote!({
+                    let __ir_callee: ::inkwell::values::FunctionValue = #callee;
+
+                    __ir_builder.build_call(__ir_callee, &[#(#args),*], #name)
+                });
+
+                match &statement.result {
+                    Some(result) => {
+                        let message = format!("`%{}` is the result of a call to a function returning void", result.name);
+
+                        quote!(#call.try_as_basic_value().left().expect(#message))
+                    },
+                    None => call,
+                }
+            },
+            IrInstruction::Ret(None) => {
+                if self.return_type.is_some() {
+                    return Err(Error::new(self.name.span, format!("`ret void` in function @{} which returns a value", self.name.name)));
+                }
+
+                quote!(__ir_builder.build_return(None))
+            },
+            IrInstruction::Ret(Some(value)) => {
+                if self.return_type.is_none() {
+                    return Err(Error::new(self.name.span, format!("`ret` with a value in function @{} which returns void", self.name.name)));
+                }
+
+                value.check(scope)?;
+
+                let value = value.expand(Some(quote!(__ir_return_type)))?;
+
+                quote!({
+                    let __ir_value = #value;
+
+                    __ir_builder.build_return(Some(&__ir_value))
+                })
+            },
+            IrInstruction::Br(block) => {
+                scope.check_block(block)?;
+
+                let block = block_ident(block);
+
+                quote!(__ir_builder.build_unconditional_branch(#block))
+            },
+            IrInstruction::CondBr { condition, then_block, else_block } => {
+                condition.check(scope)?;
+                scope.check_block(then_block)?;
+                scope.check_block(else_block)?;
+
+                let condition = condition.expand(None)?;
+                let then_block = block_ident(then_block);
+                let else_block = block_ident(else_block);
+
+                quote!(__ir_builder.build_conditional_branch((#condition).into_int_value(), #then_block, #else_block))
+            },
+            IrInstruction::Unreachable => quote!(__ir_builder.build_unreachable()),
+        };
+
+        match &statement.result {
+            Some(result) => {
+                scope.define_value(result)?;
+
+                let ident = value_ident(result);
+
+                Ok(quote!(let #ident = ::inkwell::values::BasicValueEnum::from(#expr);))
+            },
+            None => Ok(quote!(#expr;)),
+        }
+    }
+}
+
+impl IrInput {
+    pub(crate) fn expand(&self) -> Result<TokenStream> {
+        let context = &self.context;
+        let module = &self.module;
+        let mut names = HashSet::new();
+        let mut functions = Vec::with_capacity(self.functions.len());
+        let mut idents = Vec::with_capacity(self.functions.len());
+
+        for (index, function) in self.functions.iter().enumerate() {
+            if !names.insert(function.name.name.clone()) {
+                return Err(Error::new(function.name.span, format!("function @{} is defined more than once", function.name.name)));
+            }
+
+            let ident = format_ident!("__ir_function_{}", index);
+            let function = function.expand()?;
+
+            functions.push(quote!(let #ident = #function;));
+            idents.push(ident);
+        }
+
+        let result = if idents.len() == 1 {
+            quote!(#(#idents)*)
+        } else {
+            quote!((#(#idents),*))
+        };
+
+        Ok(quote! {
+            {
+                let __ir_context: &::inkwell::context::Context = #context;
+                let __ir_module: &::inkwell::module::Module = #module;
+                let __ir_builder = __ir_context.create_builder();
+
+                #(#functions)*
+
+                #result
+            }
+        })
+    }
+}
@@ -18,6 +18,8 @@ use syn::fold::Fold;
 use syn::spanned::Spanned;
 use syn::{Token, LitFloat, Ident, Item, Field, Variant, Attribute};
 
+mod ir;
+
 // This array should match the LLVM features in the top level Cargo manifest
 const FEATURE_VERSIONS: [&str; 14] =
     ["llvm3-6", "llvm3-7", "llvm3-8", "llvm3-9", "llvm4-0", "llvm5-0", "llvm6-0", "llvm7-0", "llvm8-0", "llvm9-0", "llvm10-0", "llvm11-0", "llvm12-0", "llvm13-0"];
@@ -604,3 +606,53 @@ pub fn llvm_enum(attribute_args: TokenStream, attributee: TokenStream) -> TokenS
     };
     q.into()
 }
+
+/// Builds functions from a small, LLVM IR like language, expanding to the equivalent
+/// `Context`, `Module` and `Builder` calls. Syntax errors, unknown instructions, undefined
+/// values and undefined blocks are reported at compile time. The macro evaluates to the new
+/// `FunctionValue`, or to a tuple of them when several functions are given.
+///
+/// The first arguments are a `&Context` and a `&Module`, followed by any number of
+/// `fn name(type %param, ...) -> type { ... }` definitions and `extern fn name(type, ...) -> type;`
+/// declarations. Function bodies consist of `label:` blocks (the first label may be omitted,
+/// and defaults to `entry`) of optionally `;` terminated instructions:
+///
+/// * `add`, `sub`, `mul` (optionally `nsw` or `nuw`), `udiv`, `sdiv` (optionally `exact`), `urem`,
+///   `srem`, `shl`, `lshr`, `ashr`, `and`, `or`, `xor`, `fadd`, `fsub`, `fmul`, `fdiv` and `frem`
+/// * `icmp <pred>` and `fcmp <pred>`, using LLVM's predicate names
+/// * `fneg`, `select`, `alloca <type>`, `load <ptr>`, `store <value>, <ptr>` and
+///   `phi <type> [<value>, %block], ...`
+/// * `trunc`, `zext`, `sext`, `fptrunc`, `fpext`, `fptoui`, `fptosi`, `uitofp`, `sitofp`,
+///   `ptrtoint`, `inttoptr` and `bitcast`, written as `zext <value> to <type>`
+/// * `call @name(<args>)`, where `name` is a function in the module, or `call {expr}(<args>)`
+/// * `ret`, `ret void`, `ret <value>`, `br label %block`, `br <cond>, label %then, label %else`
+///   and `unreachable`
+///
+/// Types are `iN`, `half`, `float`, `double`, `void` (as a return type only), pointers such as
+/// `i8*` or a `{expr}` evaluating to a `BasicType`. Operands are `%name`s, `{expr}`s evaluating to
+/// a `BasicValue`, or literals (`1`, `-1`, `1.5`, `true`, `null`) which may be prefixed by their type
+/// as in `i64 1` and otherwise take the type of the other operand where it can be inferred.
+///
+/// # Examples
+///
+/// ```ignore
+/// let context = Context::create();
+/// let module = context.create_module("my_module");
+/// let one = context.i32_type().const_int(1, false);
+///
+/// let function = ir!(&context, &module;
+///     fn add_one(i32 %a) -> i32 {
+///         %sum = add %a, {one};
+///         ret %sum;
+///     }
+/// );
+/// ```
+#[proc_macro]
+pub fn ir(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as ir::IrInput);
+
+    match input.expand() {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
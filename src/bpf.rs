@@ -0,0 +1,579 @@
+//! Helpers for authoring eBPF programs.
+//!
+//! libbpf expects programs, maps and the license to be laid out in specific ELF sections, with
+//! maps described by BTF type information. A `BpfObjectBuilder` adds each of these to a module so
+//! that, once compiled for a "bpfel" or "bpfeb" target, the object can be opened by libbpf.
+
+use crate::attributes::{Attribute, AttributeLoc};
+use crate::builder::Builder;
+use crate::context::ContextRef;
+use crate::debug_info::{
+    debug_metadata_version, AsDIScope, DIBasicType, DICompileUnit, DIFlags, DIFlagsConstants, DIType, DWARFEmissionKind,
+    DWARFSourceLanguage, DebugInfoBuilder,
+};
+use crate::module::{FlagBehavior, Module};
+use crate::types::{FunctionType, IntType};
+use crate::values::{BasicMetadataValueEnum, CallSiteValue, CallableValue, FunctionValue, GlobalValue};
+use crate::AddressSpace;
+
+use llvm_sys::core::LLVMIntTypeInContext;
+
+use std::convert::TryFrom;
+
+/// The type of a BPF map, which decides how the kernel stores and looks up its entries.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BpfMapType {
+    /// A hash table.
+    Hash,
+    /// An array indexed by `u32` keys, with all entries preallocated.
+    Array,
+    /// An array of programs, used with the tail call helper.
+    ProgArray,
+    /// An array of perf event ring buffers, one per CPU.
+    PerfEventArray,
+    /// A hash table with a separate value per CPU.
+    PerCpuHash,
+    /// An array with a separate value per CPU.
+    PerCpuArray,
+    /// Stack traces, as collected by the get stack ID helper.
+    StackTrace,
+    /// An array of cgroups.
+    CgroupArray,
+    /// A hash table evicting its least recently used entries when full.
+    LruHash,
+    /// A least recently used hash table with a separate value per CPU.
+    LruPerCpuHash,
+    /// A longest prefix match trie, as used for routing tables.
+    LpmTrie,
+    /// An array of other maps.
+    ArrayOfMaps,
+    /// A hash table of other maps.
+    HashOfMaps,
+    /// An array of network devices, used to redirect packets.
+    DevMap,
+    /// An array of sockets, used to redirect packets.
+    SockMap,
+    /// An array of CPUs, used to redirect packets.
+    CpuMap,
+    /// An array of AF_XDP sockets.
+    XskMap,
+    /// A hash table of sockets, used to redirect packets.
+    SockHash,
+    /// Storage local to each cgroup.
+    CgroupStorage,
+    /// An array of sockets for the `SO_REUSEPORT` selector.
+    ReuseportSockArray,
+    /// Storage local to each cgroup with a separate value per CPU.
+    PerCpuCgroupStorage,
+    /// A first in, first out queue, which has no keys.
+    Queue,
+    /// A last in, first out stack, which has no keys.
+    Stack,
+    /// Storage local to each socket.
+    SkStorage,
+    /// A hash table of network devices, used to redirect packets.
+    DevMapHash,
+    /// Kernel structures of function pointers implemented by BPF programs.
+    StructOps,
+    /// A ring buffer shared by all CPUs, which has neither keys nor values.
+    RingBuf,
+    /// Storage local to each inode.
+    InodeStorage,
+    /// Storage local to each task.
+    TaskStorage,
+}
+
+impl BpfMapType {
+    /// Gets the `BPF_MAP_TYPE_*` value the kernel identifies this map type by.
+    pub fn get_id(self) -> u32 {
+        match self {
+            BpfMapType::Hash => 1,
+            BpfMapType::Array => 2,
+            BpfMapType::ProgArray => 3,
+            BpfMapType::PerfEventArray => 4,
+            BpfMapType::PerCpuHash => 5,
+            BpfMapType::PerCpuArray => 6,
+            BpfMapType::StackTrace => 7,
+            BpfMapType::CgroupArray => 8,
+            BpfMapType::LruHash => 9,
+            BpfMapType::LruPerCpuHash => 10,
+            BpfMapType::LpmTrie => 11,
+            BpfMapType::ArrayOfMaps => 12,
+            BpfMapType::HashOfMaps => 13,
+            BpfMapType::DevMap => 14,
+            BpfMapType::SockMap => 15,
+            BpfMapType::CpuMap => 16,
+            BpfMapType::XskMap => 17,
+            BpfMapType::SockHash => 18,
+            BpfMapType::CgroupStorage => 19,
+            BpfMapType::ReuseportSockArray => 20,
+            BpfMapType::PerCpuCgroupStorage => 21,
+            BpfMapType::Queue => 22,
+            BpfMapType::Stack => 23,
+            BpfMapType::SkStorage => 24,
+            BpfMapType::DevMapHash => 25,
+            BpfMapType::StructOps => 26,
+            BpfMapType::RingBuf => 27,
+            BpfMapType::InodeStorage => 28,
+            BpfMapType::TaskStorage => 29,
+        }
+    }
+}
+
+/// Describes a BPF map to be created by the loader.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct BpfMapDefinition {
+    map_type: BpfMapType,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    flags: u32,
+}
+
+impl BpfMapDefinition {
+    /// Creates a definition of a map of `map_type` holding up to `max_entries` entries, whose keys
+    /// and values are `key_size` and `value_size` bytes. Sizes of zero are left out, as some map
+    /// types require.
+    pub fn new(map_type: BpfMapType, key_size: u32, value_size: u32, max_entries: u32) -> Self {
+        BpfMapDefinition {
+            map_type,
+            key_size,
+            value_size,
+            max_entries,
+            flags: 0,
+        }
+    }
+
+    /// Sets the `BPF_F_*` flags the map is created with.
+    pub fn with_flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Gets the type of the map.
+    pub fn get_map_type(&self) -> BpfMapType {
+        self.map_type
+    }
+
+    /// Gets the size of the map's keys in bytes.
+    pub fn get_key_size(&self) -> u32 {
+        self.key_size
+    }
+
+    /// Gets the size of the map's values in bytes.
+    pub fn get_value_size(&self) -> u32 {
+        self.value_size
+    }
+
+    /// Gets the maximum number of entries in the map.
+    pub fn get_max_entries(&self) -> u32 {
+        self.max_entries
+    }
+
+    /// Gets the `BPF_F_*` flags the map is created with.
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+
+    // The fields as libbpf reads them from BTF, in the order declared by its __uint macro users
+    fn get_fields(&self) -> Vec<(&'static str, u32)> {
+        let mut fields = vec![("type", self.map_type.get_id()), ("max_entries", self.max_entries)];
+
+        for &(name, value) in &[("key_size", self.key_size), ("value_size", self.value_size), ("map_flags", self.flags)] {
+            if value != 0 {
+                fields.push((name, value));
+            }
+        }
+
+        fields
+    }
+}
+
+/// The section a BPF program is placed in, from which libbpf infers its program type and where
+/// to attach it.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ProgramSection<'a> {
+    /// A socket filter.
+    SocketFilter,
+    /// A kprobe attached to the entry of the named kernel function.
+    Kprobe(&'a str),
+    /// A kprobe attached to the return of the named kernel function.
+    Kretprobe(&'a str),
+    /// A tracepoint, given by its category and name.
+    Tracepoint(&'a str, &'a str),
+    /// A raw tracepoint, given by its name.
+    RawTracepoint(&'a str),
+    /// An XDP program, run on each packet received by a network device.
+    Xdp,
+    /// A traffic control classifier.
+    Tc,
+    /// A program run on each packet entering a cgroup.
+    CgroupSkbIngress,
+    /// A program run on each packet leaving a cgroup.
+    CgroupSkbEgress,
+    /// Any other section, given by its full name.
+    Custom(&'a str),
+}
+
+impl ProgramSection<'_> {
+    /// Gets the name of the ELF section.
+    pub fn get_name(&self) -> String {
+        match self {
+            ProgramSection::SocketFilter => "socket".into(),
+            ProgramSection::Kprobe(function) => format!("kprobe/{}", function),
+            ProgramSection::Kretprobe(function) => format!("kretprobe/{}", function),
+            ProgramSection::Tracepoint(category, name) => format!("tracepoint/{}/{}", category, name),
+            ProgramSection::RawTracepoint(name) => format!("raw_tracepoint/{}", name),
+            ProgramSection::Xdp => "xdp".into(),
+            ProgramSection::Tc => "tc".into(),
+            ProgramSection::CgroupSkbIngress => "cgroup_skb/ingress".into(),
+            ProgramSection::CgroupSkbEgress => "cgroup_skb/egress".into(),
+            ProgramSection::Custom(name) => (*name).into(),
+        }
+    }
+}
+
+/// A kernel function callable from BPF programs.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BpfHelper {
+    /// `void *bpf_map_lookup_elem(void *map, const void *key)`
+    MapLookupElem,
+    /// `long bpf_map_update_elem(void *map, const void *key, const void *value, u64 flags)`
+    MapUpdateElem,
+    /// `long bpf_map_delete_elem(void *map, const void *key)`
+    MapDeleteElem,
+    /// `long bpf_probe_read(void *dst, u32 size, const void *unsafe_ptr)`
+    ProbeRead,
+    /// `u64 bpf_ktime_get_ns(void)`
+    KtimeGetNs,
+    /// `long bpf_trace_printk(const char *fmt, u32 fmt_size, ...)`
+    TracePrintk,
+    /// `u32 bpf_get_prandom_u32(void)`
+    GetPrandomU32,
+    /// `u32 bpf_get_smp_processor_id(void)`
+    GetSmpProcessorId,
+    /// `long bpf_tail_call(void *ctx, void *prog_array_map, u32 index)`
+    TailCall,
+    /// `u64 bpf_get_current_pid_tgid(void)`
+    GetCurrentPidTgid,
+    /// `u64 bpf_get_current_uid_gid(void)`
+    GetCurrentUidGid,
+    /// `long bpf_get_current_comm(void *buf, u32 size_of_buf)`
+    GetCurrentComm,
+    /// `long bpf_redirect(u32 ifindex, u64 flags)`
+    Redirect,
+    /// `long bpf_perf_event_output(void *ctx, void *map, u64 flags, void *data, u64 size)`
+    PerfEventOutput,
+    /// `long bpf_redirect_map(void *map, u32 key, u64 flags)`
+    RedirectMap,
+    /// `long bpf_probe_read_user(void *dst, u32 size, const void *unsafe_ptr)`
+    ProbeReadUser,
+    /// `long bpf_probe_read_kernel(void *dst, u32 size, const void *unsafe_ptr)`
+    ProbeReadKernel,
+    /// `long bpf_ringbuf_output(void *ringbuf, void *data, u64 size, u64 flags)`
+    RingbufOutput,
+    /// Any other helper, given by its `BPF_FUNC_*` ID.
+    Other(u32),
+}
+
+impl BpfHelper {
+    /// Gets the `BPF_FUNC_*` ID the kernel identifies this helper by.
+    pub fn get_id(self) -> u32 {
+        match self {
+            BpfHelper::MapLookupElem => 1,
+            BpfHelper::MapUpdateElem => 2,
+            BpfHelper::MapDeleteElem => 3,
+            BpfHelper::ProbeRead => 4,
+            BpfHelper::KtimeGetNs => 5,
+            BpfHelper::TracePrintk => 6,
+            BpfHelper::GetPrandomU32 => 7,
+            BpfHelper::GetSmpProcessorId => 8,
+            BpfHelper::TailCall => 12,
+            BpfHelper::GetCurrentPidTgid => 14,
+            BpfHelper::GetCurrentUidGid => 15,
+            BpfHelper::GetCurrentComm => 16,
+            BpfHelper::Redirect => 23,
+            BpfHelper::PerfEventOutput => 25,
+            BpfHelper::RedirectMap => 51,
+            BpfHelper::ProbeReadUser => 112,
+            BpfHelper::ProbeReadKernel => 113,
+            BpfHelper::RingbufOutput => 130,
+            BpfHelper::Other(id) => id,
+        }
+    }
+}
+
+/// Adds BPF programs, maps and a license to a `Module`, along with the debug information libbpf
+/// reads map definitions from.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::OptimizationLevel;
+/// use inkwell::bpf::{BpfMapDefinition, BpfMapType, BpfObjectBuilder, ProgramSection};
+/// use inkwell::context::Context;
+/// use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetTriple};
+///
+/// let context = Context::create();
+/// let module = context.create_module("xdp_pass");
+/// let builder = context.create_builder();
+/// let bpf_object = BpfObjectBuilder::new(&module, "xdp_pass.c");
+///
+/// bpf_object.set_license("GPL");
+/// bpf_object.add_map("packets", &BpfMapDefinition::new(BpfMapType::Array, 4, 8, 1));
+///
+/// let program = bpf_object.add_program("xdp_pass", ProgramSection::Xdp);
+/// let xdp_pass = context.i32_type().const_int(2, false);
+///
+/// builder.position_at_end(context.append_basic_block(program, "entry"));
+/// builder.build_return(Some(&xdp_pass));
+/// bpf_object.finalize();
+///
+/// Target::initialize_bpf(&InitializationConfig::default());
+///
+/// let triple = TargetTriple::create("bpfel");
+/// let target = Target::from_triple(&triple).unwrap();
+/// let target_machine = target.create_target_machine(&triple, "generic", "", OptimizationLevel::Default, RelocMode::Default, CodeModel::Default).unwrap();
+/// let object = target_machine.write_to_memory_buffer(&module, FileType::Object).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct BpfObjectBuilder<'a, 'ctx> {
+    context: ContextRef<'ctx>,
+    module: &'a Module<'ctx>,
+    i8_type: IntType<'ctx>,
+    i32_type: IntType<'ctx>,
+    i64_type: IntType<'ctx>,
+    debug_info: DebugInfoBuilder<'ctx>,
+    compile_unit: DICompileUnit<'ctx>,
+    int_type: DIBasicType<'ctx>,
+}
+
+impl<'a, 'ctx> BpfObjectBuilder<'a, 'ctx> {
+    /// Creates a `BpfObjectBuilder` adding to `module`. Its debug information names
+    /// `source_filename` as the file everything is defined in.
+    pub fn new(module: &'a Module<'ctx>, source_filename: &str) -> Self {
+        let context = module.get_context();
+        // The module's context outlives 'ctx, so types created in it may be used with the module
+        let (i8_type, i32_type, i64_type) = unsafe {
+            (
+                IntType::new(LLVMIntTypeInContext(context.context, 8)),
+                IntType::new(LLVMIntTypeInContext(context.context, 32)),
+                IntType::new(LLVMIntTypeInContext(context.context, 64)),
+            )
+        };
+
+        // Another builder may already have described the module
+        if module.get_flag("Debug Info Version").is_none() {
+            let debug_metadata_version = i32_type.const_int(debug_metadata_version() as u64, false);
+
+            module.add_basic_value_flag("Debug Info Version", FlagBehavior::Warning, debug_metadata_version);
+        }
+
+        let (debug_info, compile_unit) = module.create_debug_info_builder(
+            true,
+            DWARFSourceLanguage::C,
+            source_filename,
+            ".",
+            "inkwell",
+            false,
+            "",
+            0,
+            "",
+            DWARFEmissionKind::Full,
+            0,
+            false,
+            false,
+            #[cfg(any(feature = "llvm11-0", feature = "llvm12-0", feature = "llvm13-0"))]
+            "",
+            #[cfg(any(feature = "llvm11-0", feature = "llvm12-0", feature = "llvm13-0"))]
+            "",
+        );
+        // DW_ATE_signed
+        let int_type = debug_info.create_basic_type("int", 32, 0x05, DIFlags::PUBLIC).unwrap();
+
+        BpfObjectBuilder {
+            context,
+            module,
+            i8_type,
+            i32_type,
+            i64_type,
+            debug_info,
+            compile_unit,
+            int_type,
+        }
+    }
+
+    /// Sets the license the programs are distributed under, which the kernel checks before
+    /// allowing calls to GPL only helpers.
+    pub fn set_license(&self, license: &str) -> GlobalValue<'ctx> {
+        let chars: Vec<_> = license
+            .bytes()
+            .chain(Some(0))
+            .map(|byte| self.i8_type.const_int(byte as u64, false))
+            .collect();
+        let license_type = self.i8_type.array_type(chars.len() as u32);
+        let global = self.module.add_global(license_type, None, "_license");
+
+        global.set_initializer(&self.i8_type.const_array(&chars));
+        global.set_section("license");
+
+        global
+    }
+
+    /// Adds a map named `name` to the ".maps" section. The map's global may be passed to helpers
+    /// such as `BpfHelper::MapLookupElem` as the map argument.
+    ///
+    /// Like C programs using libbpf's `__uint` macro, the map is described by a struct of pointers
+    /// to arrays, whose lengths libbpf reads from BTF as each field's value.
+    pub fn add_map(&self, name: &str, definition: &BpfMapDefinition) -> GlobalValue<'ctx> {
+        let file = self.compile_unit.get_file();
+        let scope = self.compile_unit.as_debug_info_scope();
+        let fields = definition.get_fields();
+        let members: Vec<DIType<'ctx>> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, &(field, value))| {
+                let array_type = self.debug_info.create_array_type(self.int_type.as_type(), 32 * value as u64, 32, &[0..value as i64]);
+                let pointer_type = self.debug_info.create_pointer_type("", Some(array_type.as_type()), 64, 64, AddressSpace::Generic);
+
+                self.debug_info
+                    .create_member_type(scope, field, file, 0, 64, 64, 64 * i as u64, DIFlags::PUBLIC, pointer_type.as_type())
+                    .as_type()
+            })
+            .collect();
+        let struct_di_type = self.debug_info.create_struct_type(
+            scope,
+            "",
+            file,
+            0,
+            64 * members.len() as u64,
+            64,
+            DIFlags::PUBLIC,
+            None,
+            &members,
+            0,
+            None,
+            "",
+        );
+        let global_di = self.debug_info.create_global_variable_expression(
+            scope,
+            name,
+            "",
+            file,
+            0,
+            struct_di_type.as_type(),
+            false,
+            None,
+            None,
+            64,
+        );
+
+        // Laid out the same as the struct BTF describes, one pointer per field
+        let fields_type = self.i8_type.ptr_type(AddressSpace::Generic).array_type(fields.len() as u32);
+        let global = self.module.add_global(fields_type, None, name);
+
+        global.set_initializer(&fields_type.const_zero());
+        global.set_section(".maps");
+        global.set_alignment(8);
+        global.set_metadata(global_di.as_metadata_value(&self.context), self.context.get_kind_id("dbg"));
+
+        global
+    }
+
+    /// Adds a program named `name` to `section`. Programs take a pointer to their context, whose
+    /// type depends on the section, and return an `i32`.
+    pub fn add_program(&self, name: &str, section: ProgramSection) -> FunctionValue<'ctx> {
+        let fn_type = self.i32_type.fn_type(&[self.i8_type.ptr_type(AddressSpace::Generic).into()], false);
+        let function = self.module.add_function(name, fn_type, None);
+        let file = self.compile_unit.get_file();
+        let context_di_type = self.debug_info.create_pointer_type("", None, 64, 64, AddressSpace::Generic);
+        let subroutine_type = self.debug_info.create_subroutine_type(
+            file,
+            Some(self.int_type.as_type()),
+            &[context_di_type.as_type()],
+            DIFlags::PROTOTYPED,
+        );
+        let subprogram = self.debug_info.create_function(
+            self.compile_unit.as_debug_info_scope(),
+            name,
+            None,
+            file,
+            0,
+            subroutine_type,
+            false,
+            true,
+            0,
+            DIFlags::PROTOTYPED,
+            false,
+        );
+        // Keeps the object free of an .eh_frame section, which BPF loaders have no use for
+        let nounwind = self.context.create_enum_attribute(Attribute::get_named_enum_kind_id("nounwind"), 0);
+
+        function.as_global_value().set_section(&section.get_name());
+        function.set_subprogram(subprogram);
+        function.add_attribute(AttributeLoc::Function, nounwind);
+
+        function
+    }
+
+    /// Builds a call to `helper`, which has the signature `fn_type`. As BPF helpers are called by
+    /// ID rather than by symbol, the call is made through the ID cast to a function pointer, which
+    /// the BPF backend lowers to a helper call.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::AddressSpace;
+    /// use inkwell::bpf::{BpfHelper, BpfObjectBuilder, ProgramSection};
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("bpf");
+    /// let builder = context.create_builder();
+    /// let bpf_object = BpfObjectBuilder::new(&module, "pid.c");
+    /// let program = bpf_object.add_program("trace_open", ProgramSection::Kprobe("do_sys_open"));
+    /// let i64_type = context.i64_type();
+    ///
+    /// builder.position_at_end(context.append_basic_block(program, "entry"));
+    ///
+    /// let pid_tgid = bpf_object
+    ///     .build_helper_call(&builder, BpfHelper::GetCurrentPidTgid, i64_type.fn_type(&[], false), &[], "pid_tgid")
+    ///     .try_as_basic_value()
+    ///     .left()
+    ///     .unwrap()
+    ///     .into_int_value();
+    /// let pid = builder.build_right_shift(pid_tgid, i64_type.const_int(32, false), false, "pid");
+    ///
+    /// builder.build_return(Some(&builder.build_int_truncate(pid, context.i32_type(), "result")));
+    /// ```
+    pub fn build_helper_call(
+        &self,
+        builder: &Builder<'ctx>,
+        helper: BpfHelper,
+        fn_type: FunctionType<'ctx>,
+        args: &[BasicMetadataValueEnum<'ctx>],
+        name: &str,
+    ) -> CallSiteValue<'ctx> {
+        let helper_id = self.i64_type.const_int(helper.get_id() as u64, false);
+        let helper = helper_id.const_to_pointer(fn_type.ptr_type(AddressSpace::Generic));
+        let helper = CallableValue::try_from(helper).expect("A pointer to a function type is callable");
+
+        builder.build_call(helper, args, name)
+    }
+
+    /// Gets the compile unit describing the module, for adding further debug information.
+    pub fn get_compile_unit(&self) -> DICompileUnit<'ctx> {
+        self.compile_unit
+    }
+
+    /// Gets the `DebugInfoBuilder` used to describe the module.
+    pub fn get_debug_info_builder(&self) -> &DebugInfoBuilder<'ctx> {
+        &self.debug_info
+    }
+
+    /// Resolves the debug information added so far. This must be called before the module is
+    /// verified or compiled.
+    pub fn finalize(&self) {
+        self.debug_info.finalize();
+    }
+}
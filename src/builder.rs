@@ -18,11 +18,13 @@ use crate::values::StructValue;
 use crate::values::CallableValue;
 use crate::types::{AsTypeRef, BasicType, IntMathType, FloatMathType, PointerType, PointerMathType};
 
+use std::cell::Cell;
 use std::marker::PhantomData;
 
 #[derive(Debug)]
 pub struct Builder<'ctx> {
     builder: LLVMBuilderRef,
+    positioned_before_instruction: Cell<bool>,
     _marker: PhantomData<&'ctx ()>,
 }
 
@@ -32,6 +34,7 @@ impl<'ctx> Builder<'ctx> {
 
         Builder {
             builder,
+            positioned_before_instruction: Cell::new(false),
             _marker: PhantomData,
         }
     }
@@ -1690,18 +1693,24 @@ impl<'ctx> Builder<'ctx> {
     // REVIEW: What if instruction and basic_block are completely unrelated?
     // It'd be great if we could get the BB from the instruction behind the scenes
     pub fn position_at(&self, basic_block: BasicBlock<'ctx>, instruction: &InstructionValue<'ctx>) {
+        self.positioned_before_instruction.set(true);
+
         unsafe {
             LLVMPositionBuilder(self.builder, basic_block.basic_block, instruction.as_value_ref())
         }
     }
 
     pub fn position_before(&self, instruction: &InstructionValue<'ctx>) {
+        self.positioned_before_instruction.set(true);
+
         unsafe {
             LLVMPositionBuilderBefore(self.builder, instruction.as_value_ref())
         }
     }
 
     pub fn position_at_end(&self, basic_block: BasicBlock<'ctx>) {
+        self.positioned_before_instruction.set(false);
+
         unsafe {
             LLVMPositionBuilderAtEnd(self.builder, basic_block.basic_block);
         }
@@ -1982,6 +1991,8 @@ impl<'ctx> Builder<'ctx> {
     }
 
     pub fn clear_insertion_position(&self) {
+        self.positioned_before_instruction.set(false);
+
         unsafe {
             LLVMClearInsertionPosition(self.builder)
         }
@@ -2187,7 +2198,6 @@ impl<'ctx> Builder<'ctx> {
     #[llvm_versions(7.0..=latest)]
     pub fn set_current_debug_location(
         &self,
-        context: &'ctx crate::context::Context,
         location: DILocation<'ctx>,
     ) {
         use llvm_sys::core::LLVMMetadataAsValue;
@@ -2236,6 +2246,519 @@ impl<'ctx> Builder<'ctx> {
             );
         }
     }
+
+    /// Builds an if/else construct around `condition`. Each closure is called with the builder
+    /// positioned at the start of its branch and returns the values it wants to make available
+    /// after the construct. Those values are merged with phi instructions at the start of a new
+    /// block, at the end of which the builder is left positioned.
+    ///
+    /// A branch may terminate itself (ie by returning), in which case it doesn't branch to the merge
+    /// block and its values are ignored. If only one branch falls through, its values are returned
+    /// as-is and no phi instructions are created.
+    ///
+    /// Like the other control flow helpers, this returns an `Err` without building anything if the
+    /// builder isn't positioned at the end of an unterminated basic block inside a function.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::IntPredicate;
+    ///
+    /// // Builds a function which returns the greater of its two arguments:
+    /// let context = Context::create();
+    /// let module = context.create_module("max");
+    /// let builder = context.create_builder();
+    /// let i32_type = context.i32_type();
+    /// let fn_type = i32_type.fn_type(&[i32_type.into(), i32_type.into()], false);
+    /// let fn_value = module.add_function("max", fn_type, None);
+    /// let entry = context.append_basic_block(fn_value, "entry");
+    /// let lhs = fn_value.get_first_param().unwrap().into_int_value();
+    /// let rhs = fn_value.get_nth_param(1).unwrap().into_int_value();
+    ///
+    /// builder.position_at_end(entry);
+    ///
+    /// let cmp = builder.build_int_compare(IntPredicate::SGT, lhs, rhs, "cmp");
+    /// let merged = builder.build_if_then_else(cmp, |_| vec![lhs.into()], |_| vec![rhs.into()], "max").unwrap();
+    ///
+    /// builder.build_return(Some(&merged[0]));
+    /// ```
+    pub fn build_if_then_else<T, E>(
+        &self,
+        condition: IntValue<'ctx>,
+        then_fn: T,
+        else_fn: E,
+        name: &str,
+    ) -> Result<Vec<BasicValueEnum<'ctx>>, &'static str>
+    where
+        T: FnOnce(&Self) -> Vec<BasicValueEnum<'ctx>>,
+        E: FnOnce(&Self) -> Vec<BasicValueEnum<'ctx>>,
+    {
+        let current_block = self.get_current_block()?;
+        let function = current_block.get_parent().expect("The current block belongs to a function");
+        let existing_blocks = function.get_basic_blocks();
+        let then_block = self.insert_basic_block_after(current_block, &format!("{}.then", name));
+        let else_block = self.insert_basic_block_after(then_block, &format!("{}.else", name));
+        let merge_block = self.insert_basic_block_after(else_block, &format!("{}.end", name));
+
+        self.build_conditional_branch(condition, then_block, else_block);
+
+        self.position_at_end(then_block);
+
+        let then_values = then_fn(self);
+        let then_end = self.build_fallthrough_branch(merge_block);
+
+        self.position_at_end(else_block);
+
+        let else_values = else_fn(self);
+        let else_end = self.build_fallthrough_branch(merge_block);
+
+        self.position_at_end(merge_block);
+
+        match (then_end, else_end) {
+            (Some(then_end), Some(else_end)) => {
+                // The values' types are only known once both branches are built, so on a mismatch
+                // everything built since is removed again, leaving the function as it was
+                let error = if then_values.len() != else_values.len() {
+                    Some("Both branches must produce the same number of values.")
+                } else if then_values.iter().zip(&else_values).any(|(then_value, else_value)| then_value.get_type() != else_value.get_type()) {
+                    Some("Values merged from both branches must have the same types.")
+                } else {
+                    None
+                };
+
+                if let Some(error) = error {
+                    self.discard_blocks_since(current_block, &existing_blocks);
+
+                    return Err(error);
+                }
+
+                let merged = then_values.iter().zip(&else_values).map(|(then_value, else_value)| {
+                    let phi = self.build_phi(then_value.get_type(), name);
+
+                    phi.add_incoming(&[(then_value, then_end), (else_value, else_end)]);
+                    phi.as_basic_value()
+                }).collect();
+
+                Ok(merged)
+            },
+            (Some(_), None) => Ok(then_values),
+            (None, Some(_)) => Ok(else_values),
+            (None, None) => Ok(Vec::new()),
+        }
+    }
+
+    /// Builds an if construct around `condition` without an else branch. The closure is called with the
+    /// builder positioned at the start of the then branch, and the builder is left positioned at the end
+    /// of the block following the construct.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::AddressSpace;
+    ///
+    /// // Builds a function which stores 7 through its argument unless it is null:
+    /// let context = Context::create();
+    /// let module = context.create_module("store");
+    /// let builder = context.create_builder();
+    /// let void_type = context.void_type();
+    /// let i32_type = context.i32_type();
+    /// let i32_ptr_type = i32_type.ptr_type(AddressSpace::Generic);
+    /// let fn_type = void_type.fn_type(&[i32_ptr_type.into()], false);
+    /// let fn_value = module.add_function("store", fn_type, None);
+    /// let entry = context.append_basic_block(fn_value, "entry");
+    /// let ptr = fn_value.get_first_param().unwrap().into_pointer_value();
+    ///
+    /// builder.position_at_end(entry);
+    ///
+    /// let is_not_null = builder.build_is_not_null(ptr, "is_not_null");
+    ///
+    /// builder.build_if_then(is_not_null, |builder| {
+    ///     builder.build_store(ptr, i32_type.const_int(7, false));
+    /// }, "store").unwrap();
+    ///
+    /// builder.build_return(None);
+    /// ```
+    pub fn build_if_then<T>(
+        &self,
+        condition: IntValue<'ctx>,
+        then_fn: T,
+        name: &str,
+    ) -> Result<(), &'static str>
+    where
+        T: FnOnce(&Self),
+    {
+        let current_block = self.get_current_block()?;
+        let then_block = self.insert_basic_block_after(current_block, &format!("{}.then", name));
+        let merge_block = self.insert_basic_block_after(then_block, &format!("{}.end", name));
+
+        self.build_conditional_branch(condition, then_block, merge_block);
+
+        self.position_at_end(then_block);
+
+        then_fn(self);
+
+        self.build_fallthrough_branch(merge_block);
+        self.position_at_end(merge_block);
+
+        Ok(())
+    }
+
+    /// Builds a loop which runs until the body branches to the break block of the given `LoopBlocks`.
+    /// Branching to the continue block starts the next iteration, as does falling off the end of the body.
+    /// The builder is left positioned at the end of the block following the loop.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::IntPredicate;
+    ///
+    /// // Builds a function which counts down its argument to zero:
+    /// let context = Context::create();
+    /// let module = context.create_module("countdown");
+    /// let builder = context.create_builder();
+    /// let void_type = context.void_type();
+    /// let i32_type = context.i32_type();
+    /// let fn_type = void_type.fn_type(&[i32_type.into()], false);
+    /// let fn_value = module.add_function("countdown", fn_type, None);
+    /// let entry = context.append_basic_block(fn_value, "entry");
+    /// let arg = fn_value.get_first_param().unwrap();
+    ///
+    /// builder.position_at_end(entry);
+    ///
+    /// let counter = builder.build_alloca(i32_type, "counter");
+    ///
+    /// builder.build_store(counter, arg);
+    /// builder.build_loop(|builder, blocks| {
+    ///     let value = builder.build_load(counter, "value").into_int_value();
+    ///     let is_zero = builder.build_int_compare(IntPredicate::EQ, value, i32_type.const_zero(), "is_zero");
+    ///     let next = context.append_basic_block(fn_value, "next");
+    ///
+    ///     builder.build_conditional_branch(is_zero, blocks.get_break_block(), next);
+    ///     builder.position_at_end(next);
+    ///
+    ///     let decremented = builder.build_int_sub(value, i32_type.const_int(1, false), "decremented");
+    ///
+    ///     builder.build_store(counter, decremented);
+    /// }, "loop").unwrap();
+    ///
+    /// builder.build_return(None);
+    /// ```
+    pub fn build_loop<B>(&self, body_fn: B, name: &str) -> Result<(), &'static str>
+    where
+        B: FnOnce(&Self, LoopBlocks<'ctx>),
+    {
+        let current_block = self.get_current_block()?;
+        let body_block = self.insert_basic_block_after(current_block, &format!("{}.body", name));
+        let exit_block = self.insert_basic_block_after(body_block, &format!("{}.end", name));
+
+        self.build_unconditional_branch(body_block);
+
+        self.position_at_end(body_block);
+
+        body_fn(self, LoopBlocks::new(exit_block, body_block));
+
+        self.build_fallthrough_branch(body_block);
+        self.position_at_end(exit_block);
+
+        Ok(())
+    }
+
+    /// Builds a loop which evaluates the condition built by `cond_fn` before every iteration and runs
+    /// the body for as long as it is true. Branching to the continue block of the given `LoopBlocks`
+    /// re-evaluates the condition. The builder is left positioned at the end of the block following the loop.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::IntPredicate;
+    ///
+    /// // Builds a function which halves its argument until it is less than 10:
+    /// let context = Context::create();
+    /// let module = context.create_module("halve");
+    /// let builder = context.create_builder();
+    /// let i32_type = context.i32_type();
+    /// let fn_type = i32_type.fn_type(&[i32_type.into()], false);
+    /// let fn_value = module.add_function("halve", fn_type, None);
+    /// let entry = context.append_basic_block(fn_value, "entry");
+    /// let arg = fn_value.get_first_param().unwrap();
+    ///
+    /// builder.position_at_end(entry);
+    ///
+    /// let value = builder.build_alloca(i32_type, "value");
+    ///
+    /// builder.build_store(value, arg);
+    /// builder.build_while_loop(|builder| {
+    ///     let current = builder.build_load(value, "current").into_int_value();
+    ///
+    ///     builder.build_int_compare(IntPredicate::SGE, current, i32_type.const_int(10, false), "cmp")
+    /// }, |builder, _| {
+    ///     let current = builder.build_load(value, "current").into_int_value();
+    ///     let halved = builder.build_int_signed_div(current, i32_type.const_int(2, false), "halved");
+    ///
+    ///     builder.build_store(value, halved);
+    /// }, "while").unwrap();
+    ///
+    /// let result = builder.build_load(value, "result");
+    ///
+    /// builder.build_return(Some(&result));
+    /// ```
+    pub fn build_while_loop<C, B>(
+        &self,
+        cond_fn: C,
+        body_fn: B,
+        name: &str,
+    ) -> Result<(), &'static str>
+    where
+        C: FnOnce(&Self) -> IntValue<'ctx>,
+        B: FnOnce(&Self, LoopBlocks<'ctx>),
+    {
+        let current_block = self.get_current_block()?;
+        let cond_block = self.insert_basic_block_after(current_block, &format!("{}.cond", name));
+        let body_block = self.insert_basic_block_after(cond_block, &format!("{}.body", name));
+        let exit_block = self.insert_basic_block_after(body_block, &format!("{}.end", name));
+
+        self.build_unconditional_branch(cond_block);
+
+        self.position_at_end(cond_block);
+
+        let condition = cond_fn(self);
+
+        self.build_conditional_branch(condition, body_block, exit_block);
+
+        self.position_at_end(body_block);
+
+        body_fn(self, LoopBlocks::new(exit_block, cond_block));
+
+        self.build_fallthrough_branch(cond_block);
+        self.position_at_end(exit_block);
+
+        Ok(())
+    }
+
+    /// Builds a loop over an induction variable which starts at `start` and is incremented by `step`
+    /// after every iteration for as long as it is (signed) less than `end`. The body is called with the
+    /// induction variable, and branching to the continue block of the given `LoopBlocks` increments it
+    /// and starts the next iteration. The builder is left positioned at the end of the block following the loop.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    ///
+    /// // Builds a function which sums the integers from 0 up to (but not including) its argument:
+    /// let context = Context::create();
+    /// let module = context.create_module("sum");
+    /// let builder = context.create_builder();
+    /// let i32_type = context.i32_type();
+    /// let fn_type = i32_type.fn_type(&[i32_type.into()], false);
+    /// let fn_value = module.add_function("sum", fn_type, None);
+    /// let entry = context.append_basic_block(fn_value, "entry");
+    /// let end = fn_value.get_first_param().unwrap().into_int_value();
+    ///
+    /// builder.position_at_end(entry);
+    ///
+    /// let sum = builder.build_alloca(i32_type, "sum");
+    /// let zero = i32_type.const_zero();
+    /// let one = i32_type.const_int(1, false);
+    ///
+    /// builder.build_store(sum, zero);
+    /// builder.build_counted_loop(zero, end, one, |builder, index, _| {
+    ///     let current = builder.build_load(sum, "current").into_int_value();
+    ///     let next = builder.build_int_add(current, index, "next");
+    ///
+    ///     builder.build_store(sum, next);
+    /// }, "for").unwrap();
+    ///
+    /// let result = builder.build_load(sum, "result");
+    ///
+    /// builder.build_return(Some(&result));
+    /// ```
+    pub fn build_counted_loop<B>(
+        &self,
+        start: IntValue<'ctx>,
+        end: IntValue<'ctx>,
+        step: IntValue<'ctx>,
+        body_fn: B,
+        name: &str,
+    ) -> Result<(), &'static str>
+    where
+        B: FnOnce(&Self, IntValue<'ctx>, LoopBlocks<'ctx>),
+    {
+        if start.get_type() != end.get_type() || start.get_type() != step.get_type() {
+            return Err("The start, end and step values must have the same type.");
+        }
+
+        let preheader_block = self.get_current_block()?;
+        let cond_block = self.insert_basic_block_after(preheader_block, &format!("{}.cond", name));
+        let body_block = self.insert_basic_block_after(cond_block, &format!("{}.body", name));
+        let latch_block = self.insert_basic_block_after(body_block, &format!("{}.inc", name));
+        let exit_block = self.insert_basic_block_after(latch_block, &format!("{}.end", name));
+
+        self.build_unconditional_branch(cond_block);
+
+        self.position_at_end(cond_block);
+
+        let phi = self.build_phi(start.get_type(), &format!("{}.index", name));
+
+        phi.add_incoming(&[(&start, preheader_block)]);
+
+        let index = phi.as_basic_value().into_int_value();
+        let condition = self.build_int_compare(IntPredicate::SLT, index, end, &format!("{}.cmp", name));
+
+        self.build_conditional_branch(condition, body_block, exit_block);
+
+        self.position_at_end(body_block);
+
+        body_fn(self, index, LoopBlocks::new(exit_block, latch_block));
+
+        self.build_fallthrough_branch(latch_block);
+        self.position_at_end(latch_block);
+
+        let next_index = self.build_int_add(index, step, &format!("{}.next", name));
+
+        phi.add_incoming(&[(&next_index, latch_block)]);
+
+        self.build_unconditional_branch(cond_block);
+        self.position_at_end(exit_block);
+
+        Ok(())
+    }
+
+    /// Gets the block the builder is positioned in, as long as it belongs to a function and the builder
+    /// is positioned at its end, before it has been terminated.
+    fn get_current_block(&self) -> Result<BasicBlock<'ctx>, &'static str> {
+        let current_block = self.get_insert_block().ok_or("The builder must be positioned inside a basic block.")?;
+
+        if current_block.get_parent().is_none() {
+            return Err("The builder's current basic block must belong to a function.");
+        }
+
+        if self.positioned_before_instruction.get() {
+            return Err("The builder must be positioned at the end of its current basic block.");
+        }
+
+        if current_block.get_terminator().is_some() {
+            return Err("The builder's current basic block must not already have a terminator.");
+        }
+
+        Ok(current_block)
+    }
+
+    /// Inserts a new block directly after `basic_block`, in its context and function.
+    fn insert_basic_block_after(&self, basic_block: BasicBlock<'ctx>, name: &str) -> BasicBlock<'ctx> {
+        use llvm_sys::core::{LLVMAppendBasicBlockInContext, LLVMMoveBasicBlockAfter};
+
+        let context = basic_block.get_context();
+        let function = basic_block.get_parent().expect("The current block belongs to a function");
+        let c_string = to_c_str(name);
+
+        unsafe {
+            let new_block = LLVMAppendBasicBlockInContext(context.context, function.as_value_ref(), c_string.as_ptr());
+
+            LLVMMoveBasicBlockAfter(new_block, basic_block.basic_block);
+
+            BasicBlock::new(new_block).expect("Appending basic block should never fail")
+        }
+    }
+
+    /// Removes the blocks added to `current_block`'s function since it consisted of `existing_blocks`,
+    /// along with the terminator added to `current_block`, and positions the builder at its end.
+    fn discard_blocks_since(&self, current_block: BasicBlock<'ctx>, existing_blocks: &[BasicBlock<'ctx>]) {
+        use llvm_sys::core::{LLVMGetTypeKind, LLVMGetUndef, LLVMReplaceAllUsesWith, LLVMTypeOf};
+        use llvm_sys::LLVMTypeKind;
+
+        let function = current_block.get_parent().expect("The current block belongs to a function");
+        let added_blocks: Vec<_> = function
+            .get_basic_blocks()
+            .into_iter()
+            .filter(|block| !existing_blocks.contains(block))
+            .collect();
+
+        if let Some(terminator) = current_block.get_terminator() {
+            terminator.erase_from_basic_block();
+        }
+
+        // Instructions may be used across the added blocks, so all uses are dropped before any
+        // instruction is erased, and all instructions are erased before any block is deleted
+        let mut instructions = Vec::new();
+
+        for block in &added_blocks {
+            let mut instruction = block.get_first_instruction();
+
+            while let Some(current) = instruction {
+                instruction = current.get_next_instruction();
+                instructions.push(current);
+            }
+        }
+
+        for instruction in &instructions {
+            unsafe {
+                let value = instruction.as_value_ref();
+                let type_ = LLVMTypeOf(value);
+
+                if LLVMGetTypeKind(type_) != LLVMTypeKind::LLVMVoidTypeKind {
+                    LLVMReplaceAllUsesWith(value, LLVMGetUndef(type_));
+                }
+            }
+        }
+
+        for instruction in instructions {
+            instruction.erase_from_basic_block();
+        }
+
+        for block in added_blocks {
+            unsafe {
+                block.delete().expect("Added blocks belong to the function");
+            }
+        }
+
+        self.position_at_end(current_block);
+    }
+
+    /// Branches to `destination` unless the current block is already terminated. Returns the
+    /// block which the branch was inserted into, if any.
+    fn build_fallthrough_branch(&self, destination: BasicBlock<'ctx>) -> Option<BasicBlock<'ctx>> {
+        let current_block = self.get_insert_block()?;
+
+        if current_block.get_terminator().is_some() {
+            return None;
+        }
+
+        self.build_unconditional_branch(destination);
+
+        Some(current_block)
+    }
+}
+
+/// The blocks which the body of a loop built by a `Builder` may branch to in order to
+/// leave the loop (break) or to start its next iteration (continue).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct LoopBlocks<'ctx> {
+    break_block: BasicBlock<'ctx>,
+    continue_block: BasicBlock<'ctx>,
+}
+
+impl<'ctx> LoopBlocks<'ctx> {
+    fn new(break_block: BasicBlock<'ctx>, continue_block: BasicBlock<'ctx>) -> Self {
+        LoopBlocks {
+            break_block,
+            continue_block,
+        }
+    }
+
+    /// Gets the block following the loop. Branching to it exits the loop.
+    pub fn get_break_block(self) -> BasicBlock<'ctx> {
+        self.break_block
+    }
+
+    /// Gets the block which starts the next iteration of the loop.
+    pub fn get_continue_block(self) -> BasicBlock<'ctx> {
+        self.continue_block
+    }
 }
 
 /// Used by build_memcpy and build_memmove
@@ -0,0 +1,452 @@
+//! A `CallGraph` describes which functions of a `Module` call one another.
+
+use llvm_sys::core::{LLVMGetCalledValue, LLVMGetConstOpcode, LLVMGetFirstUse, LLVMGetNextUse, LLVMGetNumOperands, LLVMGetOperand, LLVMGetOperandUse, LLVMGetUser, LLVMIsACallInst, LLVMIsAConstantExpr, LLVMIsAFunction, LLVMIsAInlineAsm, LLVMIsAInvokeInst};
+use llvm_sys::LLVMOpcode;
+use llvm_sys::prelude::LLVMValueRef;
+
+use crate::module::{Linkage, Module};
+use crate::support::escape_dot_string;
+use crate::values::{AsValueRef, FunctionValue, InstructionOpcode};
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+
+/// A `CallGraph` is a snapshot of the direct calls between the functions of a `Module`.
+///
+/// Calls are discovered by scanning every call and invoke instruction for its called value. Uses
+/// of a function other than as the callee of a call (ie storing it, passing it as an argument or
+/// referencing it from a global initializer) mark the function as address taken, since it may then
+/// be called from anywhere. Calls through function pointers are recorded as indirect calls.
+///
+/// The graph is not updated when the `Module` is modified; a new one should be created instead.
+#[derive(Debug)]
+pub struct CallGraph<'ctx> {
+    name: String,
+    functions: Vec<FunctionValue<'ctx>>,
+    indices: HashMap<FunctionValue<'ctx>, usize>,
+    callees: Vec<Vec<usize>>,
+    callers: Vec<Vec<usize>>,
+    address_taken: Vec<bool>,
+    indirect_calls: Vec<bool>,
+}
+
+impl<'ctx> CallGraph<'ctx> {
+    /// Creates a `CallGraph` of all the functions in a `Module`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::call_graph::CallGraph;
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("my_module");
+    /// let builder = context.create_builder();
+    /// let void_type = context.void_type();
+    /// let fn_type = void_type.fn_type(&[], false);
+    /// let callee = module.add_function("callee", fn_type, None);
+    /// let caller = module.add_function("caller", fn_type, None);
+    ///
+    /// builder.position_at_end(context.append_basic_block(callee, "entry"));
+    /// builder.build_return(None);
+    /// builder.position_at_end(context.append_basic_block(caller, "entry"));
+    /// builder.build_call(callee, &[], "call");
+    /// builder.build_return(None);
+    ///
+    /// let call_graph = CallGraph::new(&module);
+    ///
+    /// assert_eq!(call_graph.get_callees(caller), vec![callee]);
+    /// assert_eq!(call_graph.get_callers(callee), vec![caller]);
+    /// ```
+    pub fn new(module: &Module<'ctx>) -> Self {
+        let mut functions = Vec::new();
+        let mut function = module.get_first_function();
+
+        while let Some(current_function) = function {
+            functions.push(current_function);
+            function = current_function.get_next_function();
+        }
+
+        let indices: HashMap<_, _> = functions.iter().enumerate().map(|(index, &function)| (function, index)).collect();
+        let mut callees = vec![Vec::new(); functions.len()];
+        let mut callers = vec![Vec::new(); functions.len()];
+        let mut indirect_calls = vec![false; functions.len()];
+
+        for (caller_index, function) in functions.iter().enumerate() {
+            let mut basic_block = function.get_first_basic_block();
+
+            while let Some(current_block) = basic_block {
+                let mut instruction = current_block.get_first_instruction();
+
+                while let Some(current_instruction) = instruction {
+                    let opcode = current_instruction.get_opcode();
+
+                    if opcode == InstructionOpcode::Call || opcode == InstructionOpcode::Invoke {
+                        match unsafe { resolve_called_function(current_instruction.as_value_ref()) } {
+                            CalledValue::Function(callee) => {
+                                let callee_index = indices[&callee];
+
+                                if !callees[caller_index].contains(&callee_index) {
+                                    callees[caller_index].push(callee_index);
+                                    callers[callee_index].push(caller_index);
+                                }
+                            },
+                            CalledValue::Indirect => indirect_calls[caller_index] = true,
+                            CalledValue::InlineAsm => (),
+                        }
+                    }
+
+                    instruction = current_instruction.get_next_instruction();
+                }
+
+                basic_block = current_block.get_next_basic_block();
+            }
+        }
+
+        let address_taken = functions.iter().map(|function| unsafe { is_address_taken(function.as_value_ref()) }).collect();
+
+        CallGraph {
+            name: module.get_name().to_string_lossy().into_owned(),
+            functions,
+            indices,
+            callees,
+            callers,
+            address_taken,
+            indirect_calls,
+        }
+    }
+
+    /// Gets all functions of the graph, in the order they appear in the `Module`.
+    pub fn get_functions(&self) -> &[FunctionValue<'ctx>] {
+        &self.functions
+    }
+
+    /// Gets the functions directly called by `function`, in the order they are first called.
+    /// Returns an empty `Vec` if `function` isn't part of the graph.
+    pub fn get_callees(&self, function: FunctionValue<'ctx>) -> Vec<FunctionValue<'ctx>> {
+        self.indices.get(&function).map_or_else(Vec::new, |&index| self.to_functions(&self.callees[index]))
+    }
+
+    /// Gets the functions which directly call `function`. Returns an empty `Vec` if `function`
+    /// isn't part of the graph.
+    pub fn get_callers(&self, function: FunctionValue<'ctx>) -> Vec<FunctionValue<'ctx>> {
+        self.indices.get(&function).map_or_else(Vec::new, |&index| self.to_functions(&self.callers[index]))
+    }
+
+    /// Determines whether `function` is used other than as the callee of a call, such that it
+    /// may be called indirectly.
+    pub fn is_address_taken(&self, function: FunctionValue<'ctx>) -> bool {
+        self.indices.get(&function).map_or(false, |&index| self.address_taken[index])
+    }
+
+    /// Determines whether `function` contains a call through a function pointer.
+    pub fn has_indirect_calls(&self, function: FunctionValue<'ctx>) -> bool {
+        self.indices.get(&function).map_or(false, |&index| self.indirect_calls[index])
+    }
+
+    /// Determines whether `function` may directly or transitively call itself.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::call_graph::CallGraph;
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("my_module");
+    /// let builder = context.create_builder();
+    /// let void_type = context.void_type();
+    /// let fn_type = void_type.fn_type(&[], false);
+    /// let function = module.add_function("forever", fn_type, None);
+    ///
+    /// builder.position_at_end(context.append_basic_block(function, "entry"));
+    /// builder.build_call(function, &[], "call");
+    /// builder.build_return(None);
+    ///
+    /// assert!(CallGraph::new(&module).is_recursive(function));
+    /// ```
+    pub fn is_recursive(&self, function: FunctionValue<'ctx>) -> bool {
+        let index = match self.indices.get(&function) {
+            Some(&index) => index,
+            None => return false,
+        };
+
+        if self.callees[index].contains(&index) {
+            return true;
+        }
+
+        self.get_scc_indices()
+            .iter()
+            .any(|scc| scc.len() > 1 && scc.contains(&index))
+    }
+
+    /// Gets the functions which may be called from outside of the `Module`: those with a definition
+    /// which either have a non local linkage or have their address taken.
+    pub fn get_entry_points(&self) -> Vec<FunctionValue<'ctx>> {
+        let indices: Vec<_> = (0..self.functions.len()).filter(|&index| self.is_entry_point(index)).collect();
+
+        self.to_functions(&indices)
+    }
+
+    /// Gets the functions with a definition which cannot be reached through direct calls from any
+    /// entry point (see `get_entry_points`). These are safe to delete.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::call_graph::CallGraph;
+    /// use inkwell::context::Context;
+    /// use inkwell::module::Linkage;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("my_module");
+    /// let builder = context.create_builder();
+    /// let void_type = context.void_type();
+    /// let fn_type = void_type.fn_type(&[], false);
+    /// let unused = module.add_function("unused", fn_type, Some(Linkage::Internal));
+    ///
+    /// builder.position_at_end(context.append_basic_block(unused, "entry"));
+    /// builder.build_return(None);
+    ///
+    /// assert_eq!(CallGraph::new(&module).get_unreachable_functions(), vec![unused]);
+    /// ```
+    pub fn get_unreachable_functions(&self) -> Vec<FunctionValue<'ctx>> {
+        let mut reachable = vec![false; self.functions.len()];
+        let mut queue: VecDeque<_> = (0..self.functions.len()).filter(|&index| self.is_entry_point(index)).collect();
+
+        for &index in &queue {
+            reachable[index] = true;
+        }
+
+        while let Some(index) = queue.pop_front() {
+            for &callee in &self.callees[index] {
+                if !reachable[callee] {
+                    reachable[callee] = true;
+                    queue.push_back(callee);
+                }
+            }
+        }
+
+        let indices: Vec<_> = (0..self.functions.len())
+            .filter(|&index| !reachable[index] && self.has_definition(index))
+            .collect();
+
+        self.to_functions(&indices)
+    }
+
+    /// Gets the strongly connected components of the graph in bottom-up order, meaning that every
+    /// component comes after all of the components it calls into. A component with more than one
+    /// function is a cycle of mutually recursive functions. Functions within a component are in
+    /// `Module` order.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::call_graph::CallGraph;
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("my_module");
+    /// let builder = context.create_builder();
+    /// let void_type = context.void_type();
+    /// let fn_type = void_type.fn_type(&[], false);
+    /// let leaf = module.add_function("leaf", fn_type, None);
+    /// let root = module.add_function("root", fn_type, None);
+    ///
+    /// builder.position_at_end(context.append_basic_block(root, "entry"));
+    /// builder.build_call(leaf, &[], "call");
+    /// builder.build_return(None);
+    ///
+    /// assert_eq!(CallGraph::new(&module).get_sccs(), vec![vec![leaf], vec![root]]);
+    /// ```
+    pub fn get_sccs(&self) -> Vec<Vec<FunctionValue<'ctx>>> {
+        self.get_scc_indices()
+            .iter()
+            .map(|scc| self.to_functions(scc))
+            .collect()
+    }
+
+    /// Prints the graph in the DOT (graphviz) format. Declarations are drawn with dashed
+    /// borders and address taken functions with bold borders.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph \"Call graph for '{}'\" {{", escape_dot_string(&self.name)).unwrap();
+        writeln!(dot, "    node [shape=box];").unwrap();
+
+        for (index, function) in self.functions.iter().enumerate() {
+            let name = function.get_name().to_string_lossy();
+            let mut styles = Vec::new();
+
+            if !self.has_definition(index) {
+                styles.push("dashed");
+            }
+
+            if self.address_taken[index] {
+                styles.push("bold");
+            }
+
+            if styles.is_empty() {
+                writeln!(dot, "    f{} [label=\"{}\"];", index, escape_dot_string(&name)).unwrap();
+            } else {
+                writeln!(dot, "    f{} [label=\"{}\", style=\"{}\"];", index, escape_dot_string(&name), styles.join(",")).unwrap();
+            }
+        }
+
+        for (caller, callees) in self.callees.iter().enumerate() {
+            for callee in callees {
+                writeln!(dot, "    f{} -> f{};", caller, callee).unwrap();
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn to_functions(&self, indices: &[usize]) -> Vec<FunctionValue<'ctx>> {
+        indices.iter().map(|&index| self.functions[index]).collect()
+    }
+
+    fn has_definition(&self, index: usize) -> bool {
+        self.functions[index].count_basic_blocks() > 0
+    }
+
+    fn is_entry_point(&self, index: usize) -> bool {
+        if !self.has_definition(index) {
+            return false;
+        }
+
+        let is_local = matches!(self.functions[index].get_linkage(), Linkage::Internal | Linkage::Private);
+
+        !is_local || self.address_taken[index]
+    }
+
+    // Tarjan's algorithm, which yields components in reverse topological order. It is
+    // written iteratively so that deep call chains can't overflow the stack.
+    fn get_scc_indices(&self) -> Vec<Vec<usize>> {
+        const UNVISITED: usize = usize::MAX;
+
+        let len = self.functions.len();
+        let mut order = vec![UNVISITED; len];
+        let mut low_link = vec![0; len];
+        let mut on_stack = vec![false; len];
+        let mut stack = Vec::new();
+        let mut sccs = Vec::new();
+        let mut next_order = 0;
+        let mut work = Vec::new();
+
+        for root in 0..len {
+            if order[root] != UNVISITED {
+                continue;
+            }
+
+            work.push((root, 0));
+
+            while let Some((node, edge)) = work.pop() {
+                if edge == 0 {
+                    order[node] = next_order;
+                    low_link[node] = next_order;
+                    next_order += 1;
+                    stack.push(node);
+                    on_stack[node] = true;
+                }
+
+                if let Some(&callee) = self.callees[node].get(edge) {
+                    work.push((node, edge + 1));
+
+                    if order[callee] == UNVISITED {
+                        work.push((callee, 0));
+                    } else if on_stack[callee] {
+                        low_link[node] = low_link[node].min(order[callee]);
+                    }
+
+                    continue;
+                }
+
+                if low_link[node] == order[node] {
+                    let mut scc = Vec::new();
+
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        scc.push(member);
+
+                        if member == node {
+                            break;
+                        }
+                    }
+
+                    scc.sort_unstable();
+                    sccs.push(scc);
+                }
+
+                if let Some(&(parent, _)) = work.last() {
+                    low_link[parent] = low_link[parent].min(low_link[node]);
+                }
+            }
+        }
+
+        sccs
+    }
+}
+
+enum CalledValue<'ctx> {
+    Function(FunctionValue<'ctx>),
+    Indirect,
+    InlineAsm,
+}
+
+/// Finds the function called by a call or invoke instruction, looking through pointer casts of the callee.
+unsafe fn resolve_called_function<'ctx>(call: LLVMValueRef) -> CalledValue<'ctx> {
+    let called_value = strip_pointer_casts(LLVMGetCalledValue(call));
+
+    if !LLVMIsAInlineAsm(called_value).is_null() {
+        return CalledValue::InlineAsm;
+    }
+
+    if LLVMIsAFunction(called_value).is_null() {
+        return CalledValue::Indirect;
+    }
+
+    CalledValue::Function(FunctionValue::new(called_value).expect("Function should not be null"))
+}
+
+/// Determines whether a function has any use other than being the callee of a call or invoke,
+/// looking through pointer casts of the function in the same way as `resolve_called_function`.
+unsafe fn is_address_taken(value: LLVMValueRef) -> bool {
+    let mut use_ = LLVMGetFirstUse(value);
+
+    while !use_.is_null() {
+        let user = LLVMGetUser(use_);
+
+        if is_pointer_cast(user) {
+            if is_address_taken(user) {
+                return true;
+            }
+        } else {
+            let is_call = !LLVMIsACallInst(user).is_null() || !LLVMIsAInvokeInst(user).is_null();
+
+            // The callee is always the last operand of a call or invoke
+            if !is_call || LLVMGetOperandUse(user, LLVMGetNumOperands(user) as u32 - 1) != use_ {
+                return true;
+            }
+        }
+
+        use_ = LLVMGetNextUse(use_);
+    }
+
+    false
+}
+
+/// Strips any constant bitcasts and address space casts from `value`.
+unsafe fn strip_pointer_casts(mut value: LLVMValueRef) -> LLVMValueRef {
+    while is_pointer_cast(value) {
+        value = LLVMGetOperand(value, 0);
+    }
+
+    value
+}
+
+unsafe fn is_pointer_cast(value: LLVMValueRef) -> bool {
+    !LLVMIsAConstantExpr(value).is_null()
+        && matches!(LLVMGetConstOpcode(value), LLVMOpcode::LLVMBitCast | LLVMOpcode::LLVMAddrSpaceCast)
+}
@@ -2,6 +2,7 @@ use std::ffi::CStr;
 use std::fmt;
 
 use crate::support::{LLVMString, LLVMStringOrRaw};
+use crate::targets::ByteOrdering;
 
 #[derive(Eq)]
 pub struct DataLayout {
@@ -51,3 +52,647 @@ impl fmt::Debug for DataLayout {
             .finish()
     }
 }
+
+impl DataLayout {
+    /// Creates a `DataLayout` from its string representation, which isn't validated. See
+    /// `DataLayoutSpec` to build one from its settings instead.
+    pub fn create(data_layout: &str) -> DataLayout {
+        DataLayout {
+            data_layout: LLVMStringOrRaw::Owned(LLVMString::create_from_str(data_layout)),
+        }
+    }
+
+    /// Parses this `DataLayout` into its settings.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::targets::{ByteOrdering, TargetData};
+    ///
+    /// let target_data = TargetData::create("e-m:e-p:32:32-i64:64-n32:64-S128");
+    /// let spec = target_data.get_data_layout().get_spec().unwrap();
+    ///
+    /// assert_eq!(spec.get_byte_ordering(), ByteOrdering::LittleEndian);
+    /// assert_eq!(spec.get_pointer_layout(0).get_size(), 32);
+    /// assert_eq!(spec.get_native_integer_widths(), &[32, 64]);
+    /// assert_eq!(spec.get_stack_alignment(), Some(128));
+    /// ```
+    pub fn get_spec(&self) -> Result<DataLayoutSpec, &'static str> {
+        let data_layout = self.as_str().to_str().map_err(|_| "Data layout is not valid UTF-8")?;
+
+        DataLayoutSpec::parse(data_layout)
+    }
+}
+
+/// How symbol names are mangled in object files.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Mangling {
+    /// ELF mangling, where private symbols get a ".L" prefix.
+    Elf,
+    /// GOFF mangling, where private symbols get an "@" prefix.
+    Goff,
+    /// MIPS mangling, where private symbols get a "$" prefix.
+    Mips,
+    /// Mach-O mangling, where private symbols get an "L" prefix and other symbols an "_" prefix.
+    MachO,
+    /// Windows x86 COFF mangling, where symbols get an "_" prefix and calling convention suffixes.
+    WindowsX86Coff,
+    /// Windows COFF mangling, as for other architectures than x86.
+    WindowsCoff,
+    /// XCOFF mangling, where private symbols get an "L.." prefix.
+    XCoff,
+}
+
+impl Mangling {
+    fn get_code(self) -> char {
+        match self {
+            Mangling::Elf => 'e',
+            Mangling::Goff => 'l',
+            Mangling::Mips => 'm',
+            Mangling::MachO => 'o',
+            Mangling::WindowsX86Coff => 'x',
+            Mangling::WindowsCoff => 'w',
+            Mangling::XCoff => 'a',
+        }
+    }
+}
+
+/// An ABI alignment and an optional preferred alignment, both in bits.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Alignment {
+    abi: u32,
+    preferred: Option<u32>,
+}
+
+impl Alignment {
+    /// Creates an `Alignment` whose preferred alignment is its ABI alignment.
+    pub fn new(abi: u32) -> Self {
+        Alignment {
+            abi,
+            preferred: None,
+        }
+    }
+
+    /// Sets a preferred alignment distinct from the ABI alignment.
+    pub fn with_preferred(self, preferred: u32) -> Self {
+        Alignment {
+            preferred: Some(preferred),
+            ..self
+        }
+    }
+
+    pub fn get_abi(&self) -> u32 {
+        self.abi
+    }
+
+    pub fn get_preferred(&self) -> u32 {
+        self.preferred.unwrap_or(self.abi)
+    }
+}
+
+/// The size and alignment, in bits, of pointers in an address space.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct PointerLayout {
+    size: u32,
+    alignment: Alignment,
+    index_size: Option<u32>,
+}
+
+impl PointerLayout {
+    /// Creates a `PointerLayout` whose index size is its size.
+    pub fn new(size: u32, alignment: Alignment) -> Self {
+        PointerLayout {
+            size,
+            alignment,
+            index_size: None,
+        }
+    }
+
+    /// Sets the size of the integers used for address calculations, such as by GEPs, distinct from the pointer size.
+    pub fn with_index_size(self, index_size: u32) -> Self {
+        PointerLayout {
+            index_size: Some(index_size),
+            ..self
+        }
+    }
+
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn get_alignment(&self) -> Alignment {
+        self.alignment
+    }
+
+    pub fn get_index_size(&self) -> u32 {
+        self.index_size.unwrap_or(self.size)
+    }
+}
+
+/// How function pointers are aligned.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum FunctionPointerAlignment {
+    /// Function pointers are aligned to the given number of bits regardless of the functions' own alignment.
+    Independent(u32),
+    /// Function pointers are aligned to a multiple of the functions' own alignment, which is given in bits.
+    MultipleOfFunctionAlignment(u32),
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum AlignmentKind {
+    Integer,
+    Float,
+    Vector,
+    Aggregate,
+    // Stack objects, in LLVM versions before 4.0
+    Stack,
+}
+
+impl AlignmentKind {
+    fn get_code(self) -> char {
+        match self {
+            AlignmentKind::Integer => 'i',
+            AlignmentKind::Float => 'f',
+            AlignmentKind::Vector => 'v',
+            AlignmentKind::Aggregate => 'a',
+            AlignmentKind::Stack => 's',
+        }
+    }
+}
+
+// Only sizes which have an entry by default are listed
+const DEFAULT_ALIGNMENTS: &[(AlignmentKind, u32, u32, u32)] = &[
+    (AlignmentKind::Integer, 1, 8, 8),
+    (AlignmentKind::Integer, 8, 8, 8),
+    (AlignmentKind::Integer, 16, 16, 16),
+    (AlignmentKind::Integer, 32, 32, 32),
+    (AlignmentKind::Integer, 64, 32, 64),
+    (AlignmentKind::Float, 16, 16, 16),
+    (AlignmentKind::Float, 32, 32, 32),
+    (AlignmentKind::Float, 64, 64, 64),
+    (AlignmentKind::Float, 128, 128, 128),
+    (AlignmentKind::Vector, 64, 64, 64),
+    (AlignmentKind::Vector, 128, 128, 128),
+];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Specification {
+    ByteOrdering(ByteOrdering),
+    Mangling(Mangling),
+    StackAlignment(u32),
+    ProgramAddressSpace(u32),
+    AllocaAddressSpace(u32),
+    GlobalsAddressSpace(u32),
+    Pointer(u32, PointerLayout),
+    // Aggregate alignments may be written with a size of 0, or none at all
+    Alignment(AlignmentKind, Option<u32>, Alignment),
+    FunctionPointerAlignment(FunctionPointerAlignment),
+    NativeIntegerWidths(Vec<u32>),
+    NonIntegralAddressSpaces(Vec<u32>),
+}
+
+impl Specification {
+    // Determines whether the two specifications set the same thing, so that one replaces the other
+    fn overrides(&self, other: &Specification) -> bool {
+        use self::Specification::*;
+
+        match (self, other) {
+            (Pointer(address_space, _), Pointer(other_address_space, _)) => address_space == other_address_space,
+            (Alignment(kind, size, _), Alignment(other_kind, other_size, _)) => {
+                kind == other_kind && size.unwrap_or(0) == other_size.unwrap_or(0)
+            },
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+
+    fn parse(specification: &str) -> Result<Self, &'static str> {
+        fn parse_number(number: &str) -> Result<u32, &'static str> {
+            number.parse().map_err(|_| "Data layout contains an invalid number")
+        }
+
+        fn parse_numbers(numbers: &str) -> Result<Vec<u32>, &'static str> {
+            numbers.split(':').map(parse_number).collect()
+        }
+
+        fn parse_alignment(abi: &str, preferred: Option<&str>) -> Result<Alignment, &'static str> {
+            Ok(Alignment {
+                abi: parse_number(abi)?,
+                preferred: preferred.map(parse_number).transpose()?,
+            })
+        }
+
+        if let Some(address_spaces) = specification.strip_prefix("ni:") {
+            return Ok(Specification::NonIntegralAddressSpaces(parse_numbers(address_spaces)?));
+        }
+
+        let mut chars = specification.chars();
+        let code = chars.next().ok_or("Data layout contains an empty specification")?;
+        let rest = chars.as_str();
+
+        let specification = match code {
+            'e' if rest.is_empty() => Specification::ByteOrdering(ByteOrdering::LittleEndian),
+            'E' if rest.is_empty() => Specification::ByteOrdering(ByteOrdering::BigEndian),
+            'm' => {
+                let mangling = match rest {
+                    ":e" => Mangling::Elf,
+                    ":l" => Mangling::Goff,
+                    ":m" => Mangling::Mips,
+                    ":o" => Mangling::MachO,
+                    ":x" => Mangling::WindowsX86Coff,
+                    ":w" => Mangling::WindowsCoff,
+                    ":a" => Mangling::XCoff,
+                    _ => return Err("Data layout contains an unknown mangling mode"),
+                };
+
+                Specification::Mangling(mangling)
+            },
+            'S' => Specification::StackAlignment(parse_number(rest)?),
+            'P' => Specification::ProgramAddressSpace(parse_number(rest)?),
+            'A' => Specification::AllocaAddressSpace(parse_number(rest)?),
+            'G' => Specification::GlobalsAddressSpace(parse_number(rest)?),
+            'n' => Specification::NativeIntegerWidths(parse_numbers(rest)?),
+            'F' => {
+                let mut chars = rest.chars();
+                let kind = chars.next();
+                let alignment = parse_number(chars.as_str())?;
+
+                match kind {
+                    Some('i') => Specification::FunctionPointerAlignment(FunctionPointerAlignment::Independent(alignment)),
+                    Some('n') => Specification::FunctionPointerAlignment(FunctionPointerAlignment::MultipleOfFunctionAlignment(alignment)),
+                    _ => return Err("Data layout contains an unknown function pointer alignment"),
+                }
+            },
+            'p' => {
+                let fields: Vec<&str> = rest.split(':').collect();
+                let address_space = match fields[0] {
+                    "" => 0,
+                    address_space => parse_number(address_space)?,
+                };
+
+                if fields.len() < 3 || fields.len() > 5 {
+                    return Err("Data layout contains an invalid pointer specification");
+                }
+
+                let pointer_layout = PointerLayout {
+                    size: parse_number(fields[1])?,
+                    alignment: parse_alignment(fields[2], fields.get(3).copied())?,
+                    index_size: fields.get(4).copied().map(parse_number).transpose()?,
+                };
+
+                Specification::Pointer(address_space, pointer_layout)
+            },
+            'i' | 'f' | 'v' | 'a' | 's' => {
+                let kind = match code {
+                    'i' => AlignmentKind::Integer,
+                    'f' => AlignmentKind::Float,
+                    'v' => AlignmentKind::Vector,
+                    'a' => AlignmentKind::Aggregate,
+                    _ => AlignmentKind::Stack,
+                };
+                let fields: Vec<&str> = rest.split(':').collect();
+                let size = match fields[0] {
+                    "" if kind == AlignmentKind::Aggregate || kind == AlignmentKind::Stack => None,
+                    size => Some(parse_number(size)?),
+                };
+
+                if fields.len() < 2 || fields.len() > 3 {
+                    return Err("Data layout contains an invalid alignment specification");
+                }
+
+                Specification::Alignment(kind, size, parse_alignment(fields[1], fields.get(2).copied())?)
+            },
+            _ => return Err("Data layout contains an unknown specification"),
+        };
+
+        Ok(specification)
+    }
+}
+
+impl fmt::Display for Specification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn write_alignment(f: &mut fmt::Formatter, alignment: &Alignment) -> fmt::Result {
+            write!(f, ":{}", alignment.abi)?;
+
+            if let Some(preferred) = alignment.preferred {
+                write!(f, ":{}", preferred)?;
+            }
+
+            Ok(())
+        }
+
+        fn write_numbers(f: &mut fmt::Formatter, numbers: &[u32]) -> fmt::Result {
+            let numbers: Vec<String> = numbers.iter().map(u32::to_string).collect();
+
+            write!(f, "{}", numbers.join(":"))
+        }
+
+        match self {
+            Specification::ByteOrdering(ByteOrdering::LittleEndian) => write!(f, "e"),
+            Specification::ByteOrdering(ByteOrdering::BigEndian) => write!(f, "E"),
+            Specification::Mangling(mangling) => write!(f, "m:{}", mangling.get_code()),
+            Specification::StackAlignment(alignment) => write!(f, "S{}", alignment),
+            Specification::ProgramAddressSpace(address_space) => write!(f, "P{}", address_space),
+            Specification::AllocaAddressSpace(address_space) => write!(f, "A{}", address_space),
+            Specification::GlobalsAddressSpace(address_space) => write!(f, "G{}", address_space),
+            Specification::Pointer(address_space, pointer_layout) => {
+                write!(f, "p")?;
+
+                if *address_space != 0 {
+                    write!(f, "{}", address_space)?;
+                }
+
+                write!(f, ":{}", pointer_layout.size)?;
+                write_alignment(f, &pointer_layout.alignment)?;
+
+                if let Some(index_size) = pointer_layout.index_size {
+                    // The preferred alignment must be written for the index size to follow it
+                    if pointer_layout.alignment.preferred.is_none() {
+                        write!(f, ":{}", pointer_layout.alignment.abi)?;
+                    }
+
+                    write!(f, ":{}", index_size)?;
+                }
+
+                Ok(())
+            },
+            Specification::Alignment(kind, size, alignment) => {
+                write!(f, "{}", kind.get_code())?;
+
+                if let Some(size) = size {
+                    write!(f, "{}", size)?;
+                }
+
+                write_alignment(f, alignment)
+            },
+            Specification::FunctionPointerAlignment(FunctionPointerAlignment::Independent(alignment)) => write!(f, "Fi{}", alignment),
+            Specification::FunctionPointerAlignment(FunctionPointerAlignment::MultipleOfFunctionAlignment(alignment)) => write!(f, "Fn{}", alignment),
+            Specification::NativeIntegerWidths(widths) => {
+                write!(f, "n")?;
+                write_numbers(f, widths)
+            },
+            Specification::NonIntegralAddressSpaces(address_spaces) => {
+                write!(f, "ni:")?;
+                write_numbers(f, address_spaces)
+            },
+        }
+    }
+}
+
+/// The settings of a `DataLayout`, which can be parsed from one or built up to create one.
+///
+/// The specifications making up a data layout string are kept in order, so that parsing a
+/// layout and converting it back to a string reproduces it exactly. Setting something which is
+/// already specified replaces its specification in place, whereas anything else is appended.
+/// Getters return LLVM's defaults for anything not specified. All sizes and alignments are in bits.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::data_layout::{Alignment, DataLayoutSpec, Mangling, PointerLayout};
+/// use inkwell::targets::ByteOrdering;
+///
+/// let spec = DataLayoutSpec::new()
+///     .byte_ordering(ByteOrdering::LittleEndian)
+///     .mangling(Mangling::Elf)
+///     .pointer_layout(0, PointerLayout::new(32, Alignment::new(32)))
+///     .integer_alignment(64, Alignment::new(64))
+///     .native_integer_widths(&[32, 64])
+///     .stack_alignment(128);
+///
+/// assert_eq!(spec.to_string(), "e-m:e-p:32:32-i64:64-n32:64-S128");
+///
+/// let data_layout = spec.build();
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DataLayoutSpec {
+    specifications: Vec<Specification>,
+}
+
+impl DataLayoutSpec {
+    /// Creates a `DataLayoutSpec` with nothing specified, so that LLVM's defaults apply throughout.
+    pub fn new() -> Self {
+        DataLayoutSpec::default()
+    }
+
+    /// Parses a data layout string, as described in the LLVM language reference.
+    pub fn parse(data_layout: &str) -> Result<Self, &'static str> {
+        if data_layout.is_empty() {
+            return Ok(DataLayoutSpec::new());
+        }
+
+        let specifications = data_layout.split('-')
+            .map(Specification::parse)
+            .collect::<Result<_, _>>()?;
+
+        Ok(DataLayoutSpec { specifications })
+    }
+
+    /// Creates a `DataLayout` with these settings.
+    pub fn build(&self) -> DataLayout {
+        DataLayout::create(&self.to_string())
+    }
+
+    fn find<'a, T, F: Fn(&'a Specification) -> Option<T>>(&'a self, f: F) -> Option<T> {
+        self.specifications.iter().rev().find_map(f)
+    }
+
+    fn set(mut self, specification: Specification) -> Self {
+        match self.specifications.iter_mut().find(|existing| existing.overrides(&specification)) {
+            Some(existing) => *existing = specification,
+            None => self.specifications.push(specification),
+        }
+
+        self
+    }
+
+    fn get_alignment(&self, kind: AlignmentKind, size: u32) -> Option<Alignment> {
+        let alignment = self.find(|specification| match *specification {
+            Specification::Alignment(found_kind, found_size, alignment) if found_kind == kind && found_size.unwrap_or(0) == size => Some(alignment),
+            _ => None,
+        });
+
+        alignment.or_else(|| {
+            DEFAULT_ALIGNMENTS.iter()
+                .find(|&&(found_kind, found_size, ..)| found_kind == kind && found_size == size)
+                .map(|&(_, _, abi, preferred)| Alignment::new(abi).with_preferred(preferred))
+        })
+    }
+
+    /// Gets the byte ordering, which defaults to little endian.
+    pub fn get_byte_ordering(&self) -> ByteOrdering {
+        self.find(|specification| match *specification {
+            Specification::ByteOrdering(byte_ordering) => Some(byte_ordering),
+            _ => None,
+        }).unwrap_or(ByteOrdering::LittleEndian)
+    }
+
+    pub fn byte_ordering(self, byte_ordering: ByteOrdering) -> Self {
+        self.set(Specification::ByteOrdering(byte_ordering))
+    }
+
+    /// Gets how symbols are mangled, if specified.
+    pub fn get_mangling(&self) -> Option<Mangling> {
+        self.find(|specification| match *specification {
+            Specification::Mangling(mangling) => Some(mangling),
+            _ => None,
+        })
+    }
+
+    pub fn mangling(self, mangling: Mangling) -> Self {
+        self.set(Specification::Mangling(mangling))
+    }
+
+    /// Gets the natural alignment of the stack, or `None` if it is unspecified.
+    pub fn get_stack_alignment(&self) -> Option<u32> {
+        self.find(|specification| match *specification {
+            Specification::StackAlignment(alignment) => Some(alignment),
+            _ => None,
+        }).filter(|&alignment| alignment != 0)
+    }
+
+    pub fn stack_alignment(self, alignment: u32) -> Self {
+        self.set(Specification::StackAlignment(alignment))
+    }
+
+    /// Gets the address space functions are placed in, which defaults to 0.
+    pub fn get_program_address_space(&self) -> u32 {
+        self.find(|specification| match *specification {
+            Specification::ProgramAddressSpace(address_space) => Some(address_space),
+            _ => None,
+        }).unwrap_or(0)
+    }
+
+    pub fn program_address_space(self, address_space: u32) -> Self {
+        self.set(Specification::ProgramAddressSpace(address_space))
+    }
+
+    /// Gets the address space allocas are placed in, which defaults to 0.
+    pub fn get_alloca_address_space(&self) -> u32 {
+        self.find(|specification| match *specification {
+            Specification::AllocaAddressSpace(address_space) => Some(address_space),
+            _ => None,
+        }).unwrap_or(0)
+    }
+
+    pub fn alloca_address_space(self, address_space: u32) -> Self {
+        self.set(Specification::AllocaAddressSpace(address_space))
+    }
+
+    /// Gets the address space global variables are placed in by default, which defaults to 0.
+    pub fn get_globals_address_space(&self) -> u32 {
+        self.find(|specification| match *specification {
+            Specification::GlobalsAddressSpace(address_space) => Some(address_space),
+            _ => None,
+        }).unwrap_or(0)
+    }
+
+    pub fn globals_address_space(self, address_space: u32) -> Self {
+        self.set(Specification::GlobalsAddressSpace(address_space))
+    }
+
+    /// Gets the layout of pointers in `address_space`. Address spaces without a layout of their own
+    /// use address space 0's, which defaults to 64 bit pointers aligned to 64 bits.
+    pub fn get_pointer_layout(&self, address_space: u32) -> PointerLayout {
+        let find_pointer_layout = |address_space| self.find(|specification| match *specification {
+            Specification::Pointer(found_address_space, pointer_layout) if found_address_space == address_space => Some(pointer_layout),
+            _ => None,
+        });
+
+        find_pointer_layout(address_space)
+            .or_else(|| find_pointer_layout(0))
+            .unwrap_or_else(|| PointerLayout::new(64, Alignment::new(64)))
+    }
+
+    pub fn pointer_layout(self, address_space: u32, pointer_layout: PointerLayout) -> Self {
+        self.set(Specification::Pointer(address_space, pointer_layout))
+    }
+
+    /// Gets the alignment of `size` bit integers, if it is specified or one of LLVM's defaults
+    /// for 1, 8, 16, 32 or 64 bit integers.
+    pub fn get_integer_alignment(&self, size: u32) -> Option<Alignment> {
+        self.get_alignment(AlignmentKind::Integer, size)
+    }
+
+    pub fn integer_alignment(self, size: u32, alignment: Alignment) -> Self {
+        self.set(Specification::Alignment(AlignmentKind::Integer, Some(size), alignment))
+    }
+
+    /// Gets the alignment of `size` bit floats, if it is specified or one of LLVM's defaults for
+    /// 16, 32, 64 or 128 bit floats.
+    pub fn get_float_alignment(&self, size: u32) -> Option<Alignment> {
+        self.get_alignment(AlignmentKind::Float, size)
+    }
+
+    pub fn float_alignment(self, size: u32, alignment: Alignment) -> Self {
+        self.set(Specification::Alignment(AlignmentKind::Float, Some(size), alignment))
+    }
+
+    /// Gets the alignment of `size` bit vectors, if it is specified or one of LLVM's defaults for
+    /// 64 or 128 bit vectors.
+    pub fn get_vector_alignment(&self, size: u32) -> Option<Alignment> {
+        self.get_alignment(AlignmentKind::Vector, size)
+    }
+
+    pub fn vector_alignment(self, size: u32, alignment: Alignment) -> Self {
+        self.set(Specification::Alignment(AlignmentKind::Vector, Some(size), alignment))
+    }
+
+    /// Gets the alignment of aggregates, which defaults to an ABI alignment of 0 and preferred alignment of 64.
+    pub fn get_aggregate_alignment(&self) -> Alignment {
+        self.get_alignment(AlignmentKind::Aggregate, 0).unwrap_or_else(|| Alignment::new(0).with_preferred(64))
+    }
+
+    pub fn aggregate_alignment(self, alignment: Alignment) -> Self {
+        self.set(Specification::Alignment(AlignmentKind::Aggregate, None, alignment))
+    }
+
+    /// Gets how function pointers are aligned, if specified.
+    pub fn get_function_pointer_alignment(&self) -> Option<FunctionPointerAlignment> {
+        self.find(|specification| match *specification {
+            Specification::FunctionPointerAlignment(alignment) => Some(alignment),
+            _ => None,
+        })
+    }
+
+    pub fn function_pointer_alignment(self, alignment: FunctionPointerAlignment) -> Self {
+        self.set(Specification::FunctionPointerAlignment(alignment))
+    }
+
+    /// Gets the widths of the integers the target natively supports, which is empty if unspecified.
+    pub fn get_native_integer_widths(&self) -> &[u32] {
+        self.find(|specification| match specification {
+            Specification::NativeIntegerWidths(widths) => Some(widths.as_slice()),
+            _ => None,
+        }).unwrap_or(&[])
+    }
+
+    pub fn native_integer_widths(self, widths: &[u32]) -> Self {
+        self.set(Specification::NativeIntegerWidths(widths.to_vec()))
+    }
+
+    /// Gets the address spaces whose pointers have no stable integer representation.
+    pub fn get_non_integral_address_spaces(&self) -> &[u32] {
+        self.find(|specification| match specification {
+            Specification::NonIntegralAddressSpaces(address_spaces) => Some(address_spaces.as_slice()),
+            _ => None,
+        }).unwrap_or(&[])
+    }
+
+    pub fn non_integral_address_spaces(self, address_spaces: &[u32]) -> Self {
+        self.set(Specification::NonIntegralAddressSpaces(address_spaces.to_vec()))
+    }
+}
+
+impl fmt::Display for DataLayoutSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, specification) in self.specifications.iter().enumerate() {
+            if i != 0 {
+                write!(f, "-")?;
+            }
+
+            write!(f, "{}", specification)?;
+        }
+
+        Ok(())
+    }
+}
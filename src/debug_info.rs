@@ -104,6 +104,7 @@ use crate::basic_block::BasicBlock;
 use crate::context::Context;
 pub use crate::debug_info::flags::{DIFlags, DIFlagsConstants};
 use crate::module::Module;
+use crate::AddressSpace;
 use crate::values::{AsValueRef, BasicValueEnum, InstructionValue, PointerValue, MetadataValue};
 
 #[llvm_versions(8.0..=latest)]
@@ -115,7 +116,8 @@ use llvm_sys::debuginfo::LLVMMetadataReplaceAllUsesWith;
 use llvm_sys::debuginfo::LLVMTemporaryMDNode;
 use llvm_sys::debuginfo::{LLVMCreateDIBuilder, LLVMCreateDIBuilderDisallowUnresolved};
 use llvm_sys::debuginfo::{
-    LLVMDIBuilderCreateAutoVariable, LLVMDIBuilderCreateBasicType, LLVMDIBuilderCreateCompileUnit,
+    LLVMDIBuilderCreateArrayType, LLVMDIBuilderCreateAutoVariable, LLVMDIBuilderCreateBasicType,
+    LLVMDIBuilderCreateCompileUnit, LLVMDIBuilderCreatePointerType, LLVMDIBuilderGetOrCreateSubrange,
     LLVMDIBuilderCreateDebugLocation, LLVMDIBuilderCreateExpression, LLVMDIBuilderCreateFile,
     LLVMDIBuilderCreateFunction, LLVMDIBuilderCreateLexicalBlock, LLVMDIBuilderCreateMemberType,
     LLVMDIBuilderCreateNameSpace, LLVMDIBuilderCreateParameterVariable,
@@ -131,6 +133,7 @@ use llvm_sys::prelude::{LLVMDIBuilderRef, LLVMMetadataRef};
 use llvm_sys::core::LLVMMetadataAsValue;
 use std::convert::TryInto;
 use std::marker::PhantomData;
+use std::ops::Range;
 
 /// Gets the version of debug metadata produced by the current LLVM version.
 pub fn debug_metadata_version() -> libc::c_uint {
@@ -561,6 +564,61 @@ impl<'ctx> DebugInfoBuilder<'ctx> {
         }
     }
 
+    /// Create a pointer type. A `pointee` of `None` makes a pointer to void.
+    pub fn create_pointer_type(
+        &self,
+        name: &str,
+        pointee: Option<DIType<'ctx>>,
+        size_in_bits: u64,
+        align_in_bits: u32,
+        address_space: AddressSpace,
+    ) -> DIDerivedType<'ctx> {
+        let pointee = pointee.map_or(std::ptr::null_mut(), |dt| dt.metadata_ref);
+        let metadata_ref = unsafe {
+            LLVMDIBuilderCreatePointerType(
+                self.builder,
+                pointee,
+                size_in_bits,
+                align_in_bits,
+                address_space as u32,
+                name.as_ptr() as _,
+                name.len(),
+            )
+        };
+        DIDerivedType {
+            metadata_ref,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create an array type of `inner_type` elements, with one subscript range per dimension.
+    pub fn create_array_type(
+        &self,
+        inner_type: DIType<'ctx>,
+        size_in_bits: u64,
+        align_in_bits: u32,
+        subscripts: &[Range<i64>],
+    ) -> DICompositeType<'ctx> {
+        let mut subscripts: Vec<LLVMMetadataRef> = subscripts
+            .iter()
+            .map(|range| unsafe { LLVMDIBuilderGetOrCreateSubrange(self.builder, range.start, range.end - range.start) })
+            .collect();
+        let metadata_ref = unsafe {
+            LLVMDIBuilderCreateArrayType(
+                self.builder,
+                size_in_bits,
+                align_in_bits,
+                inner_type.metadata_ref,
+                subscripts.as_mut_ptr(),
+                subscripts.len().try_into().unwrap(),
+            )
+        };
+        DICompositeType {
+            metadata_ref,
+            _marker: PhantomData,
+        }
+    }
+
     /// Create a struct type.
     pub fn create_struct_type(
         &self,
@@ -1,18 +1,38 @@
-use libc::c_int;
-use llvm_sys::execution_engine::{LLVMGetExecutionEngineTargetData, LLVMExecutionEngineRef, LLVMRunFunction, LLVMRunFunctionAsMain, LLVMDisposeExecutionEngine, LLVMGetFunctionAddress, LLVMAddModule, LLVMFindFunction, LLVMLinkInMCJIT, LLVMLinkInInterpreter, LLVMRemoveModule, LLVMGenericValueRef, LLVMFreeMachineCodeForFunction, LLVMAddGlobalMapping, LLVMRunStaticConstructors, LLVMRunStaticDestructors};
-
+use libc::{c_char, c_int, c_void};
+use llvm_sys::core::{LLVMAddFunction, LLVMGetGlobalParent, LLVMGetModuleContext, LLVMModuleCreateWithNameInContext};
+use llvm_sys::execution_engine::{LLVMCreateGenericValueOfPointer, LLVMDisposeMCJITMemoryManager, LLVMInitializeMCJITCompilerOptions, LLVMMCJITCompilerOptions, LLVMMCJITMemoryManagerRef, LLVMDisposeGenericValue, LLVMGetExecutionEngineTargetData, LLVMExecutionEngineRef, LLVMRunFunction, LLVMRunFunctionAsMain, LLVMDisposeExecutionEngine, LLVMGetFunctionAddress, LLVMAddModule, LLVMFindFunction, LLVMLinkInMCJIT, LLVMLinkInInterpreter, LLVMRemoveModule, LLVMGenericValueRef, LLVMFreeMachineCodeForFunction, LLVMAddGlobalMapping, LLVMRunStaticConstructors, LLVMRunStaticDestructors};
+
+#[llvm_versions(8.0..=latest)]
+use llvm_sys::execution_engine::{LLVMCreateGDBRegistrationListener, LLVMCreateIntelJITEventListener, LLVMCreateOProfileJITEventListener, LLVMCreatePerfJITEventListener};
+#[llvm_versions(8.0..=latest)]
+use llvm_sys::prelude::LLVMJITEventListenerRef;
+
+#[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7")))]
+use crate::AtomicOrdering;
+use crate::{AddressSpace, OptimizationLevel};
+use crate::builder::Builder;
 use crate::context::Context;
-use crate::module::Module;
-use crate::support::{to_c_str, LLVMString};
-use crate::targets::TargetData;
-use crate::values::{AnyValue, AsValueRef, FunctionValue, GenericValue};
-
+use crate::memory_manager::{create_mcjit_memory_manager, MCJITMemoryManager};
+use crate::module::{Linkage, Module};
+use crate::support::{abort_on_panic, to_c_str, LLVMString};
+use crate::targets::{CodeModel, TargetData};
+use crate::types::{AsTypeRef, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType};
+use crate::values::{get_element_layout, AggregateValueEnum, AnyValue, AsValueRef, BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallableValue, FunctionValue, GenericValue, PointerValue};
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::env;
 use std::error::Error;
+use std::ffi::CString;
+use std::iter;
+use std::ptr;
 use std::rc::Rc;
 use std::ops::Deref;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::marker::PhantomData;
+use std::slice;
 use std::mem::{forget, transmute_copy, size_of, MaybeUninit};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 static EE_INNER_PANIC: &str = "ExecutionEngineInner should exist until Drop";
 
@@ -100,7 +120,7 @@ impl<'ctx> ExecutionEngine<'ctx> {
         let target_data = LLVMGetExecutionEngineTargetData(*execution_engine);
 
         ExecutionEngine {
-            execution_engine: Some(ExecEngineInner(execution_engine, PhantomData)),
+            execution_engine: Some(ExecEngineInner(execution_engine, Rc::default(), Rc::default(), PhantomData)),
             target_data: Some(TargetData::new(target_data)),
             jit_mode,
         }
@@ -178,6 +198,256 @@ impl<'ctx> ExecutionEngine<'ctx> {
         }
     }
 
+    /// Registers `closure` as the body of `function`, a declaration in one of this `ExecutionEngine`'s
+    /// modules, so that JIT code calling `function` calls `closure` instead. This generates a body for
+    /// `function` which passes a pointer to the closure through to an `extern "C"` trampoline, so it must
+    /// be called before `function`'s module is compiled.
+    ///
+    /// The closure is kept alive until this `ExecutionEngine`, all of its clones and any `JitFunction`s
+    /// from it have been dropped. It may only take and return `JitScalar`s, which must each match the
+    /// corresponding type of `function`. As with [`MCJITMemoryManager`] callbacks, panicking in it
+    /// aborts the process.
+    ///
+    /// [`MCJITMemoryManager`]: ../memory_manager/trait.MCJITMemoryManager.html
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::OptimizationLevel;
+    /// use inkwell::context::Context;
+    /// use inkwell::execution_engine::JitFunction;
+    /// use inkwell::targets::{InitializationConfig, Target};
+    ///
+    /// Target::initialize_native(&InitializationConfig::default()).unwrap();
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("counter");
+    /// let builder = context.create_builder();
+    /// let i64_type = context.i64_type();
+    /// let fn_type = i64_type.fn_type(&[i64_type.into()], false);
+    /// let bump = module.add_function("bump", fn_type, None);
+    /// let twice = module.add_function("twice", fn_type, None);
+    /// let entry = context.append_basic_block(twice, "entry");
+    ///
+    /// builder.position_at_end(entry);
+    ///
+    /// let by = twice.get_first_param().unwrap();
+    ///
+    /// builder.build_call(bump, &[by.into()], "first");
+    ///
+    /// let second = builder.build_call(bump, &[by.into()], "second").try_as_basic_value().left().unwrap();
+    ///
+    /// builder.build_return(Some(&second));
+    ///
+    /// let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+    /// let mut total = 0;
+    ///
+    /// execution_engine.add_closure(bump, move |by: u64| { total += by; total }).unwrap();
+    ///
+    /// unsafe {
+    ///     let twice: JitFunction<unsafe extern "C" fn(u64) -> u64> = execution_engine.get_function("twice").unwrap();
+    ///
+    ///     assert_eq!(twice.call(3), 6);
+    ///     assert_eq!(twice.call(1), 8);
+    /// }
+    /// ```
+    pub fn add_closure<Args, F>(&self, function: FunctionValue<'ctx>, closure: F) -> Result<(), &'static str>
+    where
+        F: JitClosure<Args> + 'ctx,
+    {
+        if !self.jit_mode {
+            return Err("Closures can only be called from JIT ExecutionEngines");
+        }
+
+        if function.count_basic_blocks() != 0 {
+            return Err("Function already has a body");
+        }
+
+        let fn_type = function.get_type();
+
+        if fn_type.is_var_arg() {
+            return Err("Closures cannot be variadic");
+        }
+
+        if fn_type.count_param_types() != F::ARITY {
+            return Err("Closure and function take different numbers of arguments");
+        }
+
+        if !F::matches_fn_type(fn_type, self.get_target_data()) {
+            return Err("Closure and function have different argument or return types");
+        }
+
+        let mut closure = Box::new(closure);
+        let closure_address = &mut *closure as *mut F as usize;
+        let trampoline_address = F::trampoline_address();
+
+        let context = fn_type.get_context();
+        let builder = context.create_builder();
+        let int_ptr_type = context.ptr_sized_int_type(self.get_target_data(), None);
+        let i8_ptr_type = context.i8_type().ptr_type(AddressSpace::Generic);
+        let param_types: Vec<BasicMetadataTypeEnum> = iter::once(i8_ptr_type.into())
+            .chain(fn_type.get_param_types().into_iter().map(BasicMetadataTypeEnum::from))
+            .collect();
+        let trampoline_type = match fn_type.get_return_type() {
+            Some(return_type) => return_type.fn_type(&param_types, false),
+            None => context.void_type().fn_type(&param_types, false),
+        };
+        let trampoline = int_ptr_type.const_int(trampoline_address as u64, false).const_to_pointer(trampoline_type.ptr_type(AddressSpace::Generic));
+        let trampoline = CallableValue::try_from(trampoline).expect("Trampoline should be a function pointer");
+        let closure_arg = int_ptr_type.const_int(closure_address as u64, false).const_to_pointer(i8_ptr_type);
+        let args: Vec<BasicMetadataValueEnum> = iter::once(closure_arg.into())
+            .chain(function.get_params().into_iter().map(BasicMetadataValueEnum::from))
+            .collect();
+        let entry = context.append_basic_block(function, "entry");
+
+        builder.position_at_end(entry);
+
+        match builder.build_call(trampoline, &args, "").try_as_basic_value().left() {
+            Some(value) => builder.build_return(Some(&value)),
+            None => builder.build_return(None),
+        };
+
+        self.execution_engine.as_ref().expect(EE_INNER_PANIC).1.0.borrow_mut().push(closure);
+
+        Ok(())
+    }
+
+    /// Adds `module` to this `ExecutionEngine` and creates an `IndirectStub` named `name` which calls
+    /// `function`, defined in `module`. Other modules which call `name` call through the stub, so
+    /// `function` can later be swapped out with `redefine_indirect_stub` without invalidating any
+    /// pointers to it. `function` is renamed to a private symbol of the stub's.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::module::Module;
+    /// use inkwell::values::FunctionValue;
+    /// use inkwell::OptimizationLevel;
+    ///
+    /// fn define_answer<'ctx>(context: &'ctx Context, answer: u64) -> (Module<'ctx>, FunctionValue<'ctx>) {
+    ///     let module = context.create_module("answer");
+    ///     let i64_type = context.i64_type();
+    ///     let function = module.add_function("answer", i64_type.fn_type(&[], false), None);
+    ///     let builder = context.create_builder();
+    ///
+    ///     builder.position_at_end(context.append_basic_block(function, "entry"));
+    ///     builder.build_return(Some(&i64_type.const_int(answer, false)));
+    ///
+    ///     (module, function)
+    /// }
+    ///
+    /// let context = Context::create();
+    /// let main_module = context.create_module("main");
+    /// let execution_engine = main_module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+    /// let (module, function) = define_answer(&context, 41);
+    /// let mut stub = execution_engine.add_indirect_stub("answer", module, function).unwrap();
+    /// let answer = stub.get_address();
+    ///
+    /// let (module, function) = define_answer(&context, 42);
+    ///
+    /// execution_engine.redefine_indirect_stub(&mut stub, module, function).unwrap();
+    ///
+    /// assert_eq!(stub.get_address(), answer);
+    /// ```
+    pub fn add_indirect_stub(&self, name: &str, module: Module<'ctx>, function: FunctionValue<'ctx>) -> Result<IndirectStub<'ctx>, &'static str> {
+        if !self.jit_mode {
+            return Err("Indirect stubs can only be used with JIT ExecutionEngines");
+        }
+
+        let fn_type = function.get_type();
+
+        if fn_type.is_var_arg() {
+            return Err("Indirect stubs cannot be variadic");
+        }
+
+        let target_address = self.compile_stub_target(name, 0, &module, function)?;
+        let target = Box::new(AtomicUsize::new(target_address));
+        let target_ptr = &*target as *const AtomicUsize as usize;
+
+        // The stub's code refers to target, so it must live as long as this ExecutionEngine
+        self.execution_engine.as_ref().expect(EE_INNER_PANIC).2.0.borrow_mut().push(target);
+
+        let stub_name = to_c_str(&format!("{}.inkwell_stub", name)).into_owned();
+        let stub_module = unsafe {
+            Module::new(LLVMModuleCreateWithNameInContext(stub_name.as_ptr(), LLVMGetModuleContext(module.module.get())))
+        };
+
+        unsafe {
+            build_indirect_stub(&stub_module, name, fn_type, target_ptr, self.get_target_data());
+        }
+
+        // Dropping the stub's module leaves it owned by this ExecutionEngine
+        self.add_module(&stub_module).expect("Stub module should not belong to an ExecutionEngine");
+
+        let address = self.get_function_address(name).map_err(|_| "Unable to compile indirect stub")?;
+
+        Ok(IndirectStub {
+            execution_engine: self.execution_engine.clone().expect(EE_INNER_PANIC),
+            name: name.to_string(),
+            fn_type,
+            address,
+            target: target_ptr as *const AtomicUsize,
+            generation: 0,
+            module: Some(module),
+        })
+    }
+
+    /// Adds `module` to this `ExecutionEngine` and atomically points `stub` at `function`, defined in
+    /// `module`, so that subsequent calls through the stub run it. Only `module` is compiled. The module
+    /// which previously implemented the stub is removed from this `ExecutionEngine` and disposed, though
+    /// its code remains loaded in case it is still running.
+    pub fn redefine_indirect_stub(&self, stub: &mut IndirectStub<'ctx>, module: Module<'ctx>, function: FunctionValue<'ctx>) -> Result<(), &'static str> {
+        if stub.execution_engine != *self.execution_engine.as_ref().expect(EE_INNER_PANIC) {
+            return Err("Indirect stub belongs to another ExecutionEngine");
+        }
+
+        if function.get_type() != stub.fn_type {
+            return Err("Function type does not match the indirect stub's");
+        }
+
+        let target_address = self.compile_stub_target(&stub.name, stub.generation + 1, &module, function)?;
+
+        unsafe {
+            (*stub.target).store(target_address, Ordering::Release);
+        }
+
+        stub.generation += 1;
+
+        if let Some(previous_module) = stub.module.replace(module) {
+            self.remove_module(&previous_module).expect("Previous module should belong to this ExecutionEngine");
+        }
+
+        Ok(())
+    }
+
+    // Adds module to this ExecutionEngine and compiles function, renamed after the stub, returning its address
+    fn compile_stub_target(&self, name: &str, generation: usize, module: &Module<'ctx>, function: FunctionValue<'ctx>) -> Result<usize, &'static str> {
+        if unsafe { LLVMGetGlobalParent(function.as_value_ref()) } != module.module.get() {
+            return Err("Function is not defined in module");
+        }
+
+        if function.count_basic_blocks() == 0 {
+            return Err("Function has no body");
+        }
+
+        self.add_module(module).map_err(|()| "Module is already owned by an ExecutionEngine")?;
+
+        let target_name = format!("{}.inkwell_impl.{}", name, generation);
+
+        function.as_global_value().set_name(&target_name);
+        function.set_linkage(Linkage::External);
+
+        match self.get_function_address(&target_name) {
+            Ok(address) => Ok(address),
+            Err(_) => {
+                self.remove_module(module).expect("Module should belong to this ExecutionEngine");
+
+                Err("Unable to compile function")
+            },
+        }
+    }
+
     /// Adds a module to an `ExecutionEngine`.
     ///
     /// The method will be `Ok(())` if the module does not belong to an `ExecutionEngine` already and `Err(())` otherwise.
@@ -376,14 +646,105 @@ impl<'ctx> ExecutionEngine<'ctx> {
 
     // TODOC: Marked as unsafe because input function could very well do something unsafe. It's up to the caller
     // to ensure that doesn't happen by defining their function correctly.
+    //
+    // Aggregate and vector GenericValues are only supported by interpreter ExecutionEngines, so this panics
+    // if they are passed to or returned from a function run by a JIT ExecutionEngine. See try_run_function.
     pub unsafe fn run_function(&self, function: FunctionValue<'ctx>, args: &[&GenericValue<'ctx>]) -> GenericValue<'ctx> {
+        match self.try_run_function(function, args) {
+            Ok(value) => value,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Runs `function` with `args` like `run_function`, but returns an `Err` rather than panicking
+    /// when `function` can't be run with them. That is when aggregate or vector `GenericValue`s, which
+    /// only interpreters support, are passed to or returned from a function run by a JIT `ExecutionEngine`,
+    /// or when a function taking or returning them is given the wrong number of arguments.
+    ///
+    /// # Safety
+    ///
+    /// As with `run_function`, `function` must be safe to call with `args`.
+    pub unsafe fn try_run_function(&self, function: FunctionValue<'ctx>, args: &[&GenericValue<'ctx>]) -> Result<GenericValue<'ctx>, &'static str> {
+        let fn_type = function.get_type();
+        let returns_aggregate = fn_type.get_return_type().map_or(false, is_aggregate_type);
+
+        if returns_aggregate || args.iter().any(|arg| arg.is_aggregate()) {
+            if self.jit_mode {
+                return Err("Aggregate and vector GenericValues are only supported by interpreter ExecutionEngines");
+            }
+
+            if args.len() != fn_type.count_param_types() as usize {
+                return Err("Incorrect number of arguments");
+            }
+
+            return Ok(self.run_function_through_memory(function, args));
+        }
+
         let mut args: Vec<LLVMGenericValueRef> = args.iter()
                                                      .map(|val| val.generic_value)
                                                      .collect();
 
         let value = LLVMRunFunction(self.execution_engine_inner(), function.as_value_ref(), args.len() as u32, args.as_mut_ptr()); // REVIEW: usize to u32 ok??
 
-        GenericValue::new(value)
+        Ok(GenericValue::new(value))
+    }
+
+    // The C API can only pass scalar GenericValues, so aggregates and vectors are instead written to
+    // memory laid out according to the TargetData and passed to a temporary thunk which loads the
+    // arguments, calls the function and stores its return value back to memory.
+    unsafe fn run_function_through_memory(&self, function: FunctionValue<'ctx>, args: &[&GenericValue<'ctx>]) -> GenericValue<'ctx> {
+        let target_data = self.get_target_data();
+        let fn_type = function.get_type();
+        let param_types = fn_type.get_param_types();
+
+        let mut offsets = Vec::with_capacity(param_types.len());
+        let mut args_size = 0;
+
+        for param_type in &param_types {
+            let alignment = target_data.get_abi_alignment(param_type) as u64;
+
+            args_size = (args_size + alignment - 1) / alignment * alignment;
+            offsets.push(args_size);
+            args_size += target_data.get_abi_size(param_type);
+        }
+
+        // Backed by u64s so that every argument is suitably aligned
+        let mut args_memory = vec![0u64; (args_size as usize + 7) / 8];
+        let args_bytes = slice::from_raw_parts_mut(args_memory.as_mut_ptr() as *mut u8, args_memory.len() * 8);
+
+        for ((arg, param_type), &offset) in args.iter().zip(&param_types).zip(&offsets) {
+            let bytes = arg.to_bytes(*param_type, target_data);
+
+            args_bytes[offset as usize..offset as usize + bytes.len()].copy_from_slice(&bytes);
+        }
+
+        let return_type = fn_type.get_return_type();
+        let return_size = return_type.map_or(0, |ty| target_data.get_abi_size(&ty)) as usize;
+        let mut return_memory = vec![0u64; (return_size + 7) / 8];
+
+        let thunk = build_memory_thunk(function, &param_types, &offsets, target_data);
+        let mut thunk_args = [
+            LLVMCreateGenericValueOfPointer(args_memory.as_mut_ptr() as *mut c_void),
+            LLVMCreateGenericValueOfPointer(return_memory.as_mut_ptr() as *mut c_void),
+        ];
+        let result = LLVMRunFunction(self.execution_engine_inner(), thunk.as_value_ref(), 2, thunk_args.as_mut_ptr());
+
+        for thunk_arg in thunk_args.iter() {
+            LLVMDisposeGenericValue(*thunk_arg);
+        }
+
+        thunk.delete();
+
+        match return_type {
+            Some(return_type) => {
+                LLVMDisposeGenericValue(result);
+
+                let return_bytes = slice::from_raw_parts(return_memory.as_ptr() as *const u8, return_size);
+
+                GenericValue::from_bytes(return_type, return_bytes, target_data)
+            },
+            None => GenericValue::new(result),
+        }
     }
 
     // TODOC: Marked as unsafe because input function could very well do something unsafe. It's up to the caller
@@ -391,11 +752,102 @@ impl<'ctx> ExecutionEngine<'ctx> {
     // SubType: Only for JIT EEs?
     pub unsafe fn run_function_as_main(&self, function: FunctionValue<'ctx>, args: &[&str]) -> c_int {
         let cstring_args: Vec<_> = args.iter().map(|&arg| to_c_str(arg)).collect();
-        let raw_args: Vec<*const _> = cstring_args.iter().map(|arg| arg.as_ptr()).collect();
+        let raw_args: Vec<*const _> = cstring_args.iter()
+            .map(|arg| arg.as_ptr())
+            .chain(iter::once(ptr::null()))
+            .collect();
+
+        // LLVM reads envp up to its null terminator
+        let environment_variables = [ptr::null()];
+
+        LLVMRunFunctionAsMain(self.execution_engine_inner(), function.as_value_ref(), args.len() as u32, raw_args.as_ptr(), environment_variables.as_ptr()) // REVIEW: usize to u32 cast ok??
+    }
+
+    /// Runs `function` as a C `main` function given the program arguments `args` and the environment
+    /// variables `env` as `(name, value)` pairs.
+    ///
+    /// `env` is passed to `main` as its null terminated `envp` parameter, if it takes one, in the same
+    /// `NAME=value` form a C program would see. It is not applied to this process, so `getenv` still
+    /// reads this process' own environment. Since `function` runs on this thread, a call to `exit`
+    /// terminates this process just as it would in a C program, running its `atexit` handlers.
+    ///
+    /// # Safety
+    ///
+    /// As with `run_function_as_main`, `function` must be a valid `main` function.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::targets::{InitializationConfig, Target};
+    /// use inkwell::OptimizationLevel;
+    ///
+    /// Target::initialize_native(&InitializationConfig::default()).unwrap();
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("cli");
+    /// let i32_type = context.i32_type();
+    /// let main = module.add_function("main", i32_type.fn_type(&[], false), None);
+    /// let builder = context.create_builder();
+    /// let entry = context.append_basic_block(main, "entry");
+    ///
+    /// builder.position_at_end(entry);
+    /// builder.build_return(Some(&i32_type.const_int(3, false)));
+    ///
+    /// let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+    /// let code = unsafe {
+    ///     execution_engine.run_function_as_main_with_env(main, &["cli"], &[("HOME", "/home/user")])
+    /// };
+    ///
+    /// assert_eq!(code, 3);
+    /// ```
+    pub unsafe fn run_function_as_main_with_env(&self, function: FunctionValue<'ctx>, args: &[&str], env: &[(&str, &str)]) -> c_int {
+        let environment_variables: Vec<CString> = env.iter()
+            .map(|(name, value)| to_c_str(&format!("{}={}", name, value)).into_owned())
+            .collect();
+
+        self.run_main(function, args, &environment_variables)
+    }
+
+    /// Runs `function` as a C `main` function given the program arguments `args`, passing this process'
+    /// environment variables as its `envp` parameter. As with `run_function_as_main_with_env`, a call
+    /// to `exit` terminates this process.
+    ///
+    /// # Safety
+    ///
+    /// As with `run_function_as_main_with_env`, `function` must be a valid `main` function.
+    pub unsafe fn run_function_as_main_with_process_env(&self, function: FunctionValue<'ctx>, args: &[&str]) -> c_int {
+        let environment_variables: Vec<CString> = env::vars_os()
+            .map(|(name, value)| {
+                let mut variable = name;
 
-        let environment_variables = vec![]; // TODO: Support envp. Likely needs to be null terminated
+                variable.push("=");
+                variable.push(value);
 
-        LLVMRunFunctionAsMain(self.execution_engine_inner(), function.as_value_ref(), raw_args.len() as u32, raw_args.as_ptr(), environment_variables.as_ptr()) // REVIEW: usize to u32 cast ok??
+                #[cfg(unix)]
+                let variable = std::os::unix::ffi::OsStringExt::into_vec(variable);
+                #[cfg(not(unix))]
+                let variable = variable.to_string_lossy().into_owned();
+
+                CString::new(variable).expect("Environment variables cannot contain null bytes")
+            })
+            .collect();
+
+        self.run_main(function, args, &environment_variables)
+    }
+
+    unsafe fn run_main(&self, function: FunctionValue<'ctx>, args: &[&str], environment_variables: &[CString]) -> c_int {
+        let cstring_args: Vec<_> = args.iter().map(|&arg| to_c_str(arg)).collect();
+        let raw_args: Vec<*const c_char> = cstring_args.iter()
+            .map(|arg| arg.as_ptr())
+            .chain(iter::once(ptr::null()))
+            .collect();
+        let raw_envp: Vec<*const c_char> = environment_variables.iter()
+            .map(|variable| variable.as_ptr())
+            .chain(iter::once(ptr::null()))
+            .collect();
+
+        LLVMRunFunctionAsMain(self.execution_engine_inner(), function.as_value_ref(), args.len() as u32, raw_args.as_ptr(), raw_envp.as_ptr())
     }
 
     pub fn free_fn_machine_code(&self, function: FunctionValue<'ctx>) {
@@ -436,19 +888,138 @@ impl Drop for ExecutionEngine<'_> {
     }
 }
 
+fn is_aggregate_type(ty: BasicTypeEnum) -> bool {
+    matches!(ty, BasicTypeEnum::ArrayType(_) | BasicTypeEnum::StructType(_) | BasicTypeEnum::VectorType(_))
+}
+
+// Builds `void @"<function>.inkwell_thunk"(i8* %args, i8* %ret)`, which loads each argument from its offset
+// in %args, calls function and stores its return value to %ret. Aggregates and vectors are loaded and stored
+// element by element since the interpreter cannot load or store whole aggregates.
+unsafe fn build_memory_thunk<'ctx>(function: FunctionValue<'ctx>, param_types: &[BasicTypeEnum<'ctx>], offsets: &[u64], target_data: &TargetData) -> FunctionValue<'ctx> {
+    let context = function.get_type().get_context();
+    let builder = context.create_builder();
+    let i8_ptr_type = context.i8_type().ptr_type(AddressSpace::Generic);
+    let thunk_type = context.void_type().fn_type(&[i8_ptr_type.into(), i8_ptr_type.into()], false);
+    let name = to_c_str(&format!("{}.inkwell_thunk", function.get_name().to_string_lossy())).into_owned();
+    let thunk = LLVMAddFunction(LLVMGetGlobalParent(function.as_value_ref()), name.as_ptr(), thunk_type.as_type_ref());
+    let thunk = FunctionValue::new(thunk).expect("Thunk should be a valid function");
+    let entry = context.append_basic_block(thunk, "entry");
+
+    builder.position_at_end(entry);
+
+    let args_ptr = thunk.get_first_param().unwrap().into_pointer_value();
+    let return_ptr = thunk.get_last_param().unwrap().into_pointer_value();
+    let args: Vec<BasicMetadataValueEnum> = param_types.iter()
+        .zip(offsets)
+        .map(|(&param_type, &offset)| build_load_from_memory(&builder, &context, param_type, args_ptr, offset, target_data).into())
+        .collect();
+    let call = builder.build_call(function, &args, "");
+
+    if let Some(value) = call.try_as_basic_value().left() {
+        build_store_to_memory(&builder, &context, value, return_ptr, 0, target_data);
+    }
+
+    builder.build_return(None);
+
+    thunk
+}
+
+fn build_element_ptr<'ctx>(builder: &Builder<'ctx>, context: &Context, ty: BasicTypeEnum<'ctx>, base: PointerValue<'ctx>, offset: u64) -> PointerValue<'ctx> {
+    let offset = context.i64_type().const_int(offset, false);
+    let ptr = unsafe { builder.build_in_bounds_gep(base, &[offset], "") };
+
+    builder.build_pointer_cast(ptr, ty.ptr_type(AddressSpace::Generic), "")
+}
+
+fn build_load_from_memory<'ctx>(builder: &Builder<'ctx>, context: &Context, ty: BasicTypeEnum<'ctx>, base: PointerValue<'ctx>, offset: u64, target_data: &TargetData) -> BasicValueEnum<'ctx> {
+    let layout = get_element_layout(ty, target_data);
+    let mut elements = layout.into_iter()
+        .map(|(element_type, element_offset)| build_load_from_memory(builder, context, element_type, base, offset + element_offset, target_data));
+
+    match ty {
+        BasicTypeEnum::StructType(struct_type) => {
+            elements.enumerate()
+                .fold(AggregateValueEnum::from(struct_type.get_undef()), |agg, (index, element)| {
+                    builder.build_insert_value(agg, element, index as u32, "").unwrap()
+                })
+                .as_basic_value_enum()
+        },
+        BasicTypeEnum::ArrayType(array_type) => {
+            elements.enumerate()
+                .fold(AggregateValueEnum::from(array_type.get_undef()), |agg, (index, element)| {
+                    builder.build_insert_value(agg, element, index as u32, "").unwrap()
+                })
+                .as_basic_value_enum()
+        },
+        BasicTypeEnum::VectorType(vector_type) => {
+            elements.enumerate()
+                .fold(vector_type.get_undef(), |vector, (index, element)| {
+                    builder.build_insert_element(vector, element, context.i32_type().const_int(index as u64, false), "")
+                })
+                .as_basic_value_enum()
+        },
+        _ => {
+            debug_assert!(elements.next().is_none());
+
+            builder.build_load(build_element_ptr(builder, context, ty, base, offset), "")
+        },
+    }
+}
+
+fn build_store_to_memory<'ctx>(builder: &Builder<'ctx>, context: &Context, value: BasicValueEnum<'ctx>, base: PointerValue<'ctx>, offset: u64, target_data: &TargetData) {
+    let ty = value.get_type();
+    let layout = get_element_layout(ty, target_data);
+
+    for (index, (_, element_offset)) in layout.into_iter().enumerate() {
+        let element = match value {
+            BasicValueEnum::ArrayValue(array) => builder.build_extract_value(array, index as u32, "").unwrap(),
+            BasicValueEnum::StructValue(structure) => builder.build_extract_value(structure, index as u32, "").unwrap(),
+            BasicValueEnum::VectorValue(vector) => builder.build_extract_element(vector, context.i32_type().const_int(index as u64, false), ""),
+            _ => unreachable!("Scalar types have no elements"),
+        };
+
+        build_store_to_memory(builder, context, element, base, offset + element_offset, target_data);
+    }
+
+    if !is_aggregate_type(ty) {
+        builder.build_store(build_element_ptr(builder, context, ty, base, offset), value);
+    }
+}
+
 impl Clone for ExecutionEngine<'_> {
     fn clone(&self) -> Self {
         let execution_engine_rc = self.execution_engine_rc().clone();
 
-        unsafe {
+        let mut execution_engine = unsafe {
             ExecutionEngine::new(execution_engine_rc, self.jit_mode)
-        }
+        };
+
+        // Share the registered closures and stub targets rather than starting afresh
+        let inner = self.execution_engine.as_ref().expect(EE_INNER_PANIC);
+        let cloned_inner = execution_engine.execution_engine.as_mut().expect(EE_INNER_PANIC);
+
+        cloned_inner.1 = inner.1.clone();
+        cloned_inner.2 = inner.2.clone();
+
+        execution_engine
     }
 }
 
 /// A smart pointer which wraps the `Drop` logic for `LLVMExecutionEngineRef`.
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct ExecEngineInner<'ctx>(Rc<LLVMExecutionEngineRef>, PhantomData<&'ctx Context>);
+///
+/// The closures registered with `ExecutionEngine::add_closure` and the targets of the stubs created
+/// with `ExecutionEngine::add_indirect_stub` are shared alongside it and dropped after the
+/// `LLVMExecutionEngineRef` is disposed.
+#[derive(Debug, Clone)]
+struct ExecEngineInner<'ctx>(Rc<LLVMExecutionEngineRef>, Rc<JitClosures<'ctx>>, Rc<IndirectStubTargets>, PhantomData<&'ctx Context>);
+
+impl PartialEq for ExecEngineInner<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for ExecEngineInner<'_> {}
 
 impl Drop for ExecEngineInner<'_> {
     fn drop(&mut self) {
@@ -460,6 +1031,25 @@ impl Drop for ExecEngineInner<'_> {
     }
 }
 
+trait Erased {}
+
+impl<T> Erased for T {}
+
+#[derive(Default)]
+struct JitClosures<'ctx>(RefCell<Vec<Box<dyn Erased + 'ctx>>>);
+
+impl Debug for JitClosures<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_tuple("JitClosures")
+            .field(&self.0.borrow().len())
+            .finish()
+    }
+}
+
+/// The addresses which indirect stubs load their current implementation from.
+#[derive(Debug, Default)]
+struct IndirectStubTargets(RefCell<Vec<Box<AtomicUsize>>>);
+
 impl Deref for ExecEngineInner<'_> {
     type Target = LLVMExecutionEngineRef;
 
@@ -468,6 +1058,264 @@ impl Deref for ExecEngineInner<'_> {
     }
 }
 
+/// Options for creating an MCJIT `ExecutionEngine` with `Module::create_mcjit_execution_engine`.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::OptimizationLevel;
+/// use inkwell::context::Context;
+/// use inkwell::execution_engine::MCJITOptions;
+/// use inkwell::targets::CodeModel;
+///
+/// let context = Context::create();
+/// let module = context.create_module("far_away");
+/// let options = MCJITOptions::new()
+///     .opt_level(OptimizationLevel::Aggressive)
+///     .code_model(CodeModel::Large)
+///     .no_frame_pointer_elim(true);
+/// let execution_engine = module.create_mcjit_execution_engine(options).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MCJITOptions {
+    opt_level: OptimizationLevel,
+    code_model: CodeModel,
+    no_frame_pointer_elim: bool,
+    enable_fast_isel: bool,
+    memory_manager: Option<LLVMMCJITMemoryManagerRef>,
+}
+
+impl MCJITOptions {
+    /// Creates `MCJITOptions` with LLVM's defaults: `OptimizationLevel::None`, `CodeModel::JITDefault`,
+    /// frame pointer elimination, no fast instruction selection and LLVM's section memory manager.
+    pub fn new() -> Self {
+        MCJITOptions {
+            opt_level: OptimizationLevel::None,
+            code_model: CodeModel::JITDefault,
+            no_frame_pointer_elim: false,
+            enable_fast_isel: false,
+            memory_manager: None,
+        }
+    }
+
+    /// Sets the code generation `OptimizationLevel`.
+    pub fn opt_level(mut self, opt_level: OptimizationLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    /// Sets the `CodeModel`. `CodeModel::Large` allows JIT code to be located arbitrarily
+    /// far from the symbols it references.
+    pub fn code_model(mut self, code_model: CodeModel) -> Self {
+        self.code_model = code_model;
+        self
+    }
+
+    /// Sets whether frame pointers are kept, which allows JIT frames to be walked without
+    /// unwind information.
+    pub fn no_frame_pointer_elim(mut self, no_frame_pointer_elim: bool) -> Self {
+        self.no_frame_pointer_elim = no_frame_pointer_elim;
+        self
+    }
+
+    /// Sets whether the faster but less optimizing instruction selector is used where possible.
+    pub fn enable_fast_isel(mut self, enable_fast_isel: bool) -> Self {
+        self.enable_fast_isel = enable_fast_isel;
+        self
+    }
+
+    /// Sets a `MCJITMemoryManager` to allocate code and data sections with instead of LLVM's
+    /// section memory manager. It will be owned by the created `ExecutionEngine`.
+    pub fn memory_manager<M: MCJITMemoryManager + 'static>(mut self, memory_manager: M) -> Self {
+        if let Some(memory_manager) = self.memory_manager.take() {
+            unsafe {
+                LLVMDisposeMCJITMemoryManager(memory_manager);
+            }
+        }
+
+        self.memory_manager = Some(create_mcjit_memory_manager(memory_manager));
+        self
+    }
+
+    /// Converts these options into `LLVMMCJITCompilerOptions`, passing ownership of any memory manager.
+    pub(crate) fn into_llvm_options(mut self) -> LLVMMCJITCompilerOptions {
+        let mut options = MaybeUninit::uninit();
+
+        let mut options: LLVMMCJITCompilerOptions = unsafe {
+            LLVMInitializeMCJITCompilerOptions(options.as_mut_ptr(), size_of::<LLVMMCJITCompilerOptions>());
+
+            options.assume_init()
+        };
+
+        options.OptLevel = self.opt_level as u32;
+        options.CodeModel = self.code_model.as_llvm_code_model();
+        options.NoFramePointerElim = self.no_frame_pointer_elim as i32;
+        options.EnableFastISel = self.enable_fast_isel as i32;
+
+        if let Some(memory_manager) = self.memory_manager.take() {
+            options.MCJMM = memory_manager;
+        }
+
+        options
+    }
+}
+
+impl Default for MCJITOptions {
+    fn default() -> Self {
+        MCJITOptions::new()
+    }
+}
+
+impl Drop for MCJITOptions {
+    fn drop(&mut self) {
+        if let Some(memory_manager) = self.memory_manager.take() {
+            unsafe {
+                LLVMDisposeMCJITMemoryManager(memory_manager);
+            }
+        }
+    }
+}
+
+/// A listener which is notified as JIT code is loaded, and so can make it visible to debuggers
+/// and profilers. These are process wide singletons owned by LLVM.
+///
+/// LLVM's C API can only register listeners with ORC JIT stacks, through
+/// `Orc::register_jit_event_listener`. MCJIT `ExecutionEngine`s can't take listeners through it,
+/// though they always register the GDB registration listener themselves.
+#[llvm_versions(8.0..=latest)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitEventListener {
+    pub(crate) listener: LLVMJITEventListenerRef,
+}
+
+#[llvm_versions(8.0..=latest)]
+impl JitEventListener {
+    unsafe fn new(listener: LLVMJITEventListenerRef) -> Option<Self> {
+        if listener.is_null() {
+            return None;
+        }
+
+        Some(JitEventListener { listener })
+    }
+
+    /// Gets the listener which registers JIT code with gdb's JIT interface, so that it can
+    /// be stepped through and symbolized by gdb and lldb. MCJIT `ExecutionEngine`s always
+    /// have this listener registered.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::execution_engine::JitEventListener;
+    ///
+    /// let listener = JitEventListener::create_gdb_registration_listener();
+    /// ```
+    pub fn create_gdb_registration_listener() -> Self {
+        unsafe {
+            JitEventListener::new(LLVMCreateGDBRegistrationListener()).expect("GDB registration listener should always exist")
+        }
+    }
+
+    /// Gets the listener which writes `perf` jitdump files, so that JIT code shows up with
+    /// symbols in `perf report` once injected with `perf inject --jit`. Returns `None` unless
+    /// LLVM was built with `LLVM_USE_PERF`.
+    pub fn create_perf_listener() -> Option<Self> {
+        unsafe {
+            JitEventListener::new(LLVMCreatePerfJITEventListener())
+        }
+    }
+
+    /// Gets the listener which reports JIT code to Intel VTune. Returns `None` unless LLVM was
+    /// built with `LLVM_USE_INTEL_JITEVENTS`.
+    pub fn create_intel_listener() -> Option<Self> {
+        unsafe {
+            JitEventListener::new(LLVMCreateIntelJITEventListener())
+        }
+    }
+
+    /// Gets the listener which reports JIT code to OProfile. Returns `None` unless LLVM was
+    /// built with `LLVM_USE_OPROFILE`.
+    pub fn create_oprofile_listener() -> Option<Self> {
+        unsafe {
+            JitEventListener::new(LLVMCreateOProfileJITEventListener())
+        }
+    }
+}
+
+/// A stable function address, created with `ExecutionEngine::add_indirect_stub`, which forwards calls to
+/// an implementation that can be atomically swapped with `ExecutionEngine::redefine_indirect_stub`.
+///
+/// The stub keeps its `ExecutionEngine` alive, along with the module of its current implementation.
+#[derive(Debug)]
+pub struct IndirectStub<'ctx> {
+    execution_engine: ExecEngineInner<'ctx>,
+    name: String,
+    fn_type: FunctionType<'ctx>,
+    address: usize,
+    // Owned by the ExecutionEngine
+    target: *const AtomicUsize,
+    generation: usize,
+    module: Option<Module<'ctx>>,
+}
+
+impl<'ctx> IndirectStub<'ctx> {
+    /// Gets the stub's symbol name.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the stub's address, which stays the same however often it is redefined.
+    pub fn get_address(&self) -> usize {
+        self.address
+    }
+
+    /// Gets the address of the stub's current implementation.
+    pub fn get_target_address(&self) -> usize {
+        unsafe {
+            (*self.target).load(Ordering::Acquire)
+        }
+    }
+
+    /// Gets the module which defines the stub's current implementation.
+    pub fn get_module(&self) -> &Module<'ctx> {
+        self.module.as_ref().expect("IndirectStub should always have a module")
+    }
+}
+
+// Builds `@name`, with the given type, in stub_module, which loads the address of its implementation from
+// target and tail calls it.
+unsafe fn build_indirect_stub(stub_module: &Module, name: &str, fn_type: FunctionType, target: usize, target_data: &TargetData) {
+    let context = fn_type.get_context();
+    let builder = context.create_builder();
+    let int_ptr_type = context.ptr_sized_int_type(target_data, None);
+    let c_name = to_c_str(name);
+    let stub = LLVMAddFunction(stub_module.module.get(), c_name.as_ptr(), fn_type.as_type_ref());
+    let stub = FunctionValue::new(stub).expect("Stub should be a valid function");
+    let entry = context.append_basic_block(stub, "entry");
+
+    builder.position_at_end(entry);
+
+    let target = int_ptr_type.const_int(target as u64, false).const_to_pointer(int_ptr_type.ptr_type(AddressSpace::Generic));
+    let target_address = builder.build_load(target, "target_address").into_int_value();
+    let load = target_address.as_instruction_value().expect("Load should be an instruction");
+
+    load.set_alignment(target_data.get_abi_alignment(&int_ptr_type)).expect("Load should be aligned");
+
+    #[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7")))]
+    load.set_atomic_ordering(AtomicOrdering::Acquire).expect("Load should be atomic");
+
+    let target = builder.build_int_to_ptr(target_address, fn_type.ptr_type(AddressSpace::Generic), "target");
+    let target = CallableValue::try_from(target).expect("Target should be a function pointer");
+    let args: Vec<BasicMetadataValueEnum> = stub.get_params().into_iter().map(BasicMetadataValueEnum::from).collect();
+    let call = builder.build_call(target, &args, "");
+
+    call.set_tail_call(true);
+
+    match call.try_as_basic_value().left() {
+        Some(value) => builder.build_return(Some(&value)),
+        None => builder.build_return(None),
+    };
+}
+
 /// A wrapper around a function pointer which ensures the function being pointed
 /// to doesn't accidentally outlive its execution engine.
 #[derive(Clone)]
@@ -524,16 +1372,166 @@ macro_rules! impl_unsafe_fn {
 
 impl_unsafe_fn!(A, B, C, D, E, F, G, H, I, J, K, L, M);
 
+/// Marker trait representing a Rust closure (`FnMut(A, B, ...) -> Output`) which can be
+/// called from JIT code once registered with `ExecutionEngine::add_closure`.
+pub trait JitClosure<Args>: private_closure::SealedJitClosure<Args> {}
+
+/// Marker trait representing the FFI-safe scalars a `JitClosure` can take and return: integers,
+/// floats and raw pointers, along with `()` as a return type for `void` functions.
+pub trait JitScalar: private_closure::SealedJitScalar {}
+
+mod private_closure {
+    use crate::targets::TargetData;
+    use crate::types::{BasicTypeEnum, FunctionType};
+
+    /// A sealed trait which ensures nobody outside this crate can implement
+    /// `JitClosure`, and which provides the `extern "C"` trampoline for each closure type.
+    pub trait SealedJitClosure<Args> {
+        const ARITY: u32;
+
+        fn matches_fn_type(fn_type: FunctionType, target_data: &TargetData) -> bool;
+
+        fn trampoline_address() -> usize;
+    }
+
+    /// A sealed trait which ensures nobody outside this crate can implement
+    /// `JitScalar`, and which checks a scalar against the LLVM type it is passed as.
+    pub trait SealedJitScalar {
+        fn matches_type(type_: Option<BasicTypeEnum>, target_data: &TargetData) -> bool;
+    }
+}
+
+impl<Args, F: private_closure::SealedJitClosure<Args>> JitClosure<Args> for F {}
+
+impl<T: private_closure::SealedJitScalar> JitScalar for T {}
+
+impl private_closure::SealedJitScalar for () {
+    fn matches_type(type_: Option<BasicTypeEnum>, _target_data: &TargetData) -> bool {
+        type_.is_none()
+    }
+}
+
+impl<T> private_closure::SealedJitScalar for *const T {
+    fn matches_type(type_: Option<BasicTypeEnum>, _target_data: &TargetData) -> bool {
+        matches!(type_, Some(BasicTypeEnum::PointerType(_)))
+    }
+}
+
+impl<T> private_closure::SealedJitScalar for *mut T {
+    fn matches_type(type_: Option<BasicTypeEnum>, _target_data: &TargetData) -> bool {
+        matches!(type_, Some(BasicTypeEnum::PointerType(_)))
+    }
+}
+
+macro_rules! impl_jit_scalar {
+    ($variant:ident: $( $scalar:ty ),*) => {
+        $(
+            impl private_closure::SealedJitScalar for $scalar {
+                fn matches_type(type_: Option<BasicTypeEnum>, target_data: &TargetData) -> bool {
+                    match type_ {
+                        Some(type_ @ BasicTypeEnum::$variant(_)) => target_data.get_bit_size(&type_) == 8 * size_of::<$scalar>() as u64,
+                        _ => false,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_jit_scalar!(IntType: i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+// No other float types are 32 or 64 bits wide
+impl_jit_scalar!(FloatType: f32, f64);
+
+macro_rules! impl_jit_closure {
+    (@count) => { 0 };
+
+    (@count $first:ident $( , $rest:ident )*) => {
+        1 + impl_jit_closure!(@count $( $rest ),*)
+    };
+
+    (@recurse $first:ident $( , $rest:ident )*) => {
+        impl_jit_closure!($( $rest ),*);
+    };
+
+    (@recurse) => {};
+
+    ($( $param:ident ),*) => {
+        impl<Func, Output, $( $param ),*> private_closure::SealedJitClosure<($( $param, )*)> for Func
+        where
+            Func: FnMut($( $param ),*) -> Output,
+            Output: JitScalar,
+            $( $param: JitScalar ),*
+        {
+            const ARITY: u32 = impl_jit_closure!(@count $( $param ),*);
+
+            fn matches_fn_type(fn_type: FunctionType, target_data: &TargetData) -> bool {
+                #[allow(unused_mut)]
+                let mut param_types = fn_type.get_param_types().into_iter();
+
+                $(
+                    if !<$param as private_closure::SealedJitScalar>::matches_type(param_types.next(), target_data) {
+                        return false;
+                    }
+                )*
+
+                param_types.next().is_none() && <Output as private_closure::SealedJitScalar>::matches_type(fn_type.get_return_type(), target_data)
+            }
+
+            fn trampoline_address() -> usize {
+                #[allow(non_snake_case)]
+                unsafe extern "C" fn trampoline<Func, Output, $( $param ),*>(closure: *mut c_void, $( $param: $param ),*) -> Output
+                where
+                    Func: FnMut($( $param ),*) -> Output,
+                {
+                    let closure = &mut *(closure as *mut Func);
+
+                    abort_on_panic(|| closure($( $param ),*))
+                }
+
+                trampoline::<Func, Output, $( $param ),*> as usize
+            }
+        }
+
+        impl_jit_closure!(@recurse $( $param ),*);
+    };
+}
+
+impl_jit_closure!(A, B, C, D, E, F, G, H, I, J, K, L, M);
+
 #[cfg(all(feature = "experimental", not(any(feature = "llvm3-6", feature = "llvm3-7"))))]
 pub mod experimental {
     use llvm_sys::error::{LLVMErrorRef, LLVMGetErrorTypeId, LLVMConsumeError, LLVMGetErrorMessage, LLVMErrorTypeId};
     use llvm_sys::orc::{LLVMOrcCreateInstance, LLVMOrcDisposeInstance, LLVMOrcJITStackRef, LLVMOrcAddEagerlyCompiledIR, LLVMOrcAddLazilyCompiledIR, LLVMOrcGetErrorMsg, LLVMOrcGetMangledSymbol, LLVMOrcDisposeMangledSymbol};
-
+    #[llvm_versions(7.0..=11.0)]
+    use llvm_sys::error::LLVMDisposeErrorMessage;
+    #[llvm_versions(7.0..=11.0)]
+    use llvm_sys::orc::{LLVMOrcAddObjectFile, LLVMOrcGetSymbolAddress, LLVMOrcModuleHandle, LLVMOrcRemoveModule};
+    #[llvm_versions(8.0..=11.0)]
+    use llvm_sys::orc::{LLVMOrcRegisterJITEventListener, LLVMOrcUnregisterJITEventListener};
+    use llvm_sys::support::LLVMLoadLibraryPermanently;
+    #[llvm_versions(7.0..=11.0)]
+    use llvm_sys::support::LLVMSearchForAddressOfSymbol;
+
+    #[llvm_versions(8.0..=11.0)]
+    use crate::execution_engine::JitEventListener;
+    #[llvm_versions(7.0..=11.0)]
+    use crate::memory_buffer::MemoryBuffer;
     use crate::module::Module;
+    #[llvm_versions(7.0..=11.0)]
+    use crate::object_cache::{compile_with_cache, ObjectCache};
+    #[llvm_versions(7.0..=11.0)]
+    use crate::object_file::parse_archive;
     use crate::support::to_c_str;
+    #[llvm_versions(7.0..=11.0)]
+    use crate::support::LLVMString;
     use crate::targets::TargetMachine;
 
-    use std::mem::MaybeUninit;
+    #[llvm_versions(7.0..=11.0)]
+    use std::error::Error;
+    #[llvm_versions(7.0..=11.0)]
+    use std::fmt::{self, Display, Formatter};
+    use std::mem::{forget, MaybeUninit};
+    use std::ptr;
     use std::ffi::{CStr, CString};
     use std::ops::Deref;
 
@@ -598,6 +1596,14 @@ pub mod experimental {
                 LLVMOrcCreateInstance(target_machine.target_machine)
             };
 
+            // The ORC JIT stack takes ownership of the target machine
+            forget(target_machine);
+
+            // Makes the process' own symbols available to resolve_symbol
+            unsafe {
+                LLVMLoadLibraryPermanently(ptr::null());
+            }
+
             Orc(stack_ref)
         }
 
@@ -625,6 +1631,205 @@ pub mod experimental {
             }
         }
 
+        /// Registers `listener` to be notified of the objects this ORC JIT stack loads.
+        #[llvm_versions(8.0..=11.0)]
+        pub fn register_jit_event_listener(&self, listener: JitEventListener) {
+            unsafe {
+                LLVMOrcRegisterJITEventListener(self.0, listener.listener)
+            }
+        }
+
+        /// Unregisters a `listener` previously registered with `register_jit_event_listener`.
+        #[llvm_versions(8.0..=11.0)]
+        pub fn unregister_jit_event_listener(&self, listener: JitEventListener) {
+            unsafe {
+                LLVMOrcUnregisterJITEventListener(self.0, listener.listener)
+            }
+        }
+
+        /// Loads the compiled `object` into this ORC JIT stack, so that its symbols resolve for code loaded
+        /// afterwards. Its undefined symbols are resolved against the symbols already loaded, followed by
+        /// those of the current process, so its dependencies must be loaded first.
+        ///
+        /// Fails without loading anything if LLVM fails to link `object`, such as when it references
+        /// symbols which cannot be resolved, returning LLVM's own error. LLVM doesn't check whether
+        /// `object` defines symbols which are already loaded, in which case lookups find the definition
+        /// loaded first.
+        #[llvm_versions(7.0..=11.0)]
+        pub fn add_object_file(&self, object: MemoryBuffer) -> Result<OrcModuleHandle, LoadObjectError> {
+            let mut handles = self.add_object_files(vec![object])?;
+
+            Ok(handles.pop().expect("One object should have been loaded"))
+        }
+
+        /// Loads every object file in the `ar` archive `archive` into this ORC JIT stack, as with
+        /// `add_object_files`. Unlike a static linker, members are loaded whether or not they are used.
+        ///
+        /// # Example
+        ///
+        /// ```no_run
+        /// use inkwell::OptimizationLevel;
+        /// use inkwell::execution_engine::experimental::Orc;
+        /// use inkwell::memory_buffer::MemoryBuffer;
+        /// use inkwell::targets::{CodeModel, InitializationConfig, RelocMode, Target, TargetMachine};
+        ///
+        /// use std::path::Path;
+        ///
+        /// Target::initialize_native(&InitializationConfig::default()).unwrap();
+        ///
+        /// let triple = TargetMachine::get_default_triple();
+        /// let target = Target::from_triple(&triple).unwrap();
+        /// let target_machine = target.create_target_machine(&triple, "", "", OptimizationLevel::Default, RelocMode::Default, CodeModel::JITDefault).unwrap();
+        /// let orc = Orc::create(target_machine);
+        /// let runtime = MemoryBuffer::create_from_file(Path::new("libruntime.a")).unwrap();
+        ///
+        /// orc.add_archive(&runtime).unwrap();
+        ///
+        /// let runtime_init = orc.get_symbol_address("runtime_init").unwrap();
+        /// ```
+        #[llvm_versions(7.0..=11.0)]
+        pub fn add_archive(&self, archive: &MemoryBuffer) -> Result<Vec<OrcModuleHandle>, LoadObjectError> {
+            let objects = parse_archive(archive.as_slice())
+                .ok_or(LoadObjectError::InvalidObject)?
+                .into_iter()
+                .map(|(name, contents)| MemoryBuffer::create_from_memory_range_copy(contents, &name))
+                .collect();
+
+            self.add_object_files(objects)
+        }
+
+        /// Loads several compiled `objects`, which may refer to one another, into this ORC JIT stack. Either
+        /// all of them are loaded, or, if any fail to load as described by `add_object_file`, none are.
+        #[llvm_versions(7.0..=11.0)]
+        pub fn add_object_files(&self, objects: Vec<MemoryBuffer>) -> Result<Vec<OrcModuleHandle>, LoadObjectError> {
+            let mut defined_symbols = Vec::new();
+
+            for object in &objects {
+                let object_file = MemoryBuffer::create_from_memory_range(object.as_slice(), "")
+                    .create_object_file()
+                    .map_err(|()| LoadObjectError::InvalidObject)?;
+
+                for symbol in object_file.get_symbols() {
+                    match symbol.get_name() {
+                        Some(name) if !symbol.is_undefined() && !name.to_bytes().is_empty() => defined_symbols.push(name.to_owned()),
+                        _ => {},
+                    }
+                }
+            }
+
+            let mut handles = Vec::with_capacity(objects.len());
+
+            for object in objects {
+                let mut handle = 0;
+                let err = unsafe {
+                    LLVMOrcAddObjectFile(self.0, &mut handle, object.memory_buffer, Some(resolve_symbol), self.0 as *mut libc::c_void)
+                };
+
+                // The ORC JIT stack takes ownership of the object's memory buffer
+                forget(object);
+
+                if !err.is_null() {
+                    self.remove_modules(handles);
+
+                    return Err(LoadObjectError::Llvm(unsafe { error_to_llvm_string(err) }));
+                }
+
+                handles.push(OrcModuleHandle(handle));
+            }
+
+            // Objects are only linked once one of their symbols is looked up, so this is done now in order
+            // to report LLVM's errors linking them, such as for unresolved symbols
+            for symbol in &defined_symbols {
+                let mut address = 0;
+                let err = unsafe {
+                    LLVMOrcGetSymbolAddress(self.0, &mut address, symbol.as_ptr())
+                };
+
+                if !err.is_null() {
+                    self.remove_modules(handles);
+
+                    return Err(LoadObjectError::Llvm(unsafe { error_to_llvm_string(err) }));
+                }
+            }
+
+            Ok(handles)
+        }
+
+        #[llvm_versions(7.0..=11.0)]
+        fn remove_modules(&self, handles: Vec<OrcModuleHandle>) {
+            for handle in handles {
+                // The module is being discarded because of an earlier error, which is the one worth reporting
+                let _ = self.remove_module(handle);
+            }
+        }
+
+        /// Compiles `module` with `target_machine` and loads the resulting object into this ORC JIT stack,
+        /// unless `cache` already holds it, in which case code generation is skipped entirely. This is the
+        /// only way to run cached objects: MCJIT `ExecutionEngine`s can't load them, as LLVM's C API
+        /// neither exposes MCJIT's object cache nor lets objects be added to MCJIT.
+        ///
+        /// # Example
+        ///
+        /// ```no_run
+        /// use inkwell::OptimizationLevel;
+        /// use inkwell::context::Context;
+        /// use inkwell::execution_engine::experimental::Orc;
+        /// use inkwell::object_cache::DirectoryObjectCache;
+        /// use inkwell::targets::{CodeModel, InitializationConfig, RelocMode, Target, TargetMachine};
+        ///
+        /// Target::initialize_native(&InitializationConfig::default()).unwrap();
+        ///
+        /// let triple = TargetMachine::get_default_triple();
+        /// let target = Target::from_triple(&triple).unwrap();
+        /// let create_target_machine = || target.create_target_machine(&triple, "", "", OptimizationLevel::Default, RelocMode::Default, CodeModel::JITDefault).unwrap();
+        /// let orc = Orc::create(create_target_machine());
+        /// let context = Context::create();
+        /// let module = context.create_module("cached");
+        /// let mut cache = DirectoryObjectCache::new("/tmp/inkwell-object-cache").unwrap();
+        ///
+        /// orc.add_module_with_object_cache(&module, &create_target_machine(), &mut cache).unwrap();
+        /// ```
+        #[llvm_versions(7.0..=11.0)]
+        pub fn add_module_with_object_cache<'ctx>(&self, module: &Module<'ctx>, target_machine: &TargetMachine, cache: &mut dyn ObjectCache) -> Result<OrcModuleHandle, LoadObjectError> {
+            let object = compile_with_cache(cache, module, target_machine).map_err(LoadObjectError::Llvm)?;
+
+            self.add_object_file(object)
+        }
+
+        /// Unloads the object or module identified by `handle`.
+        #[llvm_versions(7.0..=11.0)]
+        pub fn remove_module(&self, handle: OrcModuleHandle) -> Result<(), LLVMString> {
+            let err = unsafe {
+                LLVMOrcRemoveModule(self.0, handle.0)
+            };
+
+            if !err.is_null() {
+                return Err(unsafe { error_to_llvm_string(err) });
+            }
+
+            Ok(())
+        }
+
+        /// Gets the address of the loaded symbol `symbol`, before mangling.
+        #[llvm_versions(7.0..=11.0)]
+        pub fn get_symbol_address(&self, symbol: &str) -> Result<usize, LLVMString> {
+            let mangled_symbol = self.get_mangled_symbol(symbol);
+            let mut address = 0;
+            let err = unsafe {
+                LLVMOrcGetSymbolAddress(self.0, &mut address, mangled_symbol.as_ptr())
+            };
+
+            if !err.is_null() {
+                return Err(unsafe { error_to_llvm_string(err) });
+            }
+
+            if address == 0 {
+                return Err(LLVMString::create_from_str(&format!("Symbol not found: {}", symbol)));
+            }
+
+            Ok(address as usize)
+        }
+
         pub fn get_mangled_symbol(&self, symbol: &str) -> MangledSymbol {
             let mut mangled_symbol = MaybeUninit::uninit();
             let c_symbol = to_c_str(symbol);
@@ -635,6 +1840,65 @@ pub mod experimental {
         }
     }
 
+    /// Identifies an object or module loaded into an `Orc` JIT stack.
+    #[llvm_versions(7.0..=11.0)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct OrcModuleHandle(LLVMOrcModuleHandle);
+
+    /// An error loading object files into an `Orc` JIT stack.
+    #[llvm_versions(7.0..=11.0)]
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum LoadObjectError {
+        /// The buffer does not hold a valid object file or archive.
+        InvalidObject,
+        /// LLVM failed to compile, load or link an object.
+        Llvm(LLVMString),
+    }
+
+    #[llvm_versions(7.0..=11.0)]
+    impl Error for LoadObjectError {}
+
+    #[llvm_versions(7.0..=11.0)]
+    impl Display for LoadObjectError {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            match self {
+                LoadObjectError::InvalidObject => write!(f, "LoadObjectError(Invalid object file or archive)"),
+                LoadObjectError::Llvm(string) => write!(f, "LoadObjectError({})", string.to_string()),
+            }
+        }
+    }
+
+    // Consumes err
+    #[llvm_versions(7.0..=11.0)]
+    unsafe fn error_to_llvm_string(err: LLVMErrorRef) -> LLVMString {
+        let message = LLVMGetErrorMessage(err);
+        let string = LLVMString::create_from_c_str(CStr::from_ptr(message));
+
+        LLVMDisposeErrorMessage(message);
+
+        string
+    }
+
+    #[llvm_versions(7.0..=11.0)]
+    extern "C" fn resolve_symbol(name: *const libc::c_char, stack: *mut libc::c_void) -> u64 {
+        let mut address = 0;
+        let err = unsafe {
+            LLVMOrcGetSymbolAddress(stack as LLVMOrcJITStackRef, &mut address, name)
+        };
+
+        if !err.is_null() {
+            drop(LLVMError(err));
+        }
+
+        if address != 0 {
+            return address;
+        }
+
+        unsafe {
+            LLVMSearchForAddressOfSymbol(name) as u64
+        }
+    }
+
     impl Drop for Orc {
         fn drop(&mut self) {
             // REVIEW: This returns an LLVMErrorRef, not sure what we can do with it...
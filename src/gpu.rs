@@ -0,0 +1,153 @@
+//! Helpers for emitting GPU kernels for the NVPTX and AMDGPU targets.
+//!
+//! Both targets number their address spaces alike, so the same constants serve either. Only code
+//! generation is involved, so no GPU is needed to emit PTX or AMDGPU code objects.
+
+use llvm_sys::core::LLVMAddNamedMetadataOperand;
+
+#[llvm_versions(3.9..=latest)]
+use crate::attributes::AttributeLoc;
+use crate::builder::Builder;
+use crate::context::Context;
+use crate::module::Module;
+use crate::support::to_c_str;
+use crate::values::{AsValueRef, FunctionValue, IntValue};
+use crate::AddressSpace;
+
+/// The address space of memory visible to every thread and the host.
+pub const GLOBAL_ADDRESS_SPACE: AddressSpace = AddressSpace::Global;
+/// The address space of memory shared by the threads of a block, also known as local data share.
+pub const SHARED_ADDRESS_SPACE: AddressSpace = AddressSpace::Shared;
+/// The address space of read only memory visible to every thread.
+pub const CONSTANT_ADDRESS_SPACE: AddressSpace = AddressSpace::Const;
+/// The address space of memory private to a single thread, such as its stack.
+pub const PRIVATE_ADDRESS_SPACE: AddressSpace = AddressSpace::Local;
+
+const AMDGPU_KERNEL_CALL_CONV: u32 = 91;
+
+/// A GPU architecture to emit kernels for.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum GpuTarget {
+    /// NVIDIA GPUs, as targeted by "nvptx64-nvidia-cuda".
+    Nvptx,
+    /// AMD GPUs, as targeted by "amdgcn-amd-amdhsa".
+    AmdGpu,
+}
+
+/// A dimension of the grid of blocks, or of the threads within a block.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Dimension {
+    /// The first dimension, which is the only one used by one dimensional launches.
+    X,
+    /// The second dimension.
+    Y,
+    /// The third dimension.
+    Z,
+}
+
+impl Dimension {
+    fn get_suffix(self) -> &'static str {
+        match self {
+            Dimension::X => "x",
+            Dimension::Y => "y",
+            Dimension::Z => "z",
+        }
+    }
+}
+
+impl GpuTarget {
+    /// Marks `function`, which must belong to `module`, as a kernel which the host can launch.
+    /// Kernels must return void.
+    ///
+    /// For NVPTX this adds `function` to the module's "nvvm.annotations" named metadata, and for
+    /// AMDGPU it gives `function` the `amdgpu_kernel` calling convention.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::gpu::{Dimension, GpuTarget, GLOBAL_ADDRESS_SPACE};
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("kernels");
+    /// let builder = context.create_builder();
+    /// let i32_type = context.i32_type();
+    /// let fn_type = context.void_type().fn_type(&[i32_type.ptr_type(GLOBAL_ADDRESS_SPACE).into()], false);
+    /// let kernel = module.add_function("write_thread_ids", fn_type, None);
+    ///
+    /// GpuTarget::Nvptx.mark_kernel(&module, kernel);
+    /// builder.position_at_end(context.append_basic_block(kernel, "entry"));
+    ///
+    /// let thread_index = GpuTarget::Nvptx.build_thread_index(&context, &module, &builder, Dimension::X);
+    /// let out = kernel.get_first_param().unwrap().into_pointer_value();
+    /// let element = unsafe { builder.build_gep(out, &[thread_index], "element") };
+    ///
+    /// builder.build_store(element, thread_index);
+    /// builder.build_return(None);
+    /// ```
+    pub fn mark_kernel<'ctx>(self, module: &Module<'ctx>, function: FunctionValue<'ctx>) {
+        match self {
+            GpuTarget::Nvptx => {
+                let context = module.get_context();
+                let annotation = context.metadata_node(&[
+                    function.as_global_value().as_pointer_value().into(),
+                    context.metadata_string("kernel").into(),
+                    context.i32_type().const_int(1, false).into(),
+                ]);
+                let key = to_c_str("nvvm.annotations");
+
+                unsafe {
+                    LLVMAddNamedMetadataOperand(module.module.get(), key.as_ptr(), annotation.as_value_ref());
+                }
+            },
+            GpuTarget::AmdGpu => function.set_call_conventions(AMDGPU_KERNEL_CALL_CONV),
+        }
+    }
+
+    /// Builds a call to the intrinsic reading the index of the current thread within its block
+    /// along `dimension`, declaring the intrinsic in `module`, which must belong to `context`, if
+    /// needed. The index is an `i32`.
+    pub fn build_thread_index<'ctx>(self, context: &'ctx Context, module: &Module<'ctx>, builder: &Builder<'ctx>, dimension: Dimension) -> IntValue<'ctx> {
+        let intrinsic = match self {
+            GpuTarget::Nvptx => "llvm.nvvm.read.ptx.sreg.tid.",
+            GpuTarget::AmdGpu => "llvm.amdgcn.workitem.id.",
+        };
+
+        build_index_intrinsic_call(context, module, builder, intrinsic, dimension, "thread_index")
+    }
+
+    /// Builds a call to the intrinsic reading the index of the current block within the grid
+    /// along `dimension`, declaring the intrinsic in `module`, which must belong to `context`, if
+    /// needed. The index is an `i32`.
+    pub fn build_block_index<'ctx>(self, context: &'ctx Context, module: &Module<'ctx>, builder: &Builder<'ctx>, dimension: Dimension) -> IntValue<'ctx> {
+        let intrinsic = match self {
+            GpuTarget::Nvptx => "llvm.nvvm.read.ptx.sreg.ctaid.",
+            GpuTarget::AmdGpu => "llvm.amdgcn.workgroup.id.",
+        };
+
+        build_index_intrinsic_call(context, module, builder, intrinsic, dimension, "block_index")
+    }
+}
+
+/// Sets the range of threads per block `function`, an AMDGPU kernel, may be launched with, which
+/// bounds the registers it may use. LLVM assumes at most 1024 threads otherwise.
+#[llvm_versions(3.9..=latest)]
+pub fn set_amdgpu_flat_work_group_size(function: FunctionValue, min: u32, max: u32) {
+    let context = function.get_type().get_context();
+    let attribute = context.create_string_attribute("amdgpu-flat-work-group-size", &format!("{},{}", min, max));
+
+    function.add_attribute(AttributeLoc::Function, attribute);
+}
+
+fn build_index_intrinsic_call<'ctx>(context: &'ctx Context, module: &Module<'ctx>, builder: &Builder<'ctx>, intrinsic: &str, dimension: Dimension, name: &str) -> IntValue<'ctx> {
+    let intrinsic = format!("{}{}", intrinsic, dimension.get_suffix());
+    let function = module
+        .get_function(&intrinsic)
+        .unwrap_or_else(|| module.add_function(&intrinsic, context.i32_type().fn_type(&[], false), None));
+
+    builder.build_call(function, &[], name)
+        .try_as_basic_value()
+        .left()
+        .expect("Index intrinsics return an i32")
+        .into_int_value()
+}
@@ -24,18 +24,35 @@ pub mod attributes;
 pub mod comdat;
 #[deny(missing_docs)]
 pub mod basic_block;
+#[deny(missing_docs)]
+#[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7", feature = "llvm3-8", feature = "llvm3-9",
+              feature = "llvm4-0", feature = "llvm5-0", feature = "llvm6-0", feature = "llvm7-0")))]
+pub mod bpf;
 pub mod builder;
 #[deny(missing_docs)]
+#[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7", feature = "llvm3-8")))]
+pub mod call_graph;
+#[deny(missing_docs)]
 pub mod context;
 pub mod data_layout;
 #[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7", feature = "llvm3-8", feature = "llvm3-9", feature = "llvm4-0", feature = "llvm5-0", feature = "llvm6-0")))]
 pub mod debug_info;
 pub mod execution_engine;
+#[deny(missing_docs)]
+pub mod gpu;
 pub mod memory_buffer;
 #[deny(missing_docs)]
+pub mod memory_manager;
+#[deny(missing_docs)]
 pub mod module;
+#[deny(missing_docs)]
+pub mod object_cache;
 pub mod object_file;
 pub mod passes;
+#[deny(missing_docs)]
+pub mod pattern_match;
+#[deny(missing_docs)]
+pub mod symbolizer;
 pub mod targets;
 pub mod types;
 pub mod values;
@@ -77,6 +94,8 @@ use llvm_sys::LLVMInlineAsmDialect;
 
 use std::convert::TryFrom;
 
+pub use inkwell_internals::ir;
+
 // Thanks to kennytm for coming up with assert_unique_features!
 // which ensures that the LLVM feature flags are mutually exclusive
 macro_rules! assert_unique_features {
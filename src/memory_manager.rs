@@ -0,0 +1,150 @@
+//! Custom memory managers for MCJIT `ExecutionEngine`s.
+//!
+//! By default MCJIT allocates code and data sections with LLVM's own section memory manager.
+//! Implementing `MCJITMemoryManager` allows the JIT's memory to be placed, protected and
+//! accounted for by Rust code instead, via `MCJITOptions::memory_manager`.
+
+use libc::{c_char, c_uint, c_void};
+use llvm_sys::LLVMBool;
+use llvm_sys::core::LLVMCreateMessage;
+use llvm_sys::execution_engine::{LLVMCreateSimpleMCJITMemoryManager, LLVMMCJITMemoryManagerRef};
+
+use crate::support::{abort_on_panic, to_c_str};
+
+use std::borrow::Cow;
+use std::ffi::CStr;
+
+/// Allocates and finalizes the memory MCJIT loads compiled code and data into.
+///
+/// The memory manager is owned by the `ExecutionEngine` it is created with and dropped, along with
+/// any memory it chooses to release, once that `ExecutionEngine` is disposed. A panic in any of these
+/// callbacks aborts the process, since it cannot unwind through LLVM.
+///
+/// # Safety
+///
+/// LLVM writes code, data and relocations through the pointers the allocation methods return. Each
+/// non null pointer must be valid for writes of at least `size` bytes, aligned to `alignment`, not
+/// overlap any other allocation, and stay valid until the memory manager is dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::memory_manager::MCJITMemoryManager;
+///
+/// // Leaks its memory and never makes it executable, so only useful for accounting
+/// #[derive(Default)]
+/// struct CountingMemoryManager {
+///     allocated: usize,
+/// }
+///
+/// unsafe impl MCJITMemoryManager for CountingMemoryManager {
+///     fn allocate_code_section(&mut self, size: usize, alignment: u32, _section_id: u32, _section_name: &str) -> *mut u8 {
+///         self.allocate(size, alignment)
+///     }
+///
+///     fn allocate_data_section(&mut self, size: usize, alignment: u32, _section_id: u32, _section_name: &str, _is_read_only: bool) -> *mut u8 {
+///         self.allocate(size, alignment)
+///     }
+///
+///     fn finalize_memory(&mut self) -> Result<(), String> {
+///         Ok(())
+///     }
+/// }
+///
+/// impl CountingMemoryManager {
+///     fn allocate(&mut self, size: usize, alignment: u32) -> *mut u8 {
+///         let layout = std::alloc::Layout::from_size_align(size.max(1), alignment.max(1) as usize).unwrap();
+///
+///         self.allocated += size;
+///
+///         unsafe { std::alloc::alloc_zeroed(layout) }
+///     }
+/// }
+/// ```
+pub unsafe trait MCJITMemoryManager {
+    /// Allocates `size` bytes, aligned to `alignment`, for the code section `section_name`. Returning
+    /// null fails the allocation.
+    fn allocate_code_section(&mut self, size: usize, alignment: u32, section_id: u32, section_name: &str) -> *mut u8;
+
+    /// Allocates `size` bytes, aligned to `alignment`, for the data section `section_name`. Returning
+    /// null fails the allocation.
+    fn allocate_data_section(&mut self, size: usize, alignment: u32, section_id: u32, section_name: &str, is_read_only: bool) -> *mut u8;
+
+    /// Called once relocations have been applied, before any code is run, to apply the final
+    /// permissions to the allocated memory, such as making code sections executable and no
+    /// longer writable. May be called again after later modules are loaded.
+    fn finalize_memory(&mut self) -> Result<(), String>;
+}
+
+/// Creates an `LLVMMCJITMemoryManagerRef` which forwards to `memory_manager`, taking ownership of it
+/// until LLVM destroys the `LLVMMCJITMemoryManagerRef`.
+pub(crate) fn create_mcjit_memory_manager<M: MCJITMemoryManager + 'static>(memory_manager: M) -> LLVMMCJITMemoryManagerRef {
+    let opaque = Box::into_raw(Box::new(memory_manager)) as *mut c_void;
+
+    unsafe {
+        LLVMCreateSimpleMCJITMemoryManager(
+            opaque,
+            allocate_code_section::<M>,
+            allocate_data_section::<M>,
+            finalize_memory::<M>,
+            Some(destroy::<M>),
+        )
+    }
+}
+
+fn section_name<'a>(section_name: *const c_char) -> Cow<'a, str> {
+    if section_name.is_null() {
+        return Cow::Borrowed("");
+    }
+
+    unsafe {
+        CStr::from_ptr(section_name).to_string_lossy()
+    }
+}
+
+extern "C" fn allocate_code_section<M: MCJITMemoryManager>(
+    opaque: *mut c_void,
+    size: usize,
+    alignment: c_uint,
+    section_id: c_uint,
+    name: *const c_char,
+) -> *mut u8 {
+    let memory_manager = unsafe { &mut *(opaque as *mut M) };
+
+    abort_on_panic(|| memory_manager.allocate_code_section(size, alignment, section_id, &section_name(name)))
+}
+
+extern "C" fn allocate_data_section<M: MCJITMemoryManager>(
+    opaque: *mut c_void,
+    size: usize,
+    alignment: c_uint,
+    section_id: c_uint,
+    name: *const c_char,
+    is_read_only: LLVMBool,
+) -> *mut u8 {
+    let memory_manager = unsafe { &mut *(opaque as *mut M) };
+
+    abort_on_panic(|| memory_manager.allocate_data_section(size, alignment, section_id, &section_name(name), is_read_only != 0))
+}
+
+extern "C" fn finalize_memory<M: MCJITMemoryManager>(opaque: *mut c_void, err_msg: *mut *mut c_char) -> LLVMBool {
+    let memory_manager = unsafe { &mut *(opaque as *mut M) };
+
+    match abort_on_panic(|| memory_manager.finalize_memory()) {
+        Ok(()) => 0,
+        Err(message) => {
+            // LLVM frees the message with free(), so it must be allocated by LLVM
+            if !err_msg.is_null() {
+                unsafe {
+                    *err_msg = LLVMCreateMessage(to_c_str(&message).as_ptr());
+                }
+            }
+
+            1
+        },
+    }
+}
+
+extern "C" fn destroy<M: MCJITMemoryManager>(opaque: *mut c_void) {
+    abort_on_panic(|| unsafe { drop(Box::from_raw(opaque as *mut M)) })
+}
@@ -9,7 +9,7 @@ use llvm_sys::core::{LLVMAddFunction, LLVMAddGlobal, LLVMDumpModule, LLVMGetName
 use llvm_sys::core::{LLVMGetModuleIdentifier, LLVMSetModuleIdentifier};
 #[llvm_versions(7.0..=latest)]
 use llvm_sys::core::{LLVMGetModuleFlag, LLVMAddModuleFlag};
-use llvm_sys::execution_engine::{LLVMCreateInterpreterForModule, LLVMCreateJITCompilerForModule, LLVMCreateExecutionEngineForModule};
+use llvm_sys::execution_engine::{LLVMCreateInterpreterForModule, LLVMCreateJITCompilerForModule, LLVMCreateExecutionEngineForModule, LLVMCreateMCJITCompilerForModule, LLVMMCJITCompilerOptions};
 use llvm_sys::prelude::{LLVMModuleRef, LLVMValueRef};
 use llvm_sys::LLVMLinkage;
 #[llvm_versions(7.0..=latest)]
@@ -17,9 +17,10 @@ use llvm_sys::LLVMModuleFlagBehavior;
 
 use std::cell::{Cell, RefCell, Ref};
 use std::ffi::CStr;
+use std::fmt::Write;
 use std::fs::File;
 use std::marker::PhantomData;
-use std::mem::{forget, MaybeUninit};
+use std::mem::{forget, size_of, MaybeUninit};
 use std::path::Path;
 use std::ptr;
 use std::rc::Rc;
@@ -31,12 +32,14 @@ use crate::context::{Context, ContextRef};
 use crate::data_layout::DataLayout;
 #[llvm_versions(7.0..=latest)]
 use crate::debug_info::{DebugInfoBuilder, DICompileUnit, DWARFEmissionKind, DWARFSourceLanguage};
-use crate::execution_engine::ExecutionEngine;
+use crate::execution_engine::{ExecutionEngine, MCJITOptions};
 use crate::memory_buffer::MemoryBuffer;
-use crate::support::{to_c_str, LLVMString};
+use crate::support::{escape_dot_string, to_c_str, LLVMString};
 use crate::targets::{InitializationConfig, Target, TargetTriple};
+#[llvm_versions(8.0..=latest)]
+use crate::targets::WasmFeature;
 use crate::types::{AsTypeRef, BasicType, FunctionType, StructType};
-use crate::values::{AsValueRef, FunctionValue, GlobalValue, MetadataValue};
+use crate::values::{AsValueRef, FunctionValue, GlobalValue, InstVisitor, MetadataValue};
 #[llvm_versions(7.0..=latest)]
 use crate::values::BasicValue;
 
@@ -537,6 +540,64 @@ impl<'ctx> Module<'ctx> {
         Ok(execution_engine)
     }
 
+    /// Creates an MCJIT `ExecutionEngine` from this `Module` with the given `MCJITOptions`, such as
+    /// its `CodeModel` and whether frame pointers are kept.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use inkwell::OptimizationLevel;
+    /// use inkwell::context::Context;
+    /// use inkwell::execution_engine::MCJITOptions;
+    /// use inkwell::targets::CodeModel;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("my_module");
+    /// let options = MCJITOptions::new()
+    ///     .opt_level(OptimizationLevel::Default)
+    ///     .code_model(CodeModel::Large)
+    ///     .no_frame_pointer_elim(true);
+    /// let execution_engine = module.create_mcjit_execution_engine(options).unwrap();
+    ///
+    /// assert_eq!(*module.get_context(), context);
+    /// ```
+    // SubType: ExecutionEngine<Jit>
+    pub fn create_mcjit_execution_engine(&self, options: MCJITOptions) -> Result<ExecutionEngine<'ctx>, LLVMString> {
+        Target::initialize_native(&InitializationConfig::default())
+            .map_err(|mut err_string| {
+                err_string.push('\0');
+
+                LLVMString::create_from_str(&err_string)
+            })?;
+
+        if self.owned_by_ee.borrow().is_some() {
+            let string = "This module is already owned by an ExecutionEngine.\0";
+            return Err(LLVMString::create_from_str(string));
+        }
+
+        // Any memory manager is owned by the ExecutionEngine, even if creating it fails
+        let mut options = options.into_llvm_options();
+        let mut execution_engine = MaybeUninit::uninit();
+        let mut err_string = MaybeUninit::uninit();
+
+        let code = unsafe {
+            // Takes ownership of module
+            LLVMCreateMCJITCompilerForModule(execution_engine.as_mut_ptr(), self.module.get(), &mut options, size_of::<LLVMMCJITCompilerOptions>(), err_string.as_mut_ptr())
+        };
+
+        if code == 1 {
+            unsafe {
+                return Err(LLVMString::new(err_string.assume_init()));
+            }
+        }
+
+        let execution_engine = unsafe { execution_engine.assume_init() };
+        let execution_engine = unsafe { ExecutionEngine::new(Rc::new(execution_engine), true) };
+
+        *self.owned_by_ee.borrow_mut() = Some(execution_engine.clone());
+
+        Ok(execution_engine)
+    }
+
     /// Creates a `GlobalValue` based on a type in an address space.
     ///
     /// # Example
@@ -771,6 +832,113 @@ impl<'ctx> Module<'ctx> {
         Ok(())
     }
 
+    /// Prints the control flow graphs of every function defined in the `Module` in the DOT (graphviz)
+    /// format, with every basic block listing its instructions. Each function is drawn as its own
+    /// cluster. See `FunctionValue::cfg_to_dot` for how edges are labeled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("my_module");
+    /// let builder = context.create_builder();
+    /// let void_type = context.void_type();
+    /// let fn_type = void_type.fn_type(&[], false);
+    /// let function = module.add_function("my_fn", fn_type, None);
+    ///
+    /// builder.position_at_end(context.append_basic_block(function, "entry"));
+    /// builder.build_return(None);
+    ///
+    /// let dot = module.cfg_to_dot();
+    ///
+    /// assert!(dot.contains("subgraph cluster_f0 {"));
+    /// assert!(dot.contains("f0b0 [label=\"entry:\\l  ret void\\l\"];"));
+    /// ```
+    #[llvm_versions(3.9..=latest)]
+    pub fn cfg_to_dot(&self) -> String {
+        self.cfg_to_dot_string(true)
+    }
+
+    /// Prints the control flow graphs of every function defined in the `Module` in the DOT (graphviz)
+    /// format, with basic blocks labeled only by their names. See `cfg_to_dot` for details.
+    #[llvm_versions(3.9..=latest)]
+    pub fn cfg_only_to_dot(&self) -> String {
+        self.cfg_to_dot_string(false)
+    }
+
+    #[llvm_versions(3.9..=latest)]
+    fn cfg_to_dot_string(&self, include_instructions: bool) -> String {
+        let mut dot = String::new();
+        let name = self.get_name().to_string_lossy();
+
+        writeln!(dot, "digraph \"CFG for '{}' module\" {{", escape_dot_string(&name)).unwrap();
+        writeln!(dot, "    node [shape=box];").unwrap();
+
+        let mut function = self.get_first_function();
+        let mut index = 0;
+
+        while let Some(current_function) = function {
+            if current_function.count_basic_blocks() > 0 {
+                let function_name = current_function.get_name().to_string_lossy();
+
+                writeln!(dot, "    subgraph cluster_f{} {{", index).unwrap();
+                writeln!(dot, "        label=\"{}\";", escape_dot_string(&function_name)).unwrap();
+
+                current_function.write_cfg_dot(&mut dot, &format!("f{}", index), "        ", include_instructions);
+
+                dot.push_str("    }\n");
+            }
+
+            function = current_function.get_next_function();
+            index += 1;
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Walks over every instruction of every function in the `Module`, calling the corresponding
+    /// hooks of `visitor`. See `FunctionValue::visit` for details.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::values::{FunctionValue, InstVisitor};
+    ///
+    /// struct FunctionCounter(usize);
+    ///
+    /// impl<'ctx> InstVisitor<'ctx> for FunctionCounter {
+    ///     fn visit_function(&mut self, _function: FunctionValue<'ctx>) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("my_module");
+    /// let void_type = context.void_type();
+    /// let fn_type = void_type.fn_type(&[], false);
+    ///
+    /// module.add_function("my_fn", fn_type, None);
+    ///
+    /// let mut counter = FunctionCounter(0);
+    ///
+    /// module.visit(&mut counter);
+    ///
+    /// assert_eq!(counter.0, 1);
+    /// ```
+    pub fn visit<V: InstVisitor<'ctx>>(&self, visitor: &mut V) {
+        let mut function = self.get_first_function();
+
+        while let Some(current_function) = function {
+            current_function.visit(visitor);
+
+            function = current_function.get_next_function();
+        }
+    }
+
     /// Sets the inline assembly for the `Module`.
     pub fn set_inline_assembly(&self, asm: &str) {
         #[cfg(any(feature = "llvm3-6", feature = "llvm3-7", feature = "llvm3-8", feature = "llvm3-9",
@@ -1400,6 +1568,66 @@ impl<'ctx> Module<'ctx> {
                               sdk
         )
     }
+
+    /// Enables `features` for every function defined in this `Module` when compiled to
+    /// WebAssembly. Functions added afterwards are unaffected.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::targets::WasmFeature;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("wasm");
+    ///
+    /// // Add functions to module here
+    ///
+    /// module.set_wasm_target_features(&[WasmFeature::Simd128, WasmFeature::BulkMemory]);
+    /// ```
+    #[llvm_versions(8.0..=latest)]
+    pub fn set_wasm_target_features(&self, features: &[WasmFeature]) {
+        let mut function = self.get_first_function();
+
+        while let Some(current) = function {
+            if current.count_basic_blocks() > 0 {
+                current.set_wasm_target_features(features);
+            }
+
+            function = current.get_next_function();
+        }
+    }
+
+    /// Adds a custom section holding `contents` to the WebAssembly object this `Module` compiles to.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("wasm");
+    ///
+    /// module.add_wasm_custom_section("build_id", b"0123456789abcdef");
+    /// ```
+    #[llvm_versions(8.0..=latest)]
+    pub fn add_wasm_custom_section(&self, name: &str, contents: &[u8]) {
+        use llvm_sys::core::{LLVMMDNodeInContext, LLVMMDStringInContext};
+
+        // The WebAssembly backend emits a section for each name and contents pair in this metadata
+        let key = to_c_str("wasm.custom_sections");
+
+        unsafe {
+            let context = LLVMGetModuleContext(self.module.get());
+            let mut strings = [
+                LLVMMDStringInContext(context, name.as_ptr() as *const ::libc::c_char, name.len() as u32),
+                LLVMMDStringInContext(context, contents.as_ptr() as *const ::libc::c_char, contents.len() as u32),
+            ];
+            let node = LLVMMDNodeInContext(context, strings.as_mut_ptr(), strings.len() as u32);
+
+            LLVMAddNamedMetadataOperand(self.module.get(), key.as_ptr(), node);
+        }
+    }
 }
 
 impl Clone for Module<'_> {
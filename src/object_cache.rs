@@ -0,0 +1,183 @@
+//! Persistent caches of the object files a `TargetMachine` compiles modules to.
+//!
+//! An `ObjectCache` stores objects by an `ObjectCacheKey`, derived from a module's bitcode and the
+//! target it is compiled for, so that `compile_with_cache` can hand back a previously emitted
+//! object instead of running code generation again. This is a cache for ahead of time
+//! compilation, not a general JIT cache.
+//!
+//! The only JIT able to load cached objects is the experimental legacy ORC stack on LLVM 7 to 11,
+//! through `Orc::add_module_with_object_cache`. MCJIT `ExecutionEngine`s always compile their
+//! modules themselves: LLVM's C API neither exposes MCJIT's object cache hook nor lets objects be
+//! added to MCJIT, so an `ObjectCache` cannot speed them up.
+
+use crate::memory_buffer::MemoryBuffer;
+use crate::module::Module;
+use crate::support::LLVMString;
+use crate::targets::{FileType, TargetMachine};
+
+use llvm_sys::target_machine::{LLVMGetTargetMachineCPU, LLVMGetTargetMachineFeatureString};
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+const FNV_OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+/// Identifies the object a module compiles to. Keys are stable across runs and builds of Inkwell,
+/// so they may be persisted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjectCacheKey(u128);
+
+impl ObjectCacheKey {
+    /// Creates the key for `module` compiled by `target_machine`. It covers the module's bitcode, as
+    /// given by `Module::write_bitcode_to_memory`, and the target machine's triple, CPU, features,
+    /// optimization level, relocation mode and code model.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::OptimizationLevel;
+    /// use inkwell::context::Context;
+    /// use inkwell::object_cache::ObjectCacheKey;
+    /// use inkwell::targets::{CodeModel, InitializationConfig, RelocMode, Target, TargetMachine};
+    ///
+    /// Target::initialize_native(&InitializationConfig::default()).unwrap();
+    ///
+    /// let triple = TargetMachine::get_default_triple();
+    /// let target = Target::from_triple(&triple).unwrap();
+    /// let target_machine = target.create_target_machine(&triple, "", "", OptimizationLevel::Default, RelocMode::Default, CodeModel::JITDefault).unwrap();
+    /// let context = Context::create();
+    /// let module = context.create_module("cached");
+    ///
+    /// let key = ObjectCacheKey::new(&module, &target_machine);
+    ///
+    /// assert_eq!(key, ObjectCacheKey::new(&module, &target_machine));
+    /// ```
+    pub fn new(module: &Module, target_machine: &TargetMachine) -> Self {
+        let (cpu, features) = unsafe {
+            (
+                LLVMString::new(LLVMGetTargetMachineCPU(target_machine.target_machine)),
+                LLVMString::new(LLVMGetTargetMachineFeatureString(target_machine.target_machine)),
+            )
+        };
+        let bitcode = module.write_bitcode_to_memory();
+        let mut hash = FNV_OFFSET_BASIS;
+
+        // Each field is length prefixed so that they cannot run into one another
+        for field in &[
+            bitcode.as_slice(),
+            target_machine.get_triple().as_str().to_bytes(),
+            cpu.as_str().to_bytes(),
+            features.as_str().to_bytes(),
+            &[target_machine.opt_level as u8, target_machine.reloc_mode as u8, target_machine.code_model as u8],
+        ] {
+            for byte in (field.len() as u64).to_le_bytes().iter().chain(field.iter()) {
+                hash ^= *byte as u128;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+
+        ObjectCacheKey(hash)
+    }
+}
+
+impl Display for ObjectCacheKey {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// Stores and reloads the objects modules compile to, as used by `compile_with_cache`.
+pub trait ObjectCache {
+    /// Gets the object previously stored under `key`, if any.
+    fn get_object(&mut self, key: &ObjectCacheKey) -> Option<MemoryBuffer>;
+
+    /// Called with each newly compiled object, which should be stored under `key`.
+    fn notify_object_compiled(&mut self, key: &ObjectCacheKey, object: &MemoryBuffer);
+}
+
+/// An `ObjectCache` which stores each object in a file, named after its key, in a directory.
+///
+/// Failing to write an object is not an error, as it will simply be recompiled next time.
+#[derive(Debug)]
+pub struct DirectoryObjectCache {
+    directory: PathBuf,
+}
+
+impl DirectoryObjectCache {
+    /// Creates a cache in `directory`, creating the directory if it doesn't already exist.
+    pub fn new<P: AsRef<Path>>(directory: P) -> io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+
+        fs::create_dir_all(&directory)?;
+
+        Ok(DirectoryObjectCache { directory })
+    }
+
+    /// Gets the directory objects are stored in.
+    pub fn get_directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Gets the path the object stored under `key` is written to.
+    pub fn get_object_path(&self, key: &ObjectCacheKey) -> PathBuf {
+        self.directory.join(format!("{}.o", key))
+    }
+}
+
+impl ObjectCache for DirectoryObjectCache {
+    fn get_object(&mut self, key: &ObjectCacheKey) -> Option<MemoryBuffer> {
+        MemoryBuffer::create_from_file(&self.get_object_path(key)).ok()
+    }
+
+    fn notify_object_compiled(&mut self, key: &ObjectCacheKey, object: &MemoryBuffer) {
+        let path = self.get_object_path(key);
+        let temp_path = self.directory.join(format!("{}.{}.tmp", key, process::id()));
+
+        // Renaming the complete file into place means concurrent readers never see part of an object
+        if fs::write(&temp_path, object.as_slice()).and_then(|()| fs::rename(&temp_path, &path)).is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+    }
+}
+
+/// Gets the object `module` compiles to with `target_machine` from `cache`, or compiles it and
+/// stores it in `cache` if there is none. The object is only returned, not loaded anywhere; it may
+/// be written out or linked like any other output of `TargetMachine::write_to_memory_buffer`.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::OptimizationLevel;
+/// use inkwell::context::Context;
+/// use inkwell::object_cache::{compile_with_cache, DirectoryObjectCache};
+/// use inkwell::targets::{CodeModel, InitializationConfig, RelocMode, Target, TargetMachine};
+///
+/// Target::initialize_native(&InitializationConfig::default()).unwrap();
+///
+/// let triple = TargetMachine::get_default_triple();
+/// let target = Target::from_triple(&triple).unwrap();
+/// let target_machine = target.create_target_machine(&triple, "", "", OptimizationLevel::Default, RelocMode::Default, CodeModel::JITDefault).unwrap();
+/// let context = Context::create();
+/// let module = context.create_module("cached");
+/// let mut cache = DirectoryObjectCache::new("/tmp/inkwell-object-cache").unwrap();
+///
+/// // Only the first run of the program compiles module
+/// let object = compile_with_cache(&mut cache, &module, &target_machine).unwrap();
+/// ```
+pub fn compile_with_cache(cache: &mut dyn ObjectCache, module: &Module, target_machine: &TargetMachine) -> Result<MemoryBuffer, LLVMString> {
+    let key = ObjectCacheKey::new(module, target_machine);
+
+    if let Some(object) = cache.get_object(&key) {
+        return Ok(object);
+    }
+
+    let object = target_machine.write_to_memory_buffer(module, FileType::Object)?;
+
+    cache.notify_object_compiled(&key, &object);
+
+    Ok(object)
+}
@@ -1,10 +1,31 @@
-use llvm_sys::object::{LLVMDisposeObjectFile, LLVMObjectFileRef, LLVMSectionIteratorRef, LLVMGetSections, LLVMDisposeSectionIterator, LLVMSymbolIteratorRef, LLVMIsSectionIteratorAtEnd, LLVMGetSectionName, LLVMDisposeRelocationIterator, LLVMRelocationIteratorRef, LLVMDisposeSymbolIterator, LLVMGetSectionContents, LLVMGetSectionSize, LLVMMoveToNextSection, LLVMGetSectionAddress, LLVMGetSymbolName, LLVMGetSymbolSize, LLVMGetRelocations, LLVMGetSymbolAddress, LLVMGetRelocationOffset, LLVMGetRelocationSymbol, LLVMGetRelocationType, LLVMGetRelocationTypeName, LLVMGetRelocationValueString, LLVMMoveToNextSymbol, LLVMMoveToNextRelocation, LLVMIsSymbolIteratorAtEnd, LLVMIsRelocationIteratorAtEnd, LLVMGetSymbols};
-
+use llvm_sys::object::{LLVMDisposeObjectFile, LLVMObjectFileRef, LLVMSectionIteratorRef, LLVMGetSections, LLVMDisposeSectionIterator, LLVMSymbolIteratorRef, LLVMIsSectionIteratorAtEnd, LLVMGetSectionName, LLVMDisposeRelocationIterator, LLVMRelocationIteratorRef, LLVMDisposeSymbolIterator, LLVMGetSectionContents, LLVMGetSectionSize, LLVMMoveToNextSection, LLVMGetSectionAddress, LLVMGetSymbolName, LLVMGetSymbolSize, LLVMGetRelocations, LLVMGetSymbolAddress, LLVMGetRelocationOffset, LLVMGetRelocationSymbol, LLVMGetRelocationType, LLVMGetRelocationTypeName, LLVMGetRelocationValueString, LLVMMoveToNextSymbol, LLVMMoveToNextRelocation, LLVMIsSymbolIteratorAtEnd, LLVMIsRelocationIteratorAtEnd, LLVMGetSymbols, LLVMMoveToContainingSection};
+#[llvm_versions(9.0..=latest)]
+use llvm_sys::object::{LLVMBinaryCopyMemoryBuffer, LLVMBinaryGetType, LLVMBinaryRef, LLVMBinaryType, LLVMCreateBinary, LLVMDisposeBinary, LLVMMachOUniversalBinaryCopyObjectForArch};
+
+#[llvm_versions(9.0..=latest)]
+use crate::context::Context;
+#[llvm_versions(9.0..=latest)]
+use crate::memory_buffer::MemoryBuffer;
+use crate::support::LLVMString;
+
+#[cfg(all(feature = "experimental", any(feature = "llvm7-0", feature = "llvm8-0", feature = "llvm9-0", feature = "llvm10-0", feature = "llvm11-0")))]
+use std::convert::TryFrom;
 use std::ffi::CStr;
+#[llvm_versions(9.0..=latest)]
+use std::marker::PhantomData;
+#[llvm_versions(9.0..=latest)]
+use std::ptr;
+#[cfg(any(
+    feature = "llvm9-0",
+    feature = "llvm10-0",
+    feature = "llvm11-0",
+    feature = "llvm12-0",
+    feature = "llvm13-0",
+    all(feature = "experimental", any(feature = "llvm7-0", feature = "llvm8-0"))
+))]
+use std::str;
 
-// REVIEW: Make sure SectionIterator's object_file ptr doesn't outlive ObjectFile
 // REVIEW: This module is very untested
-// TODO: More references to account for lifetimes
 #[derive(Debug)]
 pub struct ObjectFile {
     object_file: LLVMObjectFileRef
@@ -19,20 +40,38 @@ impl ObjectFile {
         }
     }
 
-    pub fn get_sections(&self) -> SectionIterator {
+    pub fn get_sections(&self) -> SectionIterator<'_> {
         let section_iterator = unsafe {
             LLVMGetSections(self.object_file)
         };
 
-        SectionIterator::new(section_iterator, self.object_file)
+        SectionIterator::new(section_iterator, self)
     }
 
-    pub fn get_symbols(&self) -> SymbolIterator {
+    pub fn get_symbols(&self) -> SymbolIterator<'_> {
         let symbol_iterator = unsafe {
             LLVMGetSymbols(self.object_file)
         };
 
-        SymbolIterator::new(symbol_iterator, self.object_file)
+        SymbolIterator::new(symbol_iterator, self)
+    }
+
+    // Creates a section iterator, to be disposed by the caller, positioned at section, or at the end if
+    // section isn't in this ObjectFile
+    fn find_section(&self, section: &Section) -> LLVMSectionIteratorRef {
+        unsafe {
+            let section_iterator = LLVMGetSections(self.object_file);
+
+            while LLVMIsSectionIteratorAtEnd(self.object_file, section_iterator) == 0 {
+                if LLVMGetSectionContents(section_iterator) == section.contents && LLVMGetSectionName(section_iterator) == section.name && LLVMGetSectionAddress(section_iterator) == section.address {
+                    break;
+                }
+
+                LLVMMoveToNextSection(section_iterator);
+            }
+
+            section_iterator
+        }
     }
 }
 
@@ -44,15 +83,58 @@ impl Drop for ObjectFile {
     }
 }
 
+/// The kind of data a `Section` holds, as far as can be told from its name. Section names are
+/// conventional rather than meaningful, so sections which don't follow the ELF, Mach-O and COFF
+/// conventions for these kinds are reported as `SectionKind::Other`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SectionKind {
+    /// Executable code.
+    Text,
+    /// Initialized, writable data.
+    Data,
+    /// Initialized, read only data.
+    ReadOnlyData,
+    /// Zero initialized data, which takes up no space in the file.
+    Bss,
+    /// Debug information.
+    Debug,
+    /// Any other section.
+    Other,
+}
+
+impl SectionKind {
+    fn from_name(name: &[u8]) -> Self {
+        // ELF and COFF names, followed by Mach-O section names. ELF and COFF sections may have a suffix
+        // after a '.' or '$', as with function and data sections
+        let has_prefix = |prefixes: &[&[u8]]| prefixes.iter().any(|prefix| {
+            name.starts_with(prefix) && (name.len() == prefix.len() || name[prefix.len()] == b'.' || name[prefix.len()] == b'$')
+        });
+
+        if has_prefix(&[b".text", b"__text", b"__stubs", b"__stub_helper"]) {
+            SectionKind::Text
+        } else if has_prefix(&[b".tbss", b".bss", b"__bss", b"__common", b"__thread_bss"]) {
+            SectionKind::Bss
+        } else if has_prefix(&[b".rodata", b".rdata", b"__const", b"__cstring", b"__literal4", b"__literal8", b"__literal16"]) {
+            SectionKind::ReadOnlyData
+        } else if has_prefix(&[b".tdata", b".data", b"__data", b"__thread_data"]) {
+            SectionKind::Data
+        } else if name.starts_with(b".debug_") || name.starts_with(b"__debug_") {
+            SectionKind::Debug
+        } else {
+            SectionKind::Other
+        }
+    }
+}
+
 #[derive(Debug)]
-pub struct SectionIterator {
+pub struct SectionIterator<'a> {
     section_iterator: LLVMSectionIteratorRef,
-    object_file: LLVMObjectFileRef,
+    object_file: &'a ObjectFile,
     before_first: bool,
 }
 
-impl SectionIterator {
-    fn new(section_iterator: LLVMSectionIteratorRef, object_file: LLVMObjectFileRef) -> Self {
+impl<'a> SectionIterator<'a> {
+    fn new(section_iterator: LLVMSectionIteratorRef, object_file: &'a ObjectFile) -> Self {
         assert!(!section_iterator.is_null());
 
         SectionIterator {
@@ -63,8 +145,8 @@ impl SectionIterator {
     }
 }
 
-impl Iterator for SectionIterator {
-    type Item = Section;
+impl<'a> Iterator for SectionIterator<'a> {
+    type Item = Section<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.before_first {
@@ -76,20 +158,20 @@ impl Iterator for SectionIterator {
         }
 
         let at_end = unsafe {
-            LLVMIsSectionIteratorAtEnd(self.object_file, self.section_iterator) == 1
+            LLVMIsSectionIteratorAtEnd(self.object_file.object_file, self.section_iterator) == 1
         };
 
         if at_end {
             return None;
         }
 
-        let section = Section::new(self.section_iterator, self.object_file);
+        let section = unsafe { Section::new(self.section_iterator, self.object_file) };
 
         Some(section)
     }
 }
 
-impl Drop for SectionIterator {
+impl Drop for SectionIterator<'_> {
     fn drop(&mut self) {
         unsafe {
             LLVMDisposeSectionIterator(self.section_iterator)
@@ -97,72 +179,114 @@ impl Drop for SectionIterator {
     }
 }
 
-#[derive(Debug)]
-pub struct Section {
-    section: LLVMSectionIteratorRef,
-    object_file: LLVMObjectFileRef,
+/// A section of an `ObjectFile`, which it cannot outlive.
+#[derive(Debug, Clone)]
+pub struct Section<'a> {
+    object_file: &'a ObjectFile,
+    // Point into object_file
+    name: *const libc::c_char,
+    contents: *const libc::c_char,
+    address: u64,
+    size: u64,
 }
 
-impl Section {
-    fn new(section: LLVMSectionIteratorRef, object_file: LLVMObjectFileRef) -> Self {
-        assert!(!section.is_null());
+impl<'a> Section<'a> {
+    // Reads the section section_iterator is positioned at, which must not be at the end
+    unsafe fn new(section_iterator: LLVMSectionIteratorRef, object_file: &'a ObjectFile) -> Self {
+        assert!(!section_iterator.is_null());
 
         Section {
-            section,
             object_file,
+            name: LLVMGetSectionName(section_iterator),
+            contents: LLVMGetSectionContents(section_iterator),
+            address: LLVMGetSectionAddress(section_iterator),
+            size: LLVMGetSectionSize(section_iterator),
         }
     }
 
-    pub fn get_name(&self) -> Option<&CStr> {
-        let name = unsafe {
-            LLVMGetSectionName(self.section)
-        };
-        if !name.is_null() {
-            Some(unsafe { CStr::from_ptr(name) })
+    pub fn get_name(&self) -> Option<&'a CStr> {
+        if !self.name.is_null() {
+            Some(unsafe { CStr::from_ptr(self.name) })
         } else {
             None
         }
     }
 
     pub fn size(&self) -> u64 {
-        unsafe {
-            LLVMGetSectionSize(self.section)
-        }
+        self.size
     }
 
-    pub fn get_contents(&self) -> &[u8] {
+    /// Gets the section's contents, which are empty for `SectionKind::Bss` sections as they take up
+    /// no space in the file.
+    pub fn get_contents(&self) -> &'a [u8] {
+        if self.get_kind() == SectionKind::Bss || self.contents.is_null() {
+            return &[];
+        }
+
         unsafe {
-            std::slice::from_raw_parts(
-                LLVMGetSectionContents(self.section) as *const u8,
-                self.size() as usize)
+            std::slice::from_raw_parts(self.contents as *const u8, self.size as usize)
         }
     }
 
     pub fn get_address(&self) -> u64 {
-        unsafe {
-            LLVMGetSectionAddress(self.section)
+        self.address
+    }
+
+    /// Gets the kind of data this section holds, judging by its name.
+    pub fn get_kind(&self) -> SectionKind {
+        match self.get_name() {
+            Some(name) => SectionKind::from_name(name.to_bytes()),
+            None => SectionKind::Other,
+        }
+    }
+
+    /// Determines whether this section holds executable code.
+    pub fn is_text(&self) -> bool {
+        self.get_kind() == SectionKind::Text
+    }
+
+    /// Determines whether this section holds initialized data, writable or not.
+    pub fn is_data(&self) -> bool {
+        match self.get_kind() {
+            SectionKind::Data | SectionKind::ReadOnlyData => true,
+            _ => false,
         }
     }
 
-    pub fn get_relocations(&self) -> RelocationIterator {
+    /// Determines whether this section holds zero initialized data.
+    pub fn is_bss(&self) -> bool {
+        self.get_kind() == SectionKind::Bss
+    }
+
+    pub fn get_relocations(&self) -> RelocationIterator<'a> {
+        let section_iterator = self.object_file.find_section(self);
         let relocation_iterator = unsafe {
-            LLVMGetRelocations(self.section)
+            LLVMGetRelocations(section_iterator)
         };
 
-        RelocationIterator::new(relocation_iterator, self.section, self.object_file)
+        RelocationIterator::new(relocation_iterator, section_iterator, self.object_file)
+    }
+}
+
+impl PartialEq for Section<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.object_file.object_file == other.object_file.object_file && self.name == other.name && self.contents == other.contents && self.address == other.address
     }
 }
 
+impl Eq for Section<'_> {}
+
 #[derive(Debug)]
-pub struct RelocationIterator {
+pub struct RelocationIterator<'a> {
     relocation_iterator: LLVMRelocationIteratorRef,
+    // Owned by this iterator
     section_iterator: LLVMSectionIteratorRef,
-    object_file: LLVMObjectFileRef,
+    object_file: &'a ObjectFile,
     before_first: bool,
 }
 
-impl RelocationIterator {
-    fn new(relocation_iterator: LLVMRelocationIteratorRef, section_iterator: LLVMSectionIteratorRef, object_file: LLVMObjectFileRef) -> Self {
+impl<'a> RelocationIterator<'a> {
+    fn new(relocation_iterator: LLVMRelocationIteratorRef, section_iterator: LLVMSectionIteratorRef, object_file: &'a ObjectFile) -> Self {
         assert!(!relocation_iterator.is_null());
 
         RelocationIterator {
@@ -174,8 +298,8 @@ impl RelocationIterator {
     }
 }
 
-impl Iterator for RelocationIterator {
-    type Item = Relocation;
+impl<'a> Iterator for RelocationIterator<'a> {
+    type Item = Relocation<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.before_first {
@@ -194,90 +318,106 @@ impl Iterator for RelocationIterator {
             return None;
         }
 
-        let relocation = Relocation::new(self.relocation_iterator, self.object_file);
+        let relocation = unsafe { Relocation::new(self.relocation_iterator, self.object_file) };
 
         Some(relocation)
     }
 }
 
-impl Drop for RelocationIterator {
+impl Drop for RelocationIterator<'_> {
     fn drop(&mut self) {
         unsafe {
-            LLVMDisposeRelocationIterator(self.relocation_iterator)
+            LLVMDisposeRelocationIterator(self.relocation_iterator);
+            LLVMDisposeSectionIterator(self.section_iterator);
         }
     }
 }
 
+/// A relocation in a `Section` of an `ObjectFile`, which it cannot outlive.
 #[derive(Debug)]
-pub struct Relocation {
-    relocation: LLVMRelocationIteratorRef,
-    object_file: LLVMObjectFileRef,
+pub struct Relocation<'a> {
+    offset: u64,
+    type_int: u64,
+    type_name: LLVMString,
+    value: LLVMString,
+    symbol: Option<Symbol<'a>>,
 }
 
-impl Relocation {
-    fn new(relocation: LLVMRelocationIteratorRef, object_file: LLVMObjectFileRef) -> Self {
-        assert!(!relocation.is_null());
+impl<'a> Relocation<'a> {
+    // Reads the relocation relocation_iterator is positioned at, which must not be at the end
+    unsafe fn new(relocation_iterator: LLVMRelocationIteratorRef, object_file: &'a ObjectFile) -> Self {
+        assert!(!relocation_iterator.is_null());
+
+        let symbol_iterator = LLVMGetRelocationSymbol(relocation_iterator);
+        let symbol = if LLVMIsSymbolIteratorAtEnd(object_file.object_file, symbol_iterator) == 0 {
+            let section_iterator = LLVMGetSections(object_file.object_file);
+            let symbol = Symbol::new(symbol_iterator, section_iterator, object_file);
 
+            LLVMDisposeSectionIterator(section_iterator);
+
+            Some(symbol)
+        } else {
+            None
+        };
+
+        LLVMDisposeSymbolIterator(symbol_iterator);
+
+        // Both strings are allocated with malloc, for the caller to free
         Relocation {
-            relocation,
-            object_file,
+            offset: LLVMGetRelocationOffset(relocation_iterator),
+            type_int: LLVMGetRelocationType(relocation_iterator),
+            type_name: LLVMString::new(LLVMGetRelocationTypeName(relocation_iterator)),
+            value: LLVMString::new(LLVMGetRelocationValueString(relocation_iterator)),
+            symbol,
         }
     }
 
     pub fn get_offset(&self) -> u64 {
-        unsafe {
-            LLVMGetRelocationOffset(self.relocation)
-        }
+        self.offset
     }
 
-    pub fn get_symbols(&self) -> SymbolIterator {
-        let symbol_iterator = unsafe {
-            // REVIEW: Is this just returning a single Symbol (given the name) and not a full iterator?
-            LLVMGetRelocationSymbol(self.relocation)
-        };
-
-        SymbolIterator::new(symbol_iterator, self.object_file)
+    /// Gets the symbol this relocation refers to, if any.
+    pub fn get_symbol(&self) -> Option<&Symbol<'a>> {
+        self.symbol.as_ref()
     }
 
     pub fn get_type(&self) -> (u64, &CStr) {
-        let type_int = unsafe {
-            LLVMGetRelocationType(self.relocation)
-        };
-        let type_name = unsafe {
-            CStr::from_ptr(LLVMGetRelocationTypeName(self.relocation))
-        };
-
-        (type_int, type_name)
+        (self.type_int, &self.type_name)
     }
 
     pub fn get_value(&self) -> &CStr {
-        unsafe {
-            CStr::from_ptr(LLVMGetRelocationValueString(self.relocation))
-        }
+        &self.value
     }
 }
 
 #[derive(Debug)]
-pub struct SymbolIterator {
+pub struct SymbolIterator<'a> {
     symbol_iterator: LLVMSymbolIteratorRef,
-    object_file: LLVMObjectFileRef,
+    // Used to find each symbol's section
+    section_iterator: LLVMSectionIteratorRef,
+    object_file: &'a ObjectFile,
     before_first: bool,
 }
 
-impl SymbolIterator {
-    fn new(symbol_iterator: LLVMSymbolIteratorRef, object_file: LLVMObjectFileRef) -> Self {
+impl<'a> SymbolIterator<'a> {
+    fn new(symbol_iterator: LLVMSymbolIteratorRef, object_file: &'a ObjectFile) -> Self {
         assert!(!symbol_iterator.is_null());
 
+        let section_iterator = unsafe {
+            LLVMGetSections(object_file.object_file)
+        };
+
         SymbolIterator {
             symbol_iterator,
+            section_iterator,
             object_file,
             before_first: true
         }
     }
 }
 
-impl Iterator for SymbolIterator {
-    type Item = Symbol;
+impl<'a> Iterator for SymbolIterator<'a> {
+    type Item = Symbol<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.before_first {
@@ -289,61 +429,402 @@ impl Iterator for SymbolIterator {
         }
 
         let at_end = unsafe {
-            LLVMIsSymbolIteratorAtEnd(self.object_file, self.symbol_iterator) == 1
+            LLVMIsSymbolIteratorAtEnd(self.object_file.object_file, self.symbol_iterator) == 1
         };
 
         if at_end {
             return None;
         }
 
-        let symbol = Symbol::new(self.symbol_iterator);
+        let symbol = unsafe { Symbol::new(self.symbol_iterator, self.section_iterator, self.object_file) };
 
         Some(symbol)
     }
 }
 
-impl Drop for SymbolIterator {
+impl Drop for SymbolIterator<'_> {
     fn drop(&mut self) {
         unsafe {
-            LLVMDisposeSymbolIterator(self.symbol_iterator)
+            LLVMDisposeSymbolIterator(self.symbol_iterator);
+            LLVMDisposeSectionIterator(self.section_iterator);
         }
     }
 }
 
-#[derive(Debug)]
-pub struct Symbol {
-    symbol: LLVMSymbolIteratorRef,
+/// What a `Symbol` names, as far as can be told from the section containing it. The C API does not
+/// expose symbol types or bindings, so local and global symbols are not distinguished.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SymbolKind {
+    /// A symbol which isn't contained by any section. This is usually a reference to a symbol
+    /// defined elsewhere, but also covers absolute and file symbols.
+    Undefined,
+    /// A symbol in a `SectionKind::Text` section, usually a function.
+    Text,
+    /// A symbol in a `SectionKind::Data`, `SectionKind::ReadOnlyData` or `SectionKind::Bss` section,
+    /// usually a variable or constant.
+    Data,
+    /// A symbol in any other section.
+    Other,
+}
+
+/// A symbol of an `ObjectFile`, which it cannot outlive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol<'a> {
+    // Points into the ObjectFile
+    name: *const libc::c_char,
+    address: u64,
+    size: u64,
+    section: Option<Section<'a>>,
 }
 
-impl Symbol {
-    fn new(symbol: LLVMSymbolIteratorRef) -> Self {
-        assert!(!symbol.is_null());
+impl<'a> Symbol<'a> {
+    // Reads the symbol symbol_iterator is positioned at, which must not be at the end, moving
+    // section_iterator to the section containing it
+    unsafe fn new(symbol_iterator: LLVMSymbolIteratorRef, section_iterator: LLVMSectionIteratorRef, object_file: &'a ObjectFile) -> Self {
+        assert!(!symbol_iterator.is_null());
+
+        LLVMMoveToContainingSection(section_iterator, symbol_iterator);
+
+        let section = if LLVMIsSectionIteratorAtEnd(object_file.object_file, section_iterator) == 0 {
+            Some(Section::new(section_iterator, object_file))
+        } else {
+            None
+        };
 
         Symbol {
-            symbol,
+            name: LLVMGetSymbolName(symbol_iterator),
+            address: LLVMGetSymbolAddress(symbol_iterator),
+            size: LLVMGetSymbolSize(symbol_iterator),
+            section,
         }
     }
 
-    pub fn get_name(&self) -> Option<&CStr> {
-        let name = unsafe {
-            LLVMGetSymbolName(self.symbol)
-        };
-        if !name.is_null() {
-            Some(unsafe { CStr::from_ptr(name) })
+    pub fn get_name(&self) -> Option<&'a CStr> {
+        if !self.name.is_null() {
+            Some(unsafe { CStr::from_ptr(self.name) })
         } else {
             None
         }
     }
 
     pub fn size(&self) -> u64 {
-        unsafe {
-            LLVMGetSymbolSize(self.symbol)
-        }
+        self.size
     }
 
     pub fn get_address(&self) -> u64 {
+        self.address
+    }
+
+    /// Gets the section containing this symbol, or `None` if it is undefined.
+    pub fn get_section(&self) -> Option<&Section<'a>> {
+        self.section.as_ref()
+    }
+
+    /// Determines whether this symbol is defined elsewhere. See `SymbolKind::Undefined`.
+    pub fn is_undefined(&self) -> bool {
+        self.section.is_none()
+    }
+
+    /// Gets what this symbol names, judging by the section containing it.
+    pub fn get_kind(&self) -> SymbolKind {
+        let section = match self.section {
+            Some(ref section) => section,
+            None => return SymbolKind::Undefined,
+        };
+
+        match section.get_kind() {
+            SectionKind::Text => SymbolKind::Text,
+            SectionKind::Data | SectionKind::ReadOnlyData | SectionKind::Bss => SymbolKind::Data,
+            SectionKind::Debug | SectionKind::Other => SymbolKind::Other,
+        }
+    }
+}
+
+/// The kind of file a `Binary` holds.
+#[llvm_versions(9.0..=latest)]
+#[llvm_enum(LLVMBinaryType)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BinaryType {
+    /// An `ar` archive of other binaries.
+    #[llvm_variant(LLVMBinaryTypeArchive)]
+    Archive,
+    /// A Mach-O universal (fat) binary holding a slice for each of several architectures.
+    #[llvm_variant(LLVMBinaryTypeMachOUniversalBinary)]
+    MachOUniversalBinary,
+    /// A COFF short import file.
+    #[llvm_variant(LLVMBinaryTypeCOFFImportFile)]
+    COFFImportFile,
+    /// LLVM bitcode.
+    #[llvm_variant(LLVMBinaryTypeIR)]
+    IR,
+    /// A Windows resource file.
+    #[llvm_variant(LLVMBinaryTypeWinRes)]
+    WinRes,
+    /// A COFF object file.
+    #[llvm_variant(LLVMBinaryTypeCOFF)]
+    COFF,
+    /// A 32 bit little endian ELF object file.
+    #[llvm_variant(LLVMBinaryTypeELF32L)]
+    ELF32L,
+    /// A 32 bit big endian ELF object file.
+    #[llvm_variant(LLVMBinaryTypeELF32B)]
+    ELF32B,
+    /// A 64 bit little endian ELF object file.
+    #[llvm_variant(LLVMBinaryTypeELF64L)]
+    ELF64L,
+    /// A 64 bit big endian ELF object file.
+    #[llvm_variant(LLVMBinaryTypeELF64B)]
+    ELF64B,
+    /// A 32 bit little endian Mach-O object file.
+    #[llvm_variant(LLVMBinaryTypeMachO32L)]
+    MachO32L,
+    /// A 32 bit big endian Mach-O object file.
+    #[llvm_variant(LLVMBinaryTypeMachO32B)]
+    MachO32B,
+    /// A 64 bit little endian Mach-O object file.
+    #[llvm_variant(LLVMBinaryTypeMachO64L)]
+    MachO64L,
+    /// A 64 bit big endian Mach-O object file.
+    #[llvm_variant(LLVMBinaryTypeMachO64B)]
+    MachO64B,
+    /// A WebAssembly object file.
+    #[llvm_variant(LLVMBinaryTypeWasm)]
+    Wasm,
+}
+
+#[llvm_versions(9.0..=latest)]
+impl BinaryType {
+    /// Determines whether this is an object file, which has sections and symbols, rather than a
+    /// container of binaries or some other kind of file.
+    pub fn is_object_file(self) -> bool {
+        match self {
+            BinaryType::Archive | BinaryType::MachOUniversalBinary | BinaryType::COFFImportFile | BinaryType::IR | BinaryType::WinRes => false,
+            _ => true,
+        }
+    }
+}
+
+/// Any file LLVM can read binaries from: object files of every format, archives, Mach-O universal
+/// binaries and bitcode.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::memory_buffer::MemoryBuffer;
+/// use inkwell::object_file::{Binary, BinaryType};
+///
+/// use std::path::Path;
+///
+/// let buffer = MemoryBuffer::create_from_file(Path::new("libruntime.a")).unwrap();
+/// let binary = Binary::create(buffer, None).unwrap();
+///
+/// assert_eq!(binary.get_type(), BinaryType::Archive);
+///
+/// for member in binary.get_archive_members().unwrap() {
+///     let member_binary = member.create_binary(None).unwrap();
+///
+///     println!("{}: {:?}", member.get_name(), member_binary.get_type());
+/// }
+/// ```
+#[llvm_versions(9.0..=latest)]
+#[derive(Debug)]
+pub struct Binary<'ctx> {
+    binary: LLVMBinaryRef,
+    // Read by binary, which doesn't own it, so must be dropped after it
+    memory_buffer: MemoryBuffer,
+    _marker: PhantomData<&'ctx Context>,
+}
+
+#[llvm_versions(9.0..=latest)]
+impl<'ctx> Binary<'ctx> {
+    /// Reads a `Binary` from `memory_buffer`. Bitcode can only be read given a `context` to
+    /// load it into.
+    pub fn create(memory_buffer: MemoryBuffer, context: Option<&'ctx Context>) -> Result<Self, LLVMString> {
+        let mut err_string = ptr::null_mut();
+        let context = context.map_or(ptr::null_mut(), |context| context.context);
+        let binary = unsafe {
+            LLVMCreateBinary(memory_buffer.memory_buffer, context, &mut err_string)
+        };
+
+        if binary.is_null() {
+            return Err(unsafe { LLVMString::new(err_string) });
+        }
+
+        Ok(Binary {
+            binary,
+            memory_buffer,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Gets the kind of file this `Binary` holds.
+    pub fn get_type(&self) -> BinaryType {
+        let binary_type = unsafe {
+            LLVMBinaryGetType(self.binary)
+        };
+
+        BinaryType::new(binary_type)
+    }
+
+    /// Gets the bytes of this `Binary`.
+    pub fn as_slice(&self) -> &[u8] {
+        self.memory_buffer.as_slice()
+    }
+
+    /// Gets the members of an archive, in order, skipping its symbol table. Returns an error if this
+    /// `Binary` is not an archive. Thin archives, which only reference their members, are not supported.
+    pub fn get_archive_members(&self) -> Result<Vec<ArchiveMember<'_>>, &'static str> {
+        if self.get_type() != BinaryType::Archive {
+            return Err("Binary is not an archive");
+        }
+
+        let members = parse_archive(self.as_slice()).ok_or("Archive is malformed or thin")?;
+
+        Ok(members.into_iter().map(|(name, contents)| ArchiveMember { name, contents }).collect())
+    }
+
+    /// Extracts the slice for `arch`, such as "x86_64" or "arm64", from a Mach-O universal binary. Returns
+    /// an error if this `Binary` is not a universal binary or holds no slice for `arch`.
+    pub fn get_universal_slice(&self, arch: &str) -> Result<Binary<'ctx>, LLVMString> {
+        if self.get_type() != BinaryType::MachOUniversalBinary {
+            return Err(LLVMString::create_from_str("Binary is not a Mach-O universal binary"));
+        }
+
+        let mut err_string = ptr::null_mut();
+        let slice = unsafe {
+            LLVMMachOUniversalBinaryCopyObjectForArch(self.binary, arch.as_ptr() as *const libc::c_char, arch.len(), &mut err_string)
+        };
+
+        if slice.is_null() {
+            return Err(unsafe { LLVMString::new(err_string) });
+        }
+
+        // The slice refers to this binary's memory, so is copied to outlive it
+        let memory_buffer = unsafe {
+            let memory_buffer = MemoryBuffer::new(LLVMBinaryCopyMemoryBuffer(slice));
+
+            LLVMDisposeBinary(slice);
+
+            memory_buffer
+        };
+
+        Binary::create(memory_buffer, None)
+    }
+
+    /// Converts an object file `Binary` into an `ObjectFile`, to inspect its sections and symbols.
+    pub fn into_object_file(self) -> Result<ObjectFile, ()> {
+        if !self.get_type().is_object_file() {
+            return Err(());
+        }
+
+        let memory_buffer = unsafe {
+            MemoryBuffer::new(LLVMBinaryCopyMemoryBuffer(self.binary))
+        };
+
+        memory_buffer.create_object_file()
+    }
+}
+
+#[llvm_versions(9.0..=latest)]
+impl Drop for Binary<'_> {
+    fn drop(&mut self) {
         unsafe {
-            LLVMGetSymbolAddress(self.symbol)
+            LLVMDisposeBinary(self.binary)
         }
     }
 }
+
+/// A member of an archive `Binary`.
+#[llvm_versions(9.0..=latest)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveMember<'a> {
+    name: String,
+    contents: &'a [u8],
+}
+
+#[llvm_versions(9.0..=latest)]
+impl<'a> ArchiveMember<'a> {
+    /// Gets the member's file name.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the member's contents.
+    pub fn get_contents(&self) -> &'a [u8] {
+        self.contents
+    }
+
+    /// Reads a `Binary` from a copy of the member's contents.
+    pub fn create_binary<'ctx>(&self, context: Option<&'ctx Context>) -> Result<Binary<'ctx>, LLVMString> {
+        Binary::create(MemoryBuffer::create_from_memory_range_copy(self.contents, &self.name), context)
+    }
+}
+
+// Splits a GNU or BSD ar archive into the names and contents of its members, skipping symbol tables. Used
+// by Binary and the experimental Orc::add_archive
+#[cfg(any(
+    feature = "llvm9-0",
+    feature = "llvm10-0",
+    feature = "llvm11-0",
+    feature = "llvm12-0",
+    feature = "llvm13-0",
+    all(feature = "experimental", any(feature = "llvm7-0", feature = "llvm8-0"))
+))]
+pub(crate) fn parse_archive(archive: &[u8]) -> Option<Vec<(String, &[u8])>> {
+    const MAGIC: &[u8] = b"!<arch>\n";
+    const HEADER_SIZE: usize = 60;
+
+    if !archive.starts_with(MAGIC) {
+        return None;
+    }
+
+    let mut offset = MAGIC.len();
+    let mut long_names: &[u8] = &[];
+    let mut members = Vec::new();
+
+    while offset < archive.len() {
+        let header = archive.get(offset..offset + HEADER_SIZE)?;
+
+        if &header[58..] != b"`\n" {
+            return None;
+        }
+
+        let size: usize = str::from_utf8(&header[48..58]).ok()?.trim().parse().ok()?;
+        let mut contents = archive.get(offset + HEADER_SIZE..offset + HEADER_SIZE + size)?;
+        let raw_name = str::from_utf8(&header[..16]).ok()?.trim_end();
+
+        // Members are aligned to two bytes
+        offset += HEADER_SIZE + size + size % 2;
+
+        let name = if raw_name == "//" {
+            long_names = contents;
+
+            continue;
+        } else if raw_name.starts_with("#1/") {
+            // BSD archives store long names at the start of the member
+            let name_len: usize = raw_name[3..].parse().ok()?;
+            let name = contents.get(..name_len)?;
+
+            contents = &contents[name_len..];
+
+            String::from_utf8_lossy(name).trim_end_matches('\0').to_string()
+        } else if raw_name.len() > 1 && raw_name.starts_with('/') && raw_name[1..].bytes().all(|byte| byte.is_ascii_digit()) {
+            // GNU archives store long names in the // member, terminated by "/\n"
+            let name = long_names.get(raw_name[1..].parse::<usize>().ok()?..)?;
+            let name_len = name.iter().position(|&byte| byte == b'\n').unwrap_or(name.len());
+
+            String::from_utf8_lossy(&name[..name_len]).trim_end_matches('/').to_string()
+        } else {
+            raw_name.trim_end_matches('/').to_string()
+        };
+
+        // Symbol tables are named "/" and "/SYM64/" by GNU and "__.SYMDEF" by BSD
+        if name.is_empty() || name == "/SYM64" || name.starts_with("__.SYMDEF") {
+            continue;
+        }
+
+        members.push((name, contents));
+    }
+
+    Some(members)
+}
@@ -0,0 +1,707 @@
+//! Combinators for matching patterns of IR in the spirit of LLVM's `PatternMatch.h`.
+//!
+//! Patterns are built from the `m_*` functions and then matched against a value with
+//! `match_value` or against an instruction with `match_instruction`. Patterns which take a
+//! `&mut Option<_>` capture the matched value into it, so that it can be inspected once the
+//! pattern has been dropped. Note that captures may be written to even if the match as a
+//! whole ultimately fails.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use inkwell::context::Context;
+//! use inkwell::pattern_match::{m_add, m_const_int, m_value, match_value};
+//!
+//! let context = Context::create();
+//! let module = context.create_module("my_module");
+//! let builder = context.create_builder();
+//! let i32_type = context.i32_type();
+//! let fn_type = i32_type.fn_type(&[i32_type.into()], false);
+//! let function = module.add_function("my_fn", fn_type, None);
+//!
+//! builder.position_at_end(context.append_basic_block(function, "entry"));
+//!
+//! let x = function.get_first_param().unwrap().into_int_value();
+//! let sum = builder.build_int_add(x, i32_type.const_zero(), "sum");
+//! let mut lhs = None;
+//!
+//! assert!(match_value(sum, m_add(m_value(&mut lhs), m_const_int(0))));
+//! assert_eq!(lhs.unwrap(), x);
+//! ```
+
+use llvm_sys::core::{LLVMGetTypeKind, LLVMIsAConstantFP, LLVMIsNull, LLVMIsUndef, LLVMTypeOf};
+use llvm_sys::LLVMTypeKind;
+
+use crate::values::{AsValueRef, BasicValue, BasicValueEnum, InstructionOpcode, InstructionValue, IntValue};
+use crate::{FloatPredicate, IntPredicate};
+
+/// Matches a pattern against a value.
+pub fn match_value<'ctx, V: BasicValue<'ctx>, P: Pattern<'ctx>>(value: V, mut pattern: P) -> bool {
+    pattern.match_value(value.as_basic_value_enum())
+}
+
+/// Matches a pattern against an instruction. Unlike `match_value`, this also allows matching
+/// instructions which do not produce a value, such as `store`.
+pub fn match_instruction<'ctx, P: Pattern<'ctx>>(instruction: InstructionValue<'ctx>, mut pattern: P) -> bool {
+    pattern.match_instruction(instruction)
+}
+
+/// A pattern which can be matched against values and instructions.
+pub trait Pattern<'ctx> {
+    /// Matches this pattern against a value, recording any captures.
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool;
+
+    /// Matches this pattern against an instruction, recording any captures. Instructions
+    /// which do not produce a value only match patterns which describe instructions.
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        match instruction_as_basic_value(instruction) {
+            Some(value) => self.match_value(value),
+            None => false,
+        }
+    }
+}
+
+fn instruction_as_basic_value(instruction: InstructionValue<'_>) -> Option<BasicValueEnum<'_>> {
+    let type_kind = unsafe {
+        LLVMGetTypeKind(LLVMTypeOf(instruction.as_value_ref()))
+    };
+
+    match type_kind {
+        LLVMTypeKind::LLVMHalfTypeKind |
+        LLVMTypeKind::LLVMFloatTypeKind |
+        LLVMTypeKind::LLVMDoubleTypeKind |
+        LLVMTypeKind::LLVMX86_FP80TypeKind |
+        LLVMTypeKind::LLVMFP128TypeKind |
+        LLVMTypeKind::LLVMPPC_FP128TypeKind |
+        LLVMTypeKind::LLVMIntegerTypeKind |
+        LLVMTypeKind::LLVMStructTypeKind |
+        LLVMTypeKind::LLVMArrayTypeKind |
+        LLVMTypeKind::LLVMPointerTypeKind |
+        LLVMTypeKind::LLVMVectorTypeKind => unsafe {
+            Some(BasicValueEnum::new(instruction.as_value_ref()))
+        },
+        _ => None,
+    }
+}
+
+fn get_value_operand<'ctx>(instruction: InstructionValue<'ctx>, index: u32) -> Option<BasicValueEnum<'ctx>> {
+    instruction.get_operand(index).and_then(|operand| operand.left())
+}
+
+/// Matches any value. See `m_any`.
+#[derive(Debug, Clone, Copy)]
+pub struct AnyPattern;
+
+impl<'ctx> Pattern<'ctx> for AnyPattern {
+    fn match_value(&mut self, _value: BasicValueEnum<'ctx>) -> bool {
+        true
+    }
+}
+
+/// Matches any value.
+pub fn m_any() -> AnyPattern {
+    AnyPattern
+}
+
+/// Matches and captures any value. See `m_value`.
+#[derive(Debug)]
+pub struct ValuePattern<'a, 'ctx> {
+    capture: &'a mut Option<BasicValueEnum<'ctx>>,
+}
+
+impl<'ctx> Pattern<'ctx> for ValuePattern<'_, 'ctx> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        *self.capture = Some(value);
+
+        true
+    }
+}
+
+/// Matches any value, capturing it.
+pub fn m_value<'a, 'ctx>(capture: &'a mut Option<BasicValueEnum<'ctx>>) -> ValuePattern<'a, 'ctx> {
+    ValuePattern { capture }
+}
+
+/// Matches and captures any instruction. See `m_instruction`.
+#[derive(Debug)]
+pub struct InstructionPattern<'a, 'ctx> {
+    capture: &'a mut Option<InstructionValue<'ctx>>,
+}
+
+impl<'ctx> Pattern<'ctx> for InstructionPattern<'_, 'ctx> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        match value.as_instruction_value() {
+            Some(instruction) => self.match_instruction(instruction),
+            None => false,
+        }
+    }
+
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        *self.capture = Some(instruction);
+
+        true
+    }
+}
+
+/// Matches any instruction, capturing it.
+pub fn m_instruction<'a, 'ctx>(capture: &'a mut Option<InstructionValue<'ctx>>) -> InstructionPattern<'a, 'ctx> {
+    InstructionPattern { capture }
+}
+
+/// Matches one specific value. See `m_specific`.
+#[derive(Debug, Clone, Copy)]
+pub struct SpecificPattern<'ctx> {
+    value: BasicValueEnum<'ctx>,
+}
+
+impl<'ctx> Pattern<'ctx> for SpecificPattern<'ctx> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        value == self.value
+    }
+}
+
+/// Matches only the given value.
+pub fn m_specific<'ctx, V: BasicValue<'ctx>>(value: V) -> SpecificPattern<'ctx> {
+    SpecificPattern {
+        value: value.as_basic_value_enum(),
+    }
+}
+
+/// Matches integer constants. See `m_const_int`, `m_one` and `m_all_ones`.
+#[derive(Debug, Clone, Copy)]
+pub struct ConstIntPattern {
+    value: u64,
+    sign_extend: bool,
+}
+
+impl<'ctx> Pattern<'ctx> for ConstIntPattern {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        let int_value = match value {
+            BasicValueEnum::IntValue(int_value) => int_value,
+            _ => return false,
+        };
+
+        if self.sign_extend {
+            int_value.get_sign_extended_constant() == Some(self.value as i64)
+        } else {
+            int_value.get_zero_extended_constant() == Some(self.value)
+        }
+    }
+}
+
+/// Matches an integer constant of at most 64 bits whose zero extended value is `value`.
+pub fn m_const_int(value: u64) -> ConstIntPattern {
+    ConstIntPattern { value, sign_extend: false }
+}
+
+/// Matches the integer constant one.
+pub fn m_one() -> ConstIntPattern {
+    m_const_int(1)
+}
+
+/// Matches an integer constant of at most 64 bits with all bits set.
+pub fn m_all_ones() -> ConstIntPattern {
+    ConstIntPattern { value: u64::max_value(), sign_extend: true }
+}
+
+/// Matches and captures any integer constant. See `m_constant_int`.
+#[derive(Debug)]
+pub struct ConstantIntPattern<'a, 'ctx> {
+    capture: &'a mut Option<IntValue<'ctx>>,
+}
+
+impl<'ctx> Pattern<'ctx> for ConstantIntPattern<'_, 'ctx> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        match value {
+            BasicValueEnum::IntValue(int_value) if int_value.is_constant_int() => {
+                *self.capture = Some(int_value);
+
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Matches any integer constant, capturing it.
+pub fn m_constant_int<'a, 'ctx>(capture: &'a mut Option<IntValue<'ctx>>) -> ConstantIntPattern<'a, 'ctx> {
+    ConstantIntPattern { capture }
+}
+
+/// Matches floating point constants. See `m_const_float`.
+#[derive(Debug, Clone, Copy)]
+pub struct ConstFloatPattern {
+    value: f64,
+}
+
+impl<'ctx> Pattern<'ctx> for ConstFloatPattern {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        let float_value = match value {
+            BasicValueEnum::FloatValue(float_value) => float_value,
+            _ => return false,
+        };
+
+        if unsafe { LLVMIsAConstantFP(float_value.as_value_ref()) }.is_null() {
+            return false;
+        }
+
+        match float_value.get_constant() {
+            Some((constant, _)) => constant == self.value,
+            None => false,
+        }
+    }
+}
+
+/// Matches a floating point constant equal to `value`.
+pub fn m_const_float(value: f64) -> ConstFloatPattern {
+    ConstFloatPattern { value }
+}
+
+/// Matches null (zero) constants. See `m_zero`.
+#[derive(Debug, Clone, Copy)]
+pub struct ZeroPattern;
+
+impl<'ctx> Pattern<'ctx> for ZeroPattern {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        unsafe {
+            LLVMIsNull(value.as_value_ref()) == 1
+        }
+    }
+}
+
+/// Matches a constant of any type whose bits are all zero, ie `0`, `0.0`, `null` or `zeroinitializer`.
+pub fn m_zero() -> ZeroPattern {
+    ZeroPattern
+}
+
+/// Matches undefined values. See `m_undef`.
+#[derive(Debug, Clone, Copy)]
+pub struct UndefPattern;
+
+impl<'ctx> Pattern<'ctx> for UndefPattern {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        unsafe {
+            LLVMIsUndef(value.as_value_ref()) == 1
+        }
+    }
+}
+
+/// Matches an `undef` value.
+pub fn m_undef() -> UndefPattern {
+    UndefPattern
+}
+
+/// Matches values matched by both of two patterns. See `m_both`.
+#[derive(Debug, Clone, Copy)]
+pub struct BothPattern<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<'ctx, A: Pattern<'ctx>, B: Pattern<'ctx>> Pattern<'ctx> for BothPattern<A, B> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        self.first.match_value(value) && self.second.match_value(value)
+    }
+
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        self.first.match_instruction(instruction) && self.second.match_instruction(instruction)
+    }
+}
+
+/// Matches values matched by both `first` and `second`. Commonly used to capture a value
+/// while also matching its structure, ie `m_both(m_value(&mut sum), m_add(m_any(), m_any()))`.
+pub fn m_both<'ctx, A: Pattern<'ctx>, B: Pattern<'ctx>>(first: A, second: B) -> BothPattern<A, B> {
+    BothPattern { first, second }
+}
+
+/// Matches values matched by either of two patterns. See `m_either`.
+#[derive(Debug, Clone, Copy)]
+pub struct EitherPattern<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<'ctx, A: Pattern<'ctx>, B: Pattern<'ctx>> Pattern<'ctx> for EitherPattern<A, B> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        self.first.match_value(value) || self.second.match_value(value)
+    }
+
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        self.first.match_instruction(instruction) || self.second.match_instruction(instruction)
+    }
+}
+
+/// Matches values matched by either `first` or `second`, trying `first` first.
+pub fn m_either<'ctx, A: Pattern<'ctx>, B: Pattern<'ctx>>(first: A, second: B) -> EitherPattern<A, B> {
+    EitherPattern { first, second }
+}
+
+/// Matches binary operators. See `m_binary` and friends such as `m_add`.
+#[derive(Debug, Clone, Copy)]
+pub struct BinaryPattern<L, R> {
+    opcode: InstructionOpcode,
+    lhs: L,
+    rhs: R,
+    commutative: bool,
+}
+
+impl<'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>> Pattern<'ctx> for BinaryPattern<L, R> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        match value.as_instruction_value() {
+            Some(instruction) => self.match_instruction(instruction),
+            None => false,
+        }
+    }
+
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        if instruction.get_opcode() != self.opcode || instruction.get_num_operands() != 2 {
+            return false;
+        }
+
+        let (lhs, rhs) = match (get_value_operand(instruction, 0), get_value_operand(instruction, 1)) {
+            (Some(lhs), Some(rhs)) => (lhs, rhs),
+            _ => return false,
+        };
+
+        if self.lhs.match_value(lhs) && self.rhs.match_value(rhs) {
+            return true;
+        }
+
+        self.commutative && self.lhs.match_value(rhs) && self.rhs.match_value(lhs)
+    }
+}
+
+/// Matches a binary operator with the given opcode whose operands match `lhs` and `rhs`.
+pub fn m_binary<'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>>(opcode: InstructionOpcode, lhs: L, rhs: R) -> BinaryPattern<L, R> {
+    BinaryPattern { opcode, lhs, rhs, commutative: false }
+}
+
+/// Matches a binary operator with the given opcode whose operands match `lhs` and `rhs`
+/// in either order.
+pub fn m_c_binary<'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>>(opcode: InstructionOpcode, lhs: L, rhs: R) -> BinaryPattern<L, R> {
+    BinaryPattern { opcode, lhs, rhs, commutative: true }
+}
+
+macro_rules! binary_patterns {
+    ($($(#[$attr:meta])* $name:ident => $constructor:ident($opcode:ident);)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name<'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>>(lhs: L, rhs: R) -> BinaryPattern<L, R> {
+                $constructor(InstructionOpcode::$opcode, lhs, rhs)
+            }
+        )*
+    };
+}
+
+binary_patterns! {
+    /// Matches an `add` instruction.
+    m_add => m_binary(Add);
+    /// Matches an `fadd` instruction.
+    m_fadd => m_binary(FAdd);
+    /// Matches a `sub` instruction.
+    m_sub => m_binary(Sub);
+    /// Matches an `fsub` instruction.
+    m_fsub => m_binary(FSub);
+    /// Matches a `mul` instruction.
+    m_mul => m_binary(Mul);
+    /// Matches an `fmul` instruction.
+    m_fmul => m_binary(FMul);
+    /// Matches a `udiv` instruction.
+    m_udiv => m_binary(UDiv);
+    /// Matches an `sdiv` instruction.
+    m_sdiv => m_binary(SDiv);
+    /// Matches an `fdiv` instruction.
+    m_fdiv => m_binary(FDiv);
+    /// Matches a `urem` instruction.
+    m_urem => m_binary(URem);
+    /// Matches an `srem` instruction.
+    m_srem => m_binary(SRem);
+    /// Matches an `frem` instruction.
+    m_frem => m_binary(FRem);
+    /// Matches a `shl` instruction.
+    m_shl => m_binary(Shl);
+    /// Matches an `lshr` instruction.
+    m_lshr => m_binary(LShr);
+    /// Matches an `ashr` instruction.
+    m_ashr => m_binary(AShr);
+    /// Matches an `and` instruction.
+    m_and => m_binary(And);
+    /// Matches an `or` instruction.
+    m_or => m_binary(Or);
+    /// Matches a `xor` instruction.
+    m_xor => m_binary(Xor);
+    /// Matches an `add` instruction with operands in either order.
+    m_c_add => m_c_binary(Add);
+    /// Matches an `fadd` instruction with operands in either order.
+    m_c_fadd => m_c_binary(FAdd);
+    /// Matches a `mul` instruction with operands in either order.
+    m_c_mul => m_c_binary(Mul);
+    /// Matches an `fmul` instruction with operands in either order.
+    m_c_fmul => m_c_binary(FMul);
+    /// Matches an `and` instruction with operands in either order.
+    m_c_and => m_c_binary(And);
+    /// Matches an `or` instruction with operands in either order.
+    m_c_or => m_c_binary(Or);
+    /// Matches a `xor` instruction with operands in either order.
+    m_c_xor => m_c_binary(Xor);
+}
+
+/// Matches a bitwise negation, ie `xor x, -1` with operands in either order.
+pub fn m_not<'ctx, P: Pattern<'ctx>>(value: P) -> BinaryPattern<P, ConstIntPattern> {
+    m_c_xor(value, m_all_ones())
+}
+
+/// Matches an integer negation, ie `sub 0, x`.
+pub fn m_neg<'ctx, P: Pattern<'ctx>>(value: P) -> BinaryPattern<ZeroPattern, P> {
+    m_sub(m_zero(), value)
+}
+
+/// Matches instructions with a single operand, such as casts. See `m_unary` and friends such as `m_zext`.
+#[derive(Debug, Clone, Copy)]
+pub struct UnaryPattern<P> {
+    opcode: InstructionOpcode,
+    operand: P,
+}
+
+impl<'ctx, P: Pattern<'ctx>> Pattern<'ctx> for UnaryPattern<P> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        match value.as_instruction_value() {
+            Some(instruction) => self.match_instruction(instruction),
+            None => false,
+        }
+    }
+
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        if instruction.get_opcode() != self.opcode || instruction.get_num_operands() != 1 {
+            return false;
+        }
+
+        match get_value_operand(instruction, 0) {
+            Some(operand) => self.operand.match_value(operand),
+            None => false,
+        }
+    }
+}
+
+/// Matches an instruction with the given opcode whose single operand matches `operand`.
+pub fn m_unary<'ctx, P: Pattern<'ctx>>(opcode: InstructionOpcode, operand: P) -> UnaryPattern<P> {
+    UnaryPattern { opcode, operand }
+}
+
+macro_rules! unary_patterns {
+    ($($(#[$attr:meta])* $name:ident => $opcode:ident;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name<'ctx, P: Pattern<'ctx>>(operand: P) -> UnaryPattern<P> {
+                m_unary(InstructionOpcode::$opcode, operand)
+            }
+        )*
+    };
+}
+
+unary_patterns! {
+    /// Matches a `trunc` instruction.
+    m_trunc => Trunc;
+    /// Matches a `zext` instruction.
+    m_zext => ZExt;
+    /// Matches a `sext` instruction.
+    m_sext => SExt;
+    /// Matches an `fptrunc` instruction.
+    m_fp_trunc => FPTrunc;
+    /// Matches an `fpext` instruction.
+    m_fp_ext => FPExt;
+    /// Matches an `fptoui` instruction.
+    m_fp_to_ui => FPToUI;
+    /// Matches an `fptosi` instruction.
+    m_fp_to_si => FPToSI;
+    /// Matches a `uitofp` instruction.
+    m_ui_to_fp => UIToFP;
+    /// Matches an `sitofp` instruction.
+    m_si_to_fp => SIToFP;
+    /// Matches a `ptrtoint` instruction.
+    m_ptr_to_int => PtrToInt;
+    /// Matches an `inttoptr` instruction.
+    m_int_to_ptr => IntToPtr;
+    /// Matches a `bitcast` instruction.
+    m_bitcast => BitCast;
+    /// Matches a `load` instruction from a pointer matching `operand`.
+    m_load => Load;
+}
+
+/// Matches an `fneg` instruction.
+#[llvm_versions(8.0..=latest)]
+pub fn m_fneg<'ctx, P: Pattern<'ctx>>(operand: P) -> UnaryPattern<P> {
+    m_unary(InstructionOpcode::FNeg, operand)
+}
+
+/// Matches an `icmp` instruction. See `m_icmp` and `m_any_icmp`.
+#[derive(Debug)]
+pub struct ICmpPattern<'a, L, R> {
+    predicate: Option<IntPredicate>,
+    capture: Option<&'a mut Option<IntPredicate>>,
+    lhs: L,
+    rhs: R,
+}
+
+impl<'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>> Pattern<'ctx> for ICmpPattern<'_, L, R> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        match value.as_instruction_value() {
+            Some(instruction) => self.match_instruction(instruction),
+            None => false,
+        }
+    }
+
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        if instruction.get_opcode() != InstructionOpcode::ICmp {
+            return false;
+        }
+
+        let predicate = instruction.get_icmp_predicate();
+
+        if self.predicate.is_some() && predicate != self.predicate {
+            return false;
+        }
+
+        let matched = match (get_value_operand(instruction, 0), get_value_operand(instruction, 1)) {
+            (Some(lhs), Some(rhs)) => self.lhs.match_value(lhs) && self.rhs.match_value(rhs),
+            _ => false,
+        };
+
+        if matched {
+            if let Some(capture) = self.capture.as_mut() {
+                **capture = predicate;
+            }
+        }
+
+        matched
+    }
+}
+
+/// Matches an `icmp` instruction with the given predicate.
+pub fn m_icmp<'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>>(predicate: IntPredicate, lhs: L, rhs: R) -> ICmpPattern<'static, L, R> {
+    ICmpPattern { predicate: Some(predicate), capture: None, lhs, rhs }
+}
+
+/// Matches an `icmp` instruction with any predicate, capturing the predicate.
+pub fn m_any_icmp<'a, 'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>>(predicate: &'a mut Option<IntPredicate>, lhs: L, rhs: R) -> ICmpPattern<'a, L, R> {
+    ICmpPattern { predicate: None, capture: Some(predicate), lhs, rhs }
+}
+
+/// Matches an `fcmp` instruction. See `m_fcmp` and `m_any_fcmp`.
+#[derive(Debug)]
+pub struct FCmpPattern<'a, L, R> {
+    predicate: Option<FloatPredicate>,
+    capture: Option<&'a mut Option<FloatPredicate>>,
+    lhs: L,
+    rhs: R,
+}
+
+impl<'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>> Pattern<'ctx> for FCmpPattern<'_, L, R> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        match value.as_instruction_value() {
+            Some(instruction) => self.match_instruction(instruction),
+            None => false,
+        }
+    }
+
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        if instruction.get_opcode() != InstructionOpcode::FCmp {
+            return false;
+        }
+
+        let predicate = instruction.get_fcmp_predicate();
+
+        if self.predicate.is_some() && predicate != self.predicate {
+            return false;
+        }
+
+        let matched = match (get_value_operand(instruction, 0), get_value_operand(instruction, 1)) {
+            (Some(lhs), Some(rhs)) => self.lhs.match_value(lhs) && self.rhs.match_value(rhs),
+            _ => false,
+        };
+
+        if matched {
+            if let Some(capture) = self.capture.as_mut() {
+                **capture = predicate;
+            }
+        }
+
+        matched
+    }
+}
+
+/// Matches an `fcmp` instruction with the given predicate.
+pub fn m_fcmp<'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>>(predicate: FloatPredicate, lhs: L, rhs: R) -> FCmpPattern<'static, L, R> {
+    FCmpPattern { predicate: Some(predicate), capture: None, lhs, rhs }
+}
+
+/// Matches an `fcmp` instruction with any predicate, capturing the predicate.
+pub fn m_any_fcmp<'a, 'ctx, L: Pattern<'ctx>, R: Pattern<'ctx>>(predicate: &'a mut Option<FloatPredicate>, lhs: L, rhs: R) -> FCmpPattern<'a, L, R> {
+    FCmpPattern { predicate: None, capture: Some(predicate), lhs, rhs }
+}
+
+/// Matches a `select` instruction. See `m_select`.
+#[derive(Debug, Clone, Copy)]
+pub struct SelectPattern<C, T, E> {
+    condition: C,
+    then_value: T,
+    else_value: E,
+}
+
+impl<'ctx, C: Pattern<'ctx>, T: Pattern<'ctx>, E: Pattern<'ctx>> Pattern<'ctx> for SelectPattern<C, T, E> {
+    fn match_value(&mut self, value: BasicValueEnum<'ctx>) -> bool {
+        match value.as_instruction_value() {
+            Some(instruction) => self.match_instruction(instruction),
+            None => false,
+        }
+    }
+
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        if instruction.get_opcode() != InstructionOpcode::Select {
+            return false;
+        }
+
+        match (get_value_operand(instruction, 0), get_value_operand(instruction, 1), get_value_operand(instruction, 2)) {
+            (Some(condition), Some(then_value), Some(else_value)) => {
+                self.condition.match_value(condition) && self.then_value.match_value(then_value) && self.else_value.match_value(else_value)
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Matches a `select` instruction whose operands match `condition`, `then_value` and `else_value`.
+pub fn m_select<'ctx, C: Pattern<'ctx>, T: Pattern<'ctx>, E: Pattern<'ctx>>(condition: C, then_value: T, else_value: E) -> SelectPattern<C, T, E> {
+    SelectPattern { condition, then_value, else_value }
+}
+
+/// Matches a `store` instruction. See `m_store`.
+#[derive(Debug, Clone, Copy)]
+pub struct StorePattern<V, P> {
+    value: V,
+    pointer: P,
+}
+
+impl<'ctx, V: Pattern<'ctx>, P: Pattern<'ctx>> Pattern<'ctx> for StorePattern<V, P> {
+    fn match_value(&mut self, _value: BasicValueEnum<'ctx>) -> bool {
+        // Stores never produce a value
+        false
+    }
+
+    fn match_instruction(&mut self, instruction: InstructionValue<'ctx>) -> bool {
+        if instruction.get_opcode() != InstructionOpcode::Store {
+            return false;
+        }
+
+        match (get_value_operand(instruction, 0), get_value_operand(instruction, 1)) {
+            (Some(value), Some(pointer)) => self.value.match_value(value) && self.pointer.match_value(pointer),
+            _ => false,
+        }
+    }
+}
+
+/// Matches a `store` instruction of a value matching `value` to a pointer matching `pointer`.
+/// As stores do not produce a value, this pattern can only be matched with `match_instruction`.
+pub fn m_store<'ctx, V: Pattern<'ctx>, P: Pattern<'ctx>>(value: V, pointer: P) -> StorePattern<V, P> {
+    StorePattern { value, pointer }
+}
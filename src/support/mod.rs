@@ -10,6 +10,8 @@ use std::error::Error;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::ffi::{CString, CStr};
 use std::ops::Deref;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
 
 /// An owned LLVM String. Also known as a LLVM Message
 #[derive(Eq)]
@@ -180,6 +182,32 @@ pub(crate) fn to_c_str<'s>(mut s: &'s str) -> Cow<'s, CStr> {
     }
 }
 
+/// Runs `f`, a Rust callback invoked from LLVM or JIT code, aborting the process if it panics.
+/// Unwinding out of an `extern "C"` function into foreign frames is undefined behavior, and
+/// the panic cannot be reported through LLVM, so aborting is the only sound option.
+pub(crate) fn abort_on_panic<R>(f: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(_) => process::abort(),
+    }
+}
+
+/// Escapes a string so that it can be embedded in a double quoted DOT (graphviz) string.
+pub(crate) fn escape_dot_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for ch in s.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\l"),
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
 #[test]
 fn test_to_c_str() {
     // TODO: If we raise our MSRV to >= 1.42 we can use matches!() here or
@@ -192,3 +220,10 @@ fn test_to_c_str() {
         panic!();
     }
 }
+
+#[test]
+fn test_escape_dot_string() {
+    assert_eq!(escape_dot_string("plain"), "plain");
+    assert_eq!(escape_dot_string("a \"quoted\" \\ name"), "a \\\"quoted\\\" \\\\ name");
+    assert_eq!(escape_dot_string("line\n"), "line\\l");
+}
@@ -0,0 +1,853 @@
+//! Maps code addresses back to functions and source locations.
+//!
+//! A `Symbolizer` is built from the symbols of an `ObjectFile`, from the object an `ExecutionEngine`
+//! loaded, or from an `ExecutionEngine` alone.
+//! When the object holds a DWARF line table, as emitted for modules with debug info from a
+//! `DebugInfoBuilder`, addresses are also mapped to files and lines.
+
+use crate::execution_engine::ExecutionEngine;
+use crate::module::Module;
+use crate::object_file::{ObjectFile, Section, SymbolKind};
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+
+/// The function, and possibly source location, an address belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolizedAddress {
+    function_name: String,
+    offset: u64,
+    location: Option<SourceLocation>,
+}
+
+impl SymbolizedAddress {
+    /// Gets the name of the function containing the address.
+    pub fn get_function_name(&self) -> &str {
+        &self.function_name
+    }
+
+    /// Gets the address' offset in bytes from the start of its function.
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Gets the source location the address was compiled from, if there is line table information for it.
+    pub fn get_location(&self) -> Option<&SourceLocation> {
+        self.location.as_ref()
+    }
+}
+
+/// A position in a source file, taken from a DWARF line table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl SourceLocation {
+    /// Gets the path of the source file, joined to its directory unless that is the compilation directory.
+    pub fn get_file(&self) -> &str {
+        &self.file
+    }
+
+    /// Gets the one based line number.
+    pub fn get_line(&self) -> u32 {
+        self.line
+    }
+
+    /// Gets the one based column number, or 0 if it is unknown.
+    pub fn get_column(&self) -> u32 {
+        self.column
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FunctionRange {
+    name: String,
+    start: u64,
+    // Unknown sizes extend to the next function
+    size: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+struct LineRow {
+    address: u64,
+    file: Option<usize>,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Debug, Clone)]
+struct LineSequence {
+    start: u64,
+    end: u64,
+    rows: Vec<LineRow>,
+}
+
+/// Maps code addresses back to the functions, and source locations, they belong to.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::OptimizationLevel;
+/// use inkwell::context::Context;
+/// use inkwell::symbolizer::Symbolizer;
+/// use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine};
+///
+/// Target::initialize_native(&InitializationConfig::default()).unwrap();
+///
+/// let context = Context::create();
+/// let module = context.create_module("crashy");
+///
+/// // Build module, with debug info from a DebugInfoBuilder, here
+///
+/// // Compile the module as MCJIT will, which defaults to the large code model on x86-64
+/// let triple = TargetMachine::get_default_triple();
+/// let target_machine = Target::from_triple(&triple)
+///     .unwrap()
+///     .create_target_machine(&triple, "", "", OptimizationLevel::None, RelocMode::Default, CodeModel::Large)
+///     .unwrap();
+/// let object_file = target_machine
+///     .write_to_memory_buffer(&module, FileType::Object)
+///     .unwrap()
+///     .create_object_file()
+///     .unwrap();
+/// let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+/// let symbolizer = Symbolizer::from_execution_engine_object(&execution_engine, &module, &object_file).unwrap();
+/// let crash_address = 0x7f00_dead_beef;
+///
+/// if let Some(symbolized) = symbolizer.symbolize(crash_address) {
+///     println!("{}+{:#x}", symbolized.get_function_name(), symbolized.get_offset());
+///
+///     if let Some(location) = symbolized.get_location() {
+///         println!("    at {}:{}:{}", location.get_file(), location.get_line(), location.get_column());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Symbolizer {
+    functions: Vec<FunctionRange>,
+    files: Vec<String>,
+    sequences: Vec<LineSequence>,
+}
+
+impl Symbolizer {
+    /// Creates a `Symbolizer` for the addresses of functions within `object_file` itself. The text
+    /// sections of relocatable objects are laid out one after another, in order, from address 0, so
+    /// with a single text section addresses are offsets into it.
+    ///
+    /// Only linked objects may have DWARF 5 line tables, as relocatable objects name the files in
+    /// them through relocations, which can't be read.
+    pub fn from_object_file(object_file: &ObjectFile) -> Result<Self, &'static str> {
+        let mut next_base = 0;
+
+        Symbolizer::build(object_file, |section| {
+            if section.get_address() != 0 {
+                return Some(section.get_address());
+            }
+
+            let base = (next_base + 15) & !15;
+
+            next_base = base + section.size();
+
+            Some(base)
+        })
+    }
+
+    /// Creates a `Symbolizer` for the code `execution_engine` loaded for `module`, from `object_file`,
+    /// which `module` was compiled to with the same options as the `ExecutionEngine` uses, such as by
+    /// `TargetMachine::write_to_memory_buffer`. Each of its text sections is placed where the
+    /// `ExecutionEngine` loaded the functions defined in it, so an `Err` is returned if none of them
+    /// were loaded, or if they weren't laid out as in `object_file`. The object is relocatable, so
+    /// `module`'s debug info must not use DWARF 5, as with `from_object_file`.
+    pub fn from_execution_engine_object(execution_engine: &ExecutionEngine, module: &Module, object_file: &ObjectFile) -> Result<Self, &'static str> {
+        let loaded: HashMap<String, u64> = Symbolizer::from_execution_engine(execution_engine, module).functions
+            .into_iter()
+            .map(|function| (function.name, function.start))
+            .collect();
+
+        // Each text section is loaded as a whole, so any function loaded from it places it
+        let symbolizer = Symbolizer::build(object_file, |section| {
+            object_file.get_symbols()
+                .filter(|symbol| symbol.get_kind() == SymbolKind::Text && symbol.get_section() == Some(section))
+                .find_map(|symbol| {
+                    let start = loaded.get(symbol.get_name()?.to_str().ok()?)?;
+                    let offset = symbol.get_address().wrapping_sub(section.get_address());
+
+                    Some(start.wrapping_sub(offset))
+                })
+        })?;
+        let mut placed = 0;
+
+        for function in &symbolizer.functions {
+            match loaded.get(&function.name) {
+                Some(&start) if start == function.start => placed += 1,
+                Some(_) => return Err("Object file's functions are not laid out as the ExecutionEngine loaded them"),
+                None => {},
+            }
+        }
+
+        if placed == 0 {
+            return Err("Object file defines none of the functions the ExecutionEngine loaded for the module");
+        }
+
+        Ok(symbolizer)
+    }
+
+    /// Creates a `Symbolizer` for the functions defined in `module`, which must have been added to
+    /// `execution_engine`. Without an object file, function sizes and source locations are unknown, so
+    /// each function is assumed to extend to the start of the next.
+    pub fn from_execution_engine(execution_engine: &ExecutionEngine, module: &Module) -> Self {
+        let mut functions = Vec::new();
+        let mut function = module.get_first_function();
+
+        while let Some(current) = function {
+            function = current.get_next_function();
+
+            if current.count_basic_blocks() == 0 {
+                continue;
+            }
+
+            let name = current.get_name().to_string_lossy().into_owned();
+
+            if let Ok(address) = execution_engine.get_function_address(&name) {
+                functions.push(FunctionRange {
+                    name,
+                    start: address as u64,
+                    size: None,
+                });
+            }
+        }
+
+        functions.sort_by_key(|function| function.start);
+
+        Symbolizer {
+            functions,
+            files: Vec::new(),
+            sequences: Vec::new(),
+        }
+    }
+
+    // Collects the functions and line table of object_file, placing each text section at the base
+    // address get_base returns for it, if any
+    fn build<'a, F>(object_file: &'a ObjectFile, mut get_base: F) -> Result<Self, &'static str>
+    where
+        F: FnMut(&Section<'a>) -> Option<u64>,
+    {
+        let symbols: Vec<_> = object_file.get_symbols().collect();
+        let bases: Vec<(Section<'a>, Option<u64>)> = object_file.get_sections()
+            .filter(Section::is_text)
+            .map(|section| {
+                let base = get_base(&section);
+
+                (section, base)
+            })
+            .collect();
+
+        let base_of = |section: &Section| bases.iter().find(|(text, _)| text == section).and_then(|&(_, base)| base);
+        let mut functions: Vec<FunctionRange> = symbols.iter()
+            .filter(|symbol| symbol.get_kind() == SymbolKind::Text)
+            .filter_map(|symbol| {
+                let name = symbol.get_name()?.to_string_lossy();
+                let section = symbol.get_section()?;
+
+                if name.is_empty() || name.starts_with('.') {
+                    return None;
+                }
+
+                // Symbols placed outside their own section can't be placed relative to it
+                let offset = symbol.get_address().checked_sub(section.get_address())?;
+
+                Some(FunctionRange {
+                    name: name.into_owned(),
+                    start: base_of(section)?.wrapping_add(offset),
+                    size: Some(symbol.size()).filter(|&size| size != 0),
+                })
+            })
+            .collect();
+
+        functions.sort_by_key(|function| function.start);
+
+        let mut symbolizer = Symbolizer {
+            functions,
+            files: Vec::new(),
+            sequences: Vec::new(),
+        };
+
+        let debug_line = object_file.get_sections()
+            .find(|section| section.get_name().map_or(false, |name| name.to_bytes() == b".debug_line" || name.to_bytes() == b"__debug_line"));
+        let debug_line = match debug_line {
+            Some(debug_line) => debug_line,
+            None => return Ok(symbolizer),
+        };
+
+        // Maps the offsets of relocated addresses in the line table to the addresses they're relative to
+        let relocations: HashMap<u64, Option<u64>> = debug_line.get_relocations()
+            .filter_map(|relocation| {
+                let symbol = relocation.get_symbol()?;
+                let section = symbol.get_section()?;
+                let offset = symbol.get_address().checked_sub(section.get_address())?;
+                let target = base_of(section).map(|base| base.wrapping_add(offset));
+
+                Some((relocation.get_offset(), target))
+            })
+            .collect();
+        let relocated_offsets: HashSet<u64> = debug_line.get_relocations().map(|relocation| relocation.get_offset()).collect();
+        let find_section = |names: &[&[u8]]| {
+            object_file.get_sections()
+                .find(|section| section.get_name().map_or(false, |name| names.contains(&name.to_bytes())))
+                .map(|section| section.get_contents())
+        };
+        let debug_str = find_section(&[b".debug_str", b"__debug_str"]);
+        let debug_line_str = find_section(&[b".debug_line_str", b"__debug_line_str"]);
+        // DWARF 5 line tables may name files by their offsets into .debug_str or .debug_line_str
+        let read_string = |offset: u64, form: StringForm, string_offset: u64| -> Result<String, &'static str> {
+            // Relocatable objects may only hold the offset in a relocation's addend, which can't be read
+            if relocated_offsets.contains(&offset) {
+                return Err("DWARF 5 line tables which name files through relocations are not supported");
+            }
+
+            let strings = match form {
+                StringForm::Strp => debug_str,
+                StringForm::LineStrp => debug_line_str,
+            };
+            let strings = strings.ok_or(MALFORMED_LINE_TABLE)?;
+            let string_offset = string_offset.try_into().map_err(|_| MALFORMED_LINE_TABLE)?;
+
+            DwarfReader::new(strings, string_offset).read_str()
+        };
+        let resolve_address = |offset: u64, address: u64| -> Option<u64> {
+            if let Some(&target) = relocations.get(&offset) {
+                return target.map(|target| target.wrapping_add(address));
+            }
+
+            // Unrelocated addresses are already relative to the sections' own addresses
+            let (section, base) = bases.iter()
+                .find(|(section, _)| (section.get_address()..section.get_address() + section.size()).contains(&address))?;
+
+            base.map(|base| base.wrapping_add(address - section.get_address()))
+        };
+
+        symbolizer.read_line_table(debug_line.get_contents(), resolve_address, read_string)?;
+
+        Ok(symbolizer)
+    }
+
+    fn read_line_table<F, S>(&mut self, debug_line: &[u8], resolve_address: F, read_string: S) -> Result<(), &'static str>
+    where
+        F: Fn(u64, u64) -> Option<u64>,
+        S: Fn(u64, StringForm, u64) -> Result<String, &'static str>,
+    {
+        let mut offset = 0;
+
+        while offset < debug_line.len() {
+            offset = self.read_line_program(debug_line, offset, &resolve_address, &read_string)?;
+        }
+
+        self.sequences.sort_by_key(|sequence| sequence.start);
+
+        Ok(())
+    }
+
+    // Reads the line program for one compilation unit, returning the offset of the next
+    fn read_line_program<F, S>(&mut self, debug_line: &[u8], unit_offset: usize, resolve_address: &F, read_string: &S) -> Result<usize, &'static str>
+    where
+        F: Fn(u64, u64) -> Option<u64>,
+        S: Fn(u64, StringForm, u64) -> Result<String, &'static str>,
+    {
+        let mut reader = DwarfReader::new(debug_line, unit_offset);
+        let (unit_length, offset_size) = match reader.read_u32()? {
+            0xffff_ffff => (reader.read_u64()?, 8),
+            unit_length => (unit_length as u64, 4),
+        };
+        let unit_end = reader.offset_after(unit_length)?;
+
+        if unit_end > debug_line.len() {
+            return Err(MALFORMED_LINE_TABLE);
+        }
+
+        // Objects are rarely big endian, and the version is small enough to tell which is in use
+        let version = reader.read_u16()?;
+        let version = if (2..=5).contains(&version) {
+            version
+        } else if (2..=5).contains(&version.swap_bytes()) {
+            reader.big_endian = true;
+            version.swap_bytes()
+        } else {
+            return Err(MALFORMED_LINE_TABLE);
+        };
+
+        if version >= 5 {
+            let _address_size = reader.read_u8()?;
+            let _segment_selector_size = reader.read_u8()?;
+        }
+
+        let header_length = reader.read_offset(offset_size)?;
+        let program_start = reader.offset_after(header_length)?;
+        let min_instruction_length = reader.read_u8()? as u64;
+
+        if version >= 4 {
+            let _max_ops_per_instruction = reader.read_u8()?;
+        }
+
+        let _default_is_stmt = reader.read_u8()?;
+        let line_base = reader.read_u8()? as i8 as i64;
+        let line_range = reader.read_u8()?;
+        let opcode_base = reader.read_u8()?;
+
+        if line_range == 0 || opcode_base == 0 {
+            return Err(MALFORMED_LINE_TABLE);
+        }
+
+        let standard_opcode_lengths = reader.read_bytes(opcode_base as usize - 1)?.to_vec();
+        let file_names = if version >= 5 {
+            reader.read_v5_file_names(offset_size, read_string)?
+        } else {
+            reader.read_file_names()?
+        };
+        let file_base = self.files.len();
+
+        self.files.extend(file_names);
+
+        // DWARF 5 numbers files from 0 rather than 1
+        let first_file = if version >= 5 { 0 } else { 1 };
+        let mut reader = DwarfReader { offset: program_start, ..reader };
+        let mut sequence: Option<LineSequence> = None;
+        let mut address: Option<u64> = None;
+        let mut file = 1;
+        let mut line: i64 = 1;
+        let mut column = 0;
+
+        while reader.offset < unit_end {
+            let opcode = reader.read_u8()?;
+            let mut emit_row = false;
+
+            if opcode >= opcode_base {
+                let adjusted = (opcode - opcode_base) as u64;
+
+                address = address.map(|address| address.wrapping_add(adjusted / line_range as u64 * min_instruction_length));
+                line += line_base + (adjusted % line_range as u64) as i64;
+                emit_row = true;
+            } else if opcode == 0 {
+                let length = reader.read_uleb128()?;
+                let end = reader.offset_after(length)?;
+                let sub_opcode = reader.read_u8()?;
+
+                match sub_opcode {
+                    // DW_LNE_end_sequence
+                    1 => {
+                        if let (Some(mut finished), Some(end_address)) = (sequence.take(), address) {
+                            finished.end = end_address;
+                            self.sequences.push(finished);
+                        }
+
+                        address = None;
+                        file = 1;
+                        line = 1;
+                        column = 0;
+                    },
+                    // DW_LNE_set_address
+                    2 => {
+                        let address_offset = reader.offset as u64;
+                        let value = reader.read_uint(end.checked_sub(reader.offset).ok_or(MALFORMED_LINE_TABLE)?)?;
+
+                        address = resolve_address(address_offset, value);
+                    },
+                    // DW_LNE_define_file
+                    3 if version < 5 => {
+                        let name = reader.read_str()?;
+
+                        self.files.push(name);
+                    },
+                    _ => {},
+                }
+
+                reader.offset = end;
+            } else {
+                match opcode {
+                    // DW_LNS_copy
+                    1 => emit_row = true,
+                    // DW_LNS_advance_pc
+                    2 => {
+                        let advance = reader.read_uleb128()?;
+
+                        address = address.map(|address| address.wrapping_add(advance * min_instruction_length));
+                    },
+                    // DW_LNS_advance_line
+                    3 => line += reader.read_sleb128()?,
+                    // DW_LNS_set_file
+                    4 => file = reader.read_uleb128()?,
+                    // DW_LNS_set_column
+                    5 => column = reader.read_uleb128()?,
+                    // DW_LNS_const_add_pc
+                    8 => {
+                        let adjusted = (255 - opcode_base) as u64;
+
+                        address = address.map(|address| address.wrapping_add(adjusted / line_range as u64 * min_instruction_length));
+                    },
+                    // DW_LNS_fixed_advance_pc
+                    9 => {
+                        let advance = reader.read_u16()? as u64;
+
+                        address = address.map(|address| address.wrapping_add(advance));
+                    },
+                    // DW_LNS_negate_stmt, DW_LNS_set_basic_block, DW_LNS_set_prologue_end,
+                    // DW_LNS_set_epilogue_begin, DW_LNS_set_isa and unknown opcodes, whose operands are skipped
+                    _ => {
+                        for _ in 0..standard_opcode_lengths[opcode as usize - 1] {
+                            reader.read_uleb128()?;
+                        }
+                    },
+                }
+            }
+
+            // Rows for sections which couldn't be placed have no address and are dropped
+            if let (true, Some(row_address)) = (emit_row, address) {
+                let sequence = sequence.get_or_insert_with(|| LineSequence {
+                    start: row_address,
+                    end: row_address,
+                    rows: Vec::new(),
+                });
+
+                sequence.rows.push(LineRow {
+                    address: row_address,
+                    file: (file as usize).checked_sub(first_file).map(|index| index + file_base),
+                    line: line as u32,
+                    column: column as u32,
+                });
+            }
+        }
+
+        Ok(unit_end)
+    }
+
+    /// Finds the function, and source location if known, which `address` belongs to.
+    pub fn symbolize(&self, address: u64) -> Option<SymbolizedAddress> {
+        let index = match self.functions.binary_search_by_key(&address, |function| function.start) {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let function = &self.functions[index];
+        let end = match function.size {
+            Some(size) => Some(function.start + size),
+            None => self.functions.get(index + 1).map(|next| next.start),
+        };
+
+        if end.map_or(false, |end| address >= end) {
+            return None;
+        }
+
+        Some(SymbolizedAddress {
+            function_name: function.name.clone(),
+            offset: address - function.start,
+            location: self.find_location(address),
+        })
+    }
+
+    fn find_location(&self, address: u64) -> Option<SourceLocation> {
+        let sequence = self.sequences.iter().find(|sequence| sequence.start <= address && address < sequence.end)?;
+        let row = match sequence.rows.binary_search_by_key(&address, |row| row.address) {
+            Ok(index) => {
+                // Several rows may share an address, the last of which applies
+                let last = sequence.rows[index..].iter().take_while(|row| row.address == address).count();
+
+                &sequence.rows[index + last - 1]
+            },
+            Err(0) => return None,
+            Err(index) => &sequence.rows[index - 1],
+        };
+
+        if row.line == 0 {
+            return None;
+        }
+
+        Some(SourceLocation {
+            file: self.files.get(row.file?)?.clone(),
+            line: row.line,
+            column: row.column,
+        })
+    }
+}
+
+static MALFORMED_LINE_TABLE: &str = "Malformed DWARF line table";
+
+#[derive(Debug, Clone, Copy)]
+struct DwarfReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+}
+
+impl<'a> DwarfReader<'a> {
+    fn new(data: &'a [u8], offset: usize) -> Self {
+        DwarfReader {
+            data,
+            offset,
+            big_endian: false,
+        }
+    }
+
+    // Gets the offset len bytes past the current one
+    fn offset_after(&self, len: u64) -> Result<usize, &'static str> {
+        len.try_into().ok().and_then(|len| self.offset.checked_add(len)).ok_or(MALFORMED_LINE_TABLE)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], &'static str> {
+        let end = self.offset.checked_add(len).ok_or(MALFORMED_LINE_TABLE)?;
+        let bytes = self.data.get(self.offset..end).ok_or(MALFORMED_LINE_TABLE)?;
+
+        self.offset += len;
+
+        Ok(bytes)
+    }
+
+    fn read_uint(&mut self, size: usize) -> Result<u64, &'static str> {
+        if size > 8 {
+            return Err(MALFORMED_LINE_TABLE);
+        }
+
+        let bytes = self.read_bytes(size)?;
+        let value = if self.big_endian {
+            bytes.iter().fold(0, |value, &byte| value << 8 | byte as u64)
+        } else {
+            bytes.iter().rev().fold(0, |value, &byte| value << 8 | byte as u64)
+        };
+
+        Ok(value)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, &'static str> {
+        self.read_uint(1).map(|value| value as u8)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, &'static str> {
+        self.read_uint(2).map(|value| value as u16)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, &'static str> {
+        self.read_uint(4).map(|value| value as u32)
+    }
+
+    fn read_u64(&mut self) -> Result<u64, &'static str> {
+        self.read_uint(8)
+    }
+
+    fn read_offset(&mut self, offset_size: usize) -> Result<u64, &'static str> {
+        self.read_uint(offset_size)
+    }
+
+    fn read_uleb128(&mut self) -> Result<u64, &'static str> {
+        let mut value = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+
+            if shift < 64 {
+                value |= ((byte & 0x7f) as u64) << shift;
+            }
+
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn read_sleb128(&mut self) -> Result<i64, &'static str> {
+        let mut value = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+
+            if shift < 64 {
+                value |= ((byte & 0x7f) as i64) << shift;
+            }
+
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+
+                return Ok(value);
+            }
+        }
+    }
+
+    fn read_str(&mut self) -> Result<String, &'static str> {
+        let len = self.data.get(self.offset..)
+            .and_then(|data| data.iter().position(|&byte| byte == 0))
+            .ok_or(MALFORMED_LINE_TABLE)?;
+        let string = String::from_utf8_lossy(self.read_bytes(len)?).into_owned();
+
+        self.offset += 1;
+
+        Ok(string)
+    }
+
+    // Reads the DWARF 2 to 4 include directories and file names, joining each file to its directory
+    fn read_file_names(&mut self) -> Result<Vec<String>, &'static str> {
+        let mut directories = Vec::new();
+
+        loop {
+            let directory = self.read_str()?;
+
+            if directory.is_empty() {
+                break;
+            }
+
+            directories.push(directory);
+        }
+
+        let mut files = Vec::new();
+
+        loop {
+            let name = self.read_str()?;
+
+            if name.is_empty() {
+                return Ok(files);
+            }
+
+            let directory = self.read_uleb128()?;
+            let _modification_time = self.read_uleb128()?;
+            let _length = self.read_uleb128()?;
+
+            // Directory 0 is the compilation directory, which the line table doesn't record
+            let directory = (directory as usize).checked_sub(1).and_then(|index| directories.get(index));
+
+            files.push(join_path(directory.map(String::as_str), name));
+        }
+    }
+
+    // Reads the DWARF 5 directory and file name tables, joining each file to its directory. Names held
+    // in string sections are read with read_string, given the offset of their form and its value
+    fn read_v5_file_names<S>(&mut self, offset_size: usize, read_string: &S) -> Result<Vec<String>, &'static str>
+    where
+        S: Fn(u64, StringForm, u64) -> Result<String, &'static str>,
+    {
+        let directories: Vec<String> = self.read_v5_entries(offset_size, read_string)?
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let files = self.read_v5_entries(offset_size, read_string)?
+            .into_iter()
+            .map(|(name, directory)| {
+                // Directory 0 is the compilation directory, which is left implicit as in DWARF 4
+                let directory = Some(directory as usize).filter(|&index| index != 0).and_then(|index| directories.get(index));
+
+                join_path(directory.map(String::as_str), name)
+            })
+            .collect();
+
+        Ok(files)
+    }
+
+    fn read_v5_entries<S>(&mut self, offset_size: usize, read_string: &S) -> Result<Vec<(String, u64)>, &'static str>
+    where
+        S: Fn(u64, StringForm, u64) -> Result<String, &'static str>,
+    {
+        const DW_LNCT_PATH: u64 = 1;
+        const DW_LNCT_DIRECTORY_INDEX: u64 = 2;
+
+        let format_count = self.read_u8()?;
+        let mut formats = Vec::with_capacity(format_count as usize);
+
+        for _ in 0..format_count {
+            formats.push((self.read_uleb128()?, self.read_uleb128()?));
+        }
+
+        let count = self.read_uleb128()?;
+        let mut entries = Vec::new();
+
+        for _ in 0..count {
+            let mut name = String::new();
+            let mut directory = 0;
+
+            for &(content_type, form) in &formats {
+                let form_offset = self.offset as u64;
+                let value = self.read_form(form, offset_size)?;
+
+                match (content_type, value) {
+                    (DW_LNCT_PATH, FormValue::String(string)) => name = string,
+                    (DW_LNCT_PATH, FormValue::StringOffset(form, string_offset)) => name = read_string(form_offset, form, string_offset)?,
+                    (DW_LNCT_DIRECTORY_INDEX, FormValue::Uint(index)) => directory = index,
+                    _ => {},
+                }
+            }
+
+            entries.push((name, directory));
+        }
+
+        Ok(entries)
+    }
+
+    fn read_form(&mut self, form: u64, offset_size: usize) -> Result<FormValue, &'static str> {
+        let value = match form {
+            // DW_FORM_string
+            0x08 => FormValue::String(self.read_str()?),
+            // DW_FORM_data1, DW_FORM_data2, DW_FORM_data4 and DW_FORM_data8
+            0x0b => FormValue::Uint(self.read_uint(1)?),
+            0x05 => FormValue::Uint(self.read_uint(2)?),
+            0x06 => FormValue::Uint(self.read_uint(4)?),
+            0x07 => FormValue::Uint(self.read_uint(8)?),
+            // DW_FORM_udata
+            0x0f => FormValue::Uint(self.read_uleb128()?),
+            // DW_FORM_strp and DW_FORM_line_strp
+            0x0e => FormValue::StringOffset(StringForm::Strp, self.read_offset(offset_size)?),
+            0x1f => FormValue::StringOffset(StringForm::LineStrp, self.read_offset(offset_size)?),
+            // DW_FORM_sec_offset
+            0x17 => {
+                self.read_offset(offset_size)?;
+
+                FormValue::Other
+            },
+            // DW_FORM_data16, as used for MD5 checksums
+            0x1e => {
+                self.read_bytes(16)?;
+
+                FormValue::Other
+            },
+            // DW_FORM_block
+            0x09 => {
+                let len = self.read_uleb128()?;
+
+                self.offset = self.offset_after(len)?;
+
+                FormValue::Other
+            },
+            _ => return Err(MALFORMED_LINE_TABLE),
+        };
+
+        Ok(value)
+    }
+}
+
+#[derive(Debug)]
+enum FormValue {
+    String(String),
+    StringOffset(StringForm, u64),
+    Uint(u64),
+    Other,
+}
+
+// The string sections a DWARF 5 form may refer to
+#[derive(Debug, Clone, Copy)]
+enum StringForm {
+    // .debug_str
+    Strp,
+    // .debug_line_str
+    LineStrp,
+}
+
+fn join_path(directory: Option<&str>, file: String) -> String {
+    match directory {
+        Some(directory) if !file.starts_with('/') && !directory.is_empty() => format!("{}/{}", directory.trim_end_matches('/'), file),
+        _ => file,
+    }
+}
@@ -18,6 +18,7 @@ use llvm_sys::target_machine::{
     LLVMTargetHasTargetMachine, LLVMTargetMachineEmitToFile, LLVMTargetMachineEmitToMemoryBuffer,
     LLVMTargetMachineRef, LLVMTargetRef,
 };
+use llvm_sys::core::LLVMAddTargetDependentFunctionAttr;
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 
@@ -27,8 +28,8 @@ use crate::memory_buffer::MemoryBuffer;
 use crate::module::Module;
 use crate::passes::PassManager;
 use crate::support::{to_c_str, LLVMString};
-use crate::types::{AnyType, AsTypeRef, IntType, StructType};
-use crate::values::{AsValueRef, GlobalValue};
+use crate::types::{AnyType, ArrayType, AsTypeRef, IntType, StructType, VectorType};
+use crate::values::{AsValueRef, FunctionValue, GlobalValue};
 use crate::{AddressSpace, OptimizationLevel};
 
 use std::default::Default;
@@ -48,6 +49,19 @@ pub enum CodeModel {
     Large,
 }
 
+impl CodeModel {
+    pub(crate) fn as_llvm_code_model(self) -> LLVMCodeModel {
+        match self {
+            CodeModel::Default => LLVMCodeModel::LLVMCodeModelDefault,
+            CodeModel::JITDefault => LLVMCodeModel::LLVMCodeModelJITDefault,
+            CodeModel::Small => LLVMCodeModel::LLVMCodeModelSmall,
+            CodeModel::Kernel => LLVMCodeModel::LLVMCodeModelKernel,
+            CodeModel::Medium => LLVMCodeModel::LLVMCodeModelMedium,
+            CodeModel::Large => LLVMCodeModel::LLVMCodeModelLarge,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum RelocMode {
     Default,
@@ -71,6 +85,88 @@ impl FileType {
     }
 }
 
+/// Which functions keep a frame pointer.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum FramePointer {
+    /// Frame pointers may be eliminated from any function.
+    None,
+    /// Frame pointers are kept by functions which call other functions.
+    NonLeaf,
+    /// Frame pointers are kept by every function.
+    All,
+}
+
+impl FramePointer {
+    #[llvm_versions(10.0..=latest)]
+    fn as_fn_attributes(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            FramePointer::None => &[("frame-pointer", "none")],
+            FramePointer::NonLeaf => &[("frame-pointer", "non-leaf")],
+            FramePointer::All => &[("frame-pointer", "all")],
+        }
+    }
+
+    #[llvm_versions(3.6..=9.0)]
+    fn as_fn_attributes(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            FramePointer::None => &[("no-frame-pointer-elim", "false")],
+            FramePointer::NonLeaf => &[("no-frame-pointer-elim", "false"), ("no-frame-pointer-elim-non-leaf", "")],
+            FramePointer::All => &[("no-frame-pointer-elim", "true")],
+        }
+    }
+}
+
+/// An optional WebAssembly feature, which code may only use when it is enabled.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum WasmFeature {
+    /// Shared memories and atomic memory accesses, as used for threads.
+    Atomics,
+    /// The `memory.copy` and `memory.fill` instructions, along with passive data segments.
+    BulkMemory,
+    /// The `try`, `catch` and `throw` instructions.
+    ExceptionHandling,
+    /// Functions and blocks which return several values.
+    MultiValue,
+    /// Importing and exporting mutable globals.
+    MutableGlobals,
+    /// Float to integer conversions which saturate rather than trap when out of range.
+    NontrappingFptoint,
+    /// The `externref` and `funcref` types, and multiple tables.
+    ReferenceTypes,
+    /// Sign extension instructions, such as `i32.extend8_s`.
+    SignExt,
+    /// 128 bit SIMD vectors.
+    Simd128,
+    /// The `return_call` and `return_call_indirect` instructions.
+    TailCall,
+}
+
+impl WasmFeature {
+    /// Gets the name LLVM knows this feature by, such as "bulk-memory".
+    pub fn get_name(self) -> &'static str {
+        match self {
+            WasmFeature::Atomics => "atomics",
+            WasmFeature::BulkMemory => "bulk-memory",
+            WasmFeature::ExceptionHandling => "exception-handling",
+            WasmFeature::MultiValue => "multivalue",
+            WasmFeature::MutableGlobals => "mutable-globals",
+            WasmFeature::NontrappingFptoint => "nontrapping-fptoint",
+            WasmFeature::ReferenceTypes => "reference-types",
+            WasmFeature::SignExt => "sign-ext",
+            WasmFeature::Simd128 => "simd128",
+            WasmFeature::TailCall => "tail-call",
+        }
+    }
+
+    /// Joins `features` into a string enabling each of them, such as "+simd128,+bulk-memory", as
+    /// taken by `Target::create_target_machine` and the "target-features" function attribute.
+    pub fn to_features_string(features: &[WasmFeature]) -> String {
+        let features: Vec<String> = features.iter().map(|feature| format!("+{}", feature.get_name())).collect();
+
+        features.join(",")
+    }
+}
+
 // TODO: Doc: Base gets you TargetMachine support, machine_code gets you asm_backend
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct InitializationConfig {
@@ -142,6 +238,609 @@ impl fmt::Display for TargetTriple {
     }
 }
 
+impl TargetTriple {
+    // Splits the triple into its normalized components, of which there are at least four
+    fn get_components(&self) -> Vec<&str> {
+        let mut components = normalize_components(self.as_str().to_str().unwrap_or(""));
+
+        if components.len() < 4 {
+            components.resize(4, "");
+        }
+
+        components
+    }
+
+    /// Gets the architecture of this triple, without its sub-architecture.
+    ///
+    /// Components are read from the triple as `TargetMachine::normalize_triple` would normalize it, so
+    /// "x86_64-linux-gnu" has no vendor and targets Linux, and MinGW and Cygwin triples such as
+    /// "x86_64-w64-mingw32" target `OperatingSystem::Windows`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::targets::{Arch, Environment, OperatingSystem, TargetTriple, Vendor};
+    ///
+    /// let triple = TargetTriple::create("armv7a-unknown-linux-gnueabihf");
+    ///
+    /// assert_eq!(triple.get_arch(), Arch::Arm);
+    /// assert_eq!(triple.get_sub_arch(), "v7a");
+    /// assert_eq!(triple.get_vendor(), Vendor::Unknown);
+    /// assert_eq!(triple.get_os(), OperatingSystem::Linux);
+    /// assert_eq!(triple.get_environment(), Environment::GnuEabiHf);
+    /// assert_eq!(triple.pointer_width(), Some(32));
+    /// ```
+    pub fn get_arch(&self) -> Arch {
+        Arch::parse(self.get_components()[0]).0
+    }
+
+    /// Gets the sub-architecture of this triple, such as "v7a" for "armv7a", or an empty string
+    /// if there is none.
+    pub fn get_sub_arch(&self) -> &str {
+        Arch::parse(self.get_components()[0]).1
+    }
+
+    /// Gets the vendor of this triple, or `Vendor::Unknown` if it has none, as with "x86_64-linux-gnu".
+    pub fn get_vendor(&self) -> Vendor {
+        let vendor = self.get_components()[1];
+
+        Vendor::SPELLINGS.iter()
+            .find(|&&(spelling, _)| spelling == vendor)
+            .map_or(Vendor::Unknown, |&(_, vendor)| vendor)
+    }
+
+    /// Gets the operating system of this triple, ignoring any version suffix.
+    pub fn get_os(&self) -> OperatingSystem {
+        parse_prefix(OperatingSystem::SPELLINGS, self.get_components()[2]).map_or(OperatingSystem::Unknown, |(os, _)| os)
+    }
+
+    /// Gets the version suffixed to the operating system, such as "10.15" for "macosx10.15", or an
+    /// empty string if there is none.
+    pub fn get_os_version(&self) -> &str {
+        let os = self.get_components()[2];
+
+        parse_prefix(OperatingSystem::SPELLINGS, os).map_or("", |(_, len)| &os[len..])
+    }
+
+    /// Gets the environment of this triple, ignoring any version suffix. MinGW triples have the
+    /// `Environment::Gnu` environment, and Cygwin triples `Environment::Cygnus`.
+    pub fn get_environment(&self) -> Environment {
+        parse_prefix(Environment::SPELLINGS, self.get_components()[3]).map_or(Environment::Unknown, |(environment, _)| environment)
+    }
+
+    /// Gets the object format of this triple, which is given by a suffix such as "-elf" or else
+    /// implied by its architecture and operating system.
+    pub fn get_object_format(&self) -> ObjectFormat {
+        let components = self.get_components();
+
+        if let Some(object_format) = parse_object_format(components[components.len() - 1]) {
+            return object_format;
+        }
+
+        match (self.get_arch(), self.get_os()) {
+            (Arch::Wasm32, _) | (Arch::Wasm64, _) => ObjectFormat::Wasm,
+            _ if self.is_darwin() => ObjectFormat::MachO,
+            (_, OperatingSystem::Windows) => ObjectFormat::Coff,
+            (_, OperatingSystem::Aix) => ObjectFormat::Xcoff,
+            (_, OperatingSystem::ZOS) => ObjectFormat::Goff,
+            _ => ObjectFormat::Elf,
+        }
+    }
+
+    /// Determines whether this triple targets Windows, including through MinGW or Cygwin.
+    pub fn is_windows(&self) -> bool {
+        self.get_os() == OperatingSystem::Windows
+    }
+
+    /// Determines whether this triple targets macOS, iOS, tvOS, watchOS or bare Darwin.
+    pub fn is_darwin(&self) -> bool {
+        matches!(
+            self.get_os(),
+            OperatingSystem::Darwin | OperatingSystem::MacOSX | OperatingSystem::IOS | OperatingSystem::TvOS | OperatingSystem::WatchOS
+        )
+    }
+
+    /// Determines whether this triple targets Linux, including Android.
+    pub fn is_linux(&self) -> bool {
+        self.get_os() == OperatingSystem::Linux
+    }
+
+    /// Determines whether this triple targets 32 or 64 bit WebAssembly.
+    pub fn is_wasm(&self) -> bool {
+        matches!(self.get_arch(), Arch::Wasm32 | Arch::Wasm64)
+    }
+
+    /// Gets the size of a pointer in bits, or `None` if the architecture is unknown.
+    pub fn pointer_width(&self) -> Option<u32> {
+        let width = match self.get_arch() {
+            Arch::Unknown => return None,
+            Arch::Avr | Arch::Msp430 => 16,
+            Arch::X86_64 if self.get_environment() == Environment::GnuX32 => 32,
+            Arch::Arm
+            | Arch::ArmEb
+            | Arch::Hexagon
+            | Arch::Lanai
+            | Arch::Mips
+            | Arch::Mipsel
+            | Arch::Nvptx
+            | Arch::PowerPC
+            | Arch::R600
+            | Arch::RiscV32
+            | Arch::Sparc
+            | Arch::Sparcel
+            | Arch::Thumb
+            | Arch::ThumbEb
+            | Arch::Wasm32
+            | Arch::X86 => 32,
+            _ => 64,
+        };
+
+        Some(width)
+    }
+
+    /// Determines whether two triples describe the same target once normalized, even if they are
+    /// spelled differently, as with "amd64-linux-gnu" and "x86_64-unknown-linux-gnu".
+    #[llvm_versions(7.0..=latest)]
+    pub fn is_equivalent_to(&self, other: &TargetTriple) -> bool {
+        let describe = |triple: &TargetTriple| {
+            let triple = TargetMachine::normalize_triple(triple);
+
+            (
+                triple.get_arch(),
+                triple.get_sub_arch().to_owned(),
+                triple.get_vendor(),
+                triple.get_os(),
+                triple.get_os_version().to_owned(),
+                triple.get_environment(),
+                triple.get_object_format(),
+            )
+        };
+
+        describe(self) == describe(other)
+    }
+}
+
+// Splits triple into its components, moved to their canonical positions as by LLVM's Triple::normalize, so
+// that "x86_64-linux-gnu" has an empty vendor and MinGW and Cygwin triples name Windows as their OS. Empty
+// components stand for unknown ones.
+fn normalize_components(triple: &str) -> Vec<&str> {
+    let parse_os = |component: &str| parse_prefix(OperatingSystem::SPELLINGS, component).map(|(os, _)| os);
+    let parse_environment = |component: &str| parse_prefix(Environment::SPELLINGS, component).map(|(environment, _)| environment);
+    let mut components: Vec<&str> = triple.split('-').collect();
+    let initial = |index: usize| components.get(index).copied().unwrap_or("");
+    // Like LLVM, these hold whatever was parsed last, whether or not it was valid
+    let mut os = parse_os(initial(2));
+    let mut is_cygwin = initial(2).starts_with("cygwin");
+    let mut is_mingw = initial(2).starts_with("mingw");
+    let mut environment = parse_environment(initial(3));
+    let mut object_format = parse_object_format(initial(4));
+    // Which components are already in their canonical positions
+    let mut found = [
+        Arch::parse(initial(0)).0 != Arch::Unknown,
+        Vendor::SPELLINGS.iter().any(|&(spelling, _)| spelling == initial(1)),
+        os.is_some(),
+        environment.is_some(),
+    ];
+    let is_found = |found: &[bool; 4], index: usize| found.get(index).copied().unwrap_or(false);
+
+    for position in 0..found.len() {
+        if found[position] {
+            continue;
+        }
+
+        for index in 0..components.len() {
+            if is_found(&found, index) {
+                continue;
+            }
+
+            let candidate = components[index];
+            let is_valid = match position {
+                0 => Arch::parse(candidate).0 != Arch::Unknown,
+                1 => Vendor::SPELLINGS.iter().any(|&(spelling, _)| spelling == candidate),
+                2 => {
+                    os = parse_os(candidate);
+                    is_cygwin = candidate.starts_with("cygwin");
+                    is_mingw = candidate.starts_with("mingw");
+
+                    os.is_some() || is_cygwin || is_mingw
+                },
+                _ => {
+                    environment = parse_environment(candidate);
+
+                    if environment.is_none() {
+                        object_format = parse_object_format(candidate);
+                    }
+
+                    environment.is_some() || object_format.is_some()
+                },
+            };
+
+            if !is_valid {
+                continue;
+            }
+
+            if position < index {
+                // Moves the component left, pushing the components which aren't in position to the right
+                let mut current = std::mem::replace(&mut components[index], "");
+                let mut i = position;
+
+                while !current.is_empty() {
+                    while is_found(&found, i) {
+                        i += 1;
+                    }
+
+                    std::mem::swap(&mut current, &mut components[i]);
+                    i += 1;
+                }
+            } else if position > index {
+                // Inserts empty components before the component until it reaches its position
+                let mut index = index;
+
+                while index < position {
+                    let mut current = "";
+                    let mut i = index;
+
+                    while i < components.len() {
+                        std::mem::swap(&mut current, &mut components[i]);
+
+                        if current.is_empty() {
+                            break;
+                        }
+
+                        i += 1;
+
+                        while is_found(&found, i) {
+                            i += 1;
+                        }
+                    }
+
+                    if !current.is_empty() {
+                        components.push(current);
+                    }
+
+                    index += 1;
+
+                    while is_found(&found, index) {
+                        index += 1;
+                    }
+                }
+            }
+
+            found[position] = true;
+
+            break;
+        }
+    }
+
+    let object_format = object_format.filter(|&object_format| object_format != ObjectFormat::Coff);
+
+    if os == Some(OperatingSystem::Windows) || is_mingw || is_cygwin {
+        components.resize(4, "");
+        components[2] = "windows";
+
+        if os == Some(OperatingSystem::Windows) {
+            if environment.is_none() {
+                components[3] = object_format.map_or("msvc", ObjectFormat::get_name);
+            }
+        } else if is_mingw {
+            components[3] = "gnu";
+        } else {
+            components[3] = "cygnus";
+        }
+    }
+
+    if is_mingw || is_cygwin || (os == Some(OperatingSystem::Windows) && environment.is_some()) {
+        if let Some(object_format) = object_format {
+            components.resize(4, "");
+            components.push(object_format.get_name());
+        }
+    }
+
+    components
+}
+
+// Parses an object format suffixed to the environment, as with "gnu-elf" or "elf"
+fn parse_object_format(environment: &str) -> Option<ObjectFormat> {
+    ObjectFormat::SPELLINGS.iter()
+        .filter(|&&(spelling, _)| environment.ends_with(spelling))
+        .max_by_key(|&&(spelling, _)| spelling.len())
+        .map(|&(_, object_format)| object_format)
+}
+
+// Finds the longest spelling name starts with, returning its component and length
+fn parse_prefix<T: Copy>(spellings: &[(&'static str, T)], name: &str) -> Option<(T, usize)> {
+    spellings.iter()
+        .filter(|&&(spelling, _)| name.starts_with(spelling))
+        .max_by_key(|&&(spelling, _)| spelling.len())
+        .map(|&(spelling, component)| (component, spelling.len()))
+}
+
+macro_rules! triple_component {
+    ($(#[$meta:meta])* $name:ident { $($(#[$variant_meta:meta])* $variant:ident => [$($spelling:literal),+],)+ }) => {
+        $(#[$meta])*
+        #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+        pub enum $name {
+            /// A missing or unrecognized component.
+            Unknown,
+            $($(#[$variant_meta])* $variant,)+
+        }
+
+        impl $name {
+            // The canonical spelling of each component comes first
+            const SPELLINGS: &'static [(&'static str, $name)] = &[$($(($spelling, $name::$variant),)+)+];
+
+            /// Gets the canonical spelling of this component, as used by `TargetTripleBuilder`.
+            pub fn get_name(self) -> &'static str {
+                $name::SPELLINGS.iter()
+                    .find(|&&(_, component)| component == self)
+                    .map_or("unknown", |&(spelling, _)| spelling)
+            }
+        }
+    };
+}
+
+triple_component! {
+    /// The architecture component of a `TargetTriple`.
+    Arch {
+        AArch64 => ["aarch64", "arm64"],
+        AArch64Be => ["aarch64_be"],
+        AmdGcn => ["amdgcn"],
+        Arm => ["arm"],
+        ArmEb => ["armeb"],
+        Avr => ["avr"],
+        /// Little endian eBPF, which "bpf" is taken to mean.
+        BpfEl => ["bpfel", "bpf"],
+        BpfEb => ["bpfeb"],
+        Hexagon => ["hexagon"],
+        Lanai => ["lanai"],
+        Mips => ["mips", "mipseb", "mipsallegrex"],
+        Mipsel => ["mipsel", "mipsallegrexel"],
+        Mips64 => ["mips64", "mips64eb"],
+        Mips64el => ["mips64el"],
+        Msp430 => ["msp430"],
+        Nvptx => ["nvptx"],
+        Nvptx64 => ["nvptx64"],
+        PowerPC => ["powerpc", "ppc", "ppc32"],
+        PowerPC64 => ["powerpc64", "ppu", "ppc64"],
+        PowerPC64le => ["powerpc64le", "ppc64le"],
+        R600 => ["r600"],
+        RiscV32 => ["riscv32"],
+        RiscV64 => ["riscv64"],
+        Sparc => ["sparc"],
+        Sparcel => ["sparcel"],
+        Sparcv9 => ["sparcv9", "sparc64"],
+        SystemZ => ["s390x", "systemz"],
+        Thumb => ["thumb"],
+        ThumbEb => ["thumbeb"],
+        Wasm32 => ["wasm32"],
+        Wasm64 => ["wasm64"],
+        X86 => ["i686", "i386", "i486", "i586", "i786", "i886", "i986"],
+        X86_64 => ["x86_64", "amd64"],
+    }
+}
+
+impl Arch {
+    // Parses an architecture name into its architecture and sub-architecture
+    fn parse(name: &str) -> (Arch, &str) {
+        if let Some(&(_, arch)) = Arch::SPELLINGS.iter().find(|&&(spelling, _)| spelling == name) {
+            return (arch, "");
+        }
+
+        // ARM sub-architectures are versions such as "v7a" or "v8m.main"
+        for &(prefix, arch) in &[("armeb", Arch::ArmEb), ("arm", Arch::Arm), ("thumbeb", Arch::ThumbEb), ("thumb", Arch::Thumb)] {
+            if name.starts_with(prefix) && name[prefix.len()..].starts_with('v') {
+                return (arch, &name[prefix.len()..]);
+            }
+        }
+
+        if name == "x86_64h" {
+            return (Arch::X86_64, "h");
+        }
+
+        (Arch::Unknown, "")
+    }
+}
+
+triple_component! {
+    /// The vendor component of a `TargetTriple`.
+    Vendor {
+        Amd => ["amd"],
+        Apple => ["apple"],
+        Csr => ["csr"],
+        Fsl => ["fsl"],
+        Ibm => ["ibm"],
+        ImaginationTechnologies => ["img"],
+        Mesa => ["mesa"],
+        MipsTechnologies => ["mti"],
+        Nvidia => ["nvidia"],
+        OpenEmbedded => ["oe"],
+        Pc => ["pc"],
+        Scei => ["scei"],
+        Suse => ["suse"],
+    }
+}
+
+triple_component! {
+    /// The operating system component of a `TargetTriple`.
+    OperatingSystem {
+        Aix => ["aix"],
+        AmdHsa => ["amdhsa"],
+        AmdPal => ["amdpal"],
+        CloudABI => ["cloudabi"],
+        Contiki => ["contiki"],
+        Cuda => ["cuda"],
+        Darwin => ["darwin"],
+        DragonFly => ["dragonfly"],
+        ElfIamcu => ["elfiamcu"],
+        Emscripten => ["emscripten"],
+        FreeBSD => ["freebsd"],
+        Fuchsia => ["fuchsia"],
+        Haiku => ["haiku"],
+        HermitCore => ["hermit"],
+        Hurd => ["hurd"],
+        IOS => ["ios"],
+        KFreeBSD => ["kfreebsd"],
+        Linux => ["linux"],
+        Lv2 => ["lv2"],
+        MacOSX => ["macosx", "macos"],
+        Mesa3D => ["mesa3d"],
+        Minix => ["minix"],
+        NaCl => ["nacl"],
+        NetBSD => ["netbsd"],
+        NvCl => ["nvcl"],
+        OpenBSD => ["openbsd"],
+        Ps4 => ["ps4"],
+        Rtems => ["rtems"],
+        Solaris => ["solaris"],
+        TvOS => ["tvos"],
+        Wasi => ["wasi"],
+        WatchOS => ["watchos"],
+        Windows => ["windows", "win32"],
+        ZOS => ["zos"],
+    }
+}
+
+triple_component! {
+    /// The environment, or ABI, component of a `TargetTriple`.
+    Environment {
+        Android => ["android"],
+        Code16 => ["code16"],
+        CoreClr => ["coreclr"],
+        Cygnus => ["cygnus"],
+        Eabi => ["eabi"],
+        EabiHf => ["eabihf"],
+        Gnu => ["gnu"],
+        GnuAbi64 => ["gnuabi64"],
+        GnuAbiN32 => ["gnuabin32"],
+        GnuEabi => ["gnueabi"],
+        GnuEabiHf => ["gnueabihf"],
+        GnuX32 => ["gnux32"],
+        Itanium => ["itanium"],
+        MacAbi => ["macabi"],
+        Msvc => ["msvc"],
+        Musl => ["musl"],
+        MuslEabi => ["musleabi"],
+        MuslEabiHf => ["musleabihf"],
+        Simulator => ["simulator"],
+    }
+}
+
+triple_component! {
+    /// The object file format a `TargetTriple` produces.
+    ObjectFormat {
+        Coff => ["coff"],
+        Elf => ["elf"],
+        Goff => ["goff"],
+        MachO => ["macho"],
+        Wasm => ["wasm"],
+        Xcoff => ["xcoff"],
+    }
+}
+
+/// Builds a `TargetTriple` from its components.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::targets::{Arch, Environment, ObjectFormat, OperatingSystem, TargetTripleBuilder, Vendor};
+///
+/// let triple = TargetTripleBuilder::new(Arch::X86_64)
+///     .vendor(Vendor::Pc)
+///     .os(OperatingSystem::Windows)
+///     .environment(Environment::Msvc)
+///     .build();
+///
+/// assert_eq!(triple.as_str().to_str(), Ok("x86_64-pc-windows-msvc"));
+/// assert_eq!(triple.get_object_format(), ObjectFormat::Coff);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetTripleBuilder {
+    arch: Arch,
+    sub_arch: String,
+    vendor: Vendor,
+    os: OperatingSystem,
+    os_version: String,
+    environment: Environment,
+    object_format: Option<ObjectFormat>,
+}
+
+impl TargetTripleBuilder {
+    /// Creates a builder for a triple with the architecture `arch` and all other components unknown.
+    pub fn new(arch: Arch) -> Self {
+        TargetTripleBuilder {
+            arch,
+            sub_arch: String::new(),
+            vendor: Vendor::Unknown,
+            os: OperatingSystem::Unknown,
+            os_version: String::new(),
+            environment: Environment::Unknown,
+            object_format: None,
+        }
+    }
+
+    /// Sets the sub-architecture appended to the architecture, such as "v7a" for `Arch::Arm`.
+    pub fn sub_arch(mut self, sub_arch: &str) -> Self {
+        self.sub_arch = sub_arch.to_owned();
+        self
+    }
+
+    /// Sets the vendor, which is `Vendor::Unknown` by default.
+    pub fn vendor(mut self, vendor: Vendor) -> Self {
+        self.vendor = vendor;
+        self
+    }
+
+    /// Sets the operating system, which is `OperatingSystem::Unknown` by default.
+    pub fn os(mut self, os: OperatingSystem) -> Self {
+        self.os = os;
+        self
+    }
+
+    /// Sets the version appended to the operating system, such as "10.15" for `OperatingSystem::MacOSX`.
+    pub fn os_version(mut self, os_version: &str) -> Self {
+        self.os_version = os_version.to_owned();
+        self
+    }
+
+    /// Sets the environment. When it is `Environment::Unknown`, the default, the triple has no
+    /// environment component.
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Sets an object format to suffix the triple with, overriding the one its architecture and
+    /// operating system imply.
+    pub fn object_format(mut self, object_format: ObjectFormat) -> Self {
+        self.object_format = Some(object_format);
+        self
+    }
+
+    /// Builds the triple, spelling each component canonically.
+    pub fn build(&self) -> TargetTriple {
+        let mut triple = format!(
+            "{}{}-{}-{}{}",
+            self.arch.get_name(),
+            self.sub_arch,
+            self.vendor.get_name(),
+            self.os.get_name(),
+            self.os_version,
+        );
+
+        if self.environment != Environment::Unknown {
+            triple.push('-');
+            triple.push_str(self.environment.get_name());
+        }
+
+        if let Some(object_format) = self.object_format {
+            triple.push('-');
+            triple.push_str(object_format.get_name());
+        }
+
+        TargetTriple::create(&triple)
+    }
+}
+
 static TARGET_LOCK: Lazy<RwLock<()>> = Lazy::new(|| RwLock::new(()));
 
 // NOTE: Versions verified as target-complete: 3.6, 3.7, 3.8, 3.9, 4.0
@@ -901,6 +1600,12 @@ impl Target {
         }
     }
 
+    /// Creates a `TargetMachine` for `triple` from `options`. Options which can only be applied to the
+    /// modules it compiles are set with `ModuleCodeGenOptions`.
+    pub fn create_target_machine_from_options(&self, triple: &TargetTriple, options: &TargetMachineOptions) -> Option<TargetMachine> {
+        self.create_target_machine(triple, &options.cpu, &options.features, options.opt_level, options.reloc_mode, options.code_model)
+    }
+
     pub fn create_target_machine(
         &self,
         triple: &TargetTriple,
@@ -912,21 +1617,14 @@ impl Target {
     ) -> Option<TargetMachine> {
         let cpu = to_c_str(cpu);
         let features = to_c_str(features);
-        let level = match level {
+        let llvm_level = match level {
             OptimizationLevel::None => LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
             OptimizationLevel::Less => LLVMCodeGenOptLevel::LLVMCodeGenLevelLess,
             OptimizationLevel::Default => LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
             OptimizationLevel::Aggressive => LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive,
         };
-        let code_model = match code_model {
-            CodeModel::Default => LLVMCodeModel::LLVMCodeModelDefault,
-            CodeModel::JITDefault => LLVMCodeModel::LLVMCodeModelJITDefault,
-            CodeModel::Small => LLVMCodeModel::LLVMCodeModelSmall,
-            CodeModel::Kernel => LLVMCodeModel::LLVMCodeModelKernel,
-            CodeModel::Medium => LLVMCodeModel::LLVMCodeModelMedium,
-            CodeModel::Large => LLVMCodeModel::LLVMCodeModelLarge,
-        };
-        let reloc_mode = match reloc_mode {
+        let llvm_code_model = code_model.as_llvm_code_model();
+        let llvm_reloc_mode = match reloc_mode {
             RelocMode::Default => LLVMRelocMode::LLVMRelocDefault,
             RelocMode::Static => LLVMRelocMode::LLVMRelocStatic,
             RelocMode::PIC => LLVMRelocMode::LLVMRelocPIC,
@@ -938,9 +1636,9 @@ impl Target {
                 triple.as_ptr(),
                 cpu.as_ptr(),
                 features.as_ptr(),
-                level,
-                reloc_mode,
-                code_model,
+                llvm_level,
+                llvm_reloc_mode,
+                llvm_code_model,
             )
         };
 
@@ -949,7 +1647,7 @@ impl Target {
         }
 
         unsafe {
-            Some(TargetMachine::new(target_machine))
+            Some(TargetMachine::new(target_machine, level, reloc_mode, code_model))
         }
     }
 
@@ -1045,13 +1743,17 @@ impl Target {
 #[derive(Debug)]
 pub struct TargetMachine {
     pub(crate) target_machine: LLVMTargetMachineRef,
+    // LLVM's C API can't read these back, so they are kept from creation
+    pub(crate) opt_level: OptimizationLevel,
+    pub(crate) reloc_mode: RelocMode,
+    pub(crate) code_model: CodeModel,
 }
 
 impl TargetMachine {
-    unsafe fn new(target_machine: LLVMTargetMachineRef) -> Self {
+    unsafe fn new(target_machine: LLVMTargetMachineRef, opt_level: OptimizationLevel, reloc_mode: RelocMode, code_model: CodeModel) -> Self {
         assert!(!target_machine.is_null());
 
-        TargetMachine { target_machine }
+        TargetMachine { target_machine, opt_level, reloc_mode, code_model }
     }
 
     pub fn get_target(&self) -> Target {
@@ -1283,6 +1985,180 @@ impl TargetMachine {
     }
 }
 
+/// Options to create a `TargetMachine` with, using `Target::create_target_machine_from_options`.
+///
+/// These are all the options LLVM's C API can set on a `TargetMachine`. Code generation options which
+/// it can only express in the IR of each module compiled, such as the frame pointer policy, are set
+/// with `ModuleCodeGenOptions` instead. Function and data sections, emulated TLS, unique section names
+/// and GlobalISel can be set neither way, so the target's defaults always apply to them.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::OptimizationLevel;
+/// use inkwell::targets::{InitializationConfig, RelocMode, Target, TargetMachineOptions, TargetTriple};
+///
+/// Target::initialize_x86(&InitializationConfig::default());
+///
+/// let triple = TargetTriple::create("x86_64-pc-linux-gnu");
+/// let target = Target::from_triple(&triple).unwrap();
+/// let options = TargetMachineOptions::new()
+///     .cpu("x86-64")
+///     .features("+avx2")
+///     .opt_level(OptimizationLevel::Aggressive)
+///     .reloc_mode(RelocMode::PIC);
+/// let target_machine = target.create_target_machine_from_options(&triple, &options).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetMachineOptions {
+    cpu: String,
+    features: String,
+    opt_level: OptimizationLevel,
+    reloc_mode: RelocMode,
+    code_model: CodeModel,
+}
+
+impl TargetMachineOptions {
+    /// Creates `TargetMachineOptions` with LLVM's defaults: the target's generic CPU with no extra
+    /// features, `OptimizationLevel::Default`, `RelocMode::Default` and `CodeModel::Default`.
+    pub fn new() -> Self {
+        TargetMachineOptions {
+            cpu: String::new(),
+            features: String::new(),
+            opt_level: OptimizationLevel::Default,
+            reloc_mode: RelocMode::Default,
+            code_model: CodeModel::Default,
+        }
+    }
+
+    /// Sets the CPU to generate code for, such as "x86-64" or `TargetMachine::get_host_cpu_name()`.
+    pub fn cpu(mut self, cpu: &str) -> Self {
+        self.cpu = cpu.to_string();
+        self
+    }
+
+    /// Sets the target features to enable or disable, such as "+avx2,-sse4a".
+    pub fn features(mut self, features: &str) -> Self {
+        self.features = features.to_string();
+        self
+    }
+
+    /// Sets the code generation `OptimizationLevel`.
+    pub fn opt_level(mut self, opt_level: OptimizationLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    /// Sets the `RelocMode`.
+    pub fn reloc_mode(mut self, reloc_mode: RelocMode) -> Self {
+        self.reloc_mode = reloc_mode;
+        self
+    }
+
+    /// Sets the `CodeModel`.
+    pub fn code_model(mut self, code_model: CodeModel) -> Self {
+        self.code_model = code_model;
+        self
+    }
+}
+
+impl Default for TargetMachineOptions {
+    fn default() -> Self {
+        TargetMachineOptions::new()
+    }
+}
+
+/// Code generation options which LLVM's C API cannot set on a `TargetMachine`, but which it can
+/// express as attributes of each function compiled.
+///
+/// These have no effect until `ModuleCodeGenOptions::apply_to_module` applies them to a module, so they
+/// must be applied to each module before it is compiled. Function and data sections and trapping
+/// `unreachable` instructions can be set neither way on LLVM 13 and earlier, so the target's defaults
+/// always apply to them.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::context::Context;
+/// use inkwell::targets::{FileType, FramePointer, InitializationConfig, ModuleCodeGenOptions, Target, TargetMachineOptions, TargetTriple};
+///
+/// Target::initialize_x86(&InitializationConfig::default());
+///
+/// let triple = TargetTriple::create("x86_64-pc-linux-gnu");
+/// let target = Target::from_triple(&triple).unwrap();
+/// let target_machine = target.create_target_machine_from_options(&triple, &TargetMachineOptions::new()).unwrap();
+/// let options = ModuleCodeGenOptions::new()
+///     .frame_pointer(FramePointer::All);
+///
+/// let context = Context::create();
+/// let module = context.create_module("my_module");
+///
+/// options.apply_to_module(&module);
+///
+/// let buffer = target_machine.write_to_memory_buffer(&module, FileType::Object).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ModuleCodeGenOptions {
+    soft_float: bool,
+    frame_pointer: Option<FramePointer>,
+}
+
+impl ModuleCodeGenOptions {
+    /// Creates `ModuleCodeGenOptions` with LLVM's defaults: hardware floating point operations and the
+    /// target's frame pointer policy.
+    pub fn new() -> Self {
+        ModuleCodeGenOptions::default()
+    }
+
+    /// Sets whether floating point operations are lowered to library calls rather than floating point
+    /// instructions. This does not change the float ABI, which decides how floating point values are
+    /// passed between functions, and is chosen by the triple's environment, as with
+    /// "arm-unknown-linux-gnueabihf".
+    pub fn soft_float(mut self, soft_float: bool) -> Self {
+        self.soft_float = soft_float;
+        self
+    }
+
+    /// Sets which functions keep a frame pointer, overriding the target's default.
+    pub fn frame_pointer(mut self, frame_pointer: FramePointer) -> Self {
+        self.frame_pointer = Some(frame_pointer);
+        self
+    }
+
+    /// Applies these options to the functions defined in `module`, as function attributes. Applying
+    /// the options more than once has no further effect.
+    pub fn apply_to_module(&self, module: &Module) {
+        let mut function = module.get_first_function();
+
+        while let Some(current) = function {
+            function = current.get_next_function();
+
+            if current.count_basic_blocks() == 0 {
+                continue;
+            }
+
+            if let Some(frame_pointer) = self.frame_pointer {
+                for (key, value) in frame_pointer.as_fn_attributes() {
+                    add_fn_attribute(current, key, value);
+                }
+            }
+
+            if self.soft_float {
+                add_fn_attribute(current, "use-soft-float", "true");
+            }
+        }
+    }
+}
+
+fn add_fn_attribute(function: FunctionValue, key: &str, value: &str) {
+    let key = to_c_str(key);
+    let value = to_c_str(value);
+
+    unsafe {
+        LLVMAddTargetDependentFunctionAttr(function.as_value_ref(), key.as_ptr(), value.as_ptr())
+    }
+}
+
 impl Drop for TargetMachine {
     fn drop(&mut self) {
         unsafe { LLVMDisposeTargetMachine(self.target_machine) }
@@ -1427,6 +2303,100 @@ impl TargetData {
             ))
         }
     }
+
+    /// Takes a snapshot of the layout of `struct_type`, or returns `None` if it is opaque.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::targets::TargetData;
+    ///
+    /// let context = Context::create();
+    /// let target_data = TargetData::create("e-i64:64");
+    /// let struct_type = context.struct_type(&[context.i8_type().into(), context.i64_type().into(), context.i16_type().into()], false);
+    /// let layout = target_data.get_struct_layout(&struct_type).unwrap();
+    ///
+    /// assert_eq!(layout.get_size(), 24);
+    /// assert_eq!(layout.get_alignment(), 8);
+    /// assert_eq!(layout.get_field_offsets(), &[0, 8, 16]);
+    /// assert_eq!(layout.get_padding_holes()[0].get_size(), 7);
+    /// assert_eq!(layout.get_trailing_padding(), 6);
+    /// ```
+    pub fn get_struct_layout(&self, struct_type: &StructType) -> Option<StructLayout> {
+        if struct_type.is_opaque() {
+            return None;
+        }
+
+        let field_types = struct_type.get_field_types();
+        let mut field_offsets = Vec::with_capacity(field_types.len());
+        let mut field_sizes = Vec::with_capacity(field_types.len());
+        let mut padding_holes = Vec::new();
+        let mut end = 0;
+
+        for (index, field_type) in field_types.iter().enumerate() {
+            let offset = unsafe { LLVMOffsetOfElement(self.target_data, struct_type.as_type_ref(), index as u32) };
+            let size = self.get_abi_size(field_type);
+
+            if offset > end {
+                padding_holes.push(PaddingHole {
+                    after_field: index.checked_sub(1).map(|after_field| after_field as u32),
+                    offset: end,
+                    size: offset - end,
+                });
+            }
+
+            field_offsets.push(offset);
+            field_sizes.push(size);
+            end = offset + size;
+        }
+
+        let size = self.get_abi_size(struct_type);
+
+        Some(StructLayout {
+            size,
+            alignment: self.get_abi_alignment(struct_type),
+            preferred_alignment: self.get_preferred_alignment(struct_type),
+            is_packed: struct_type.is_packed(),
+            field_offsets,
+            field_sizes,
+            padding_holes,
+            trailing_padding: size.saturating_sub(end),
+        })
+    }
+
+    /// Takes a snapshot of the layout of `array_type`, whose elements are each aligned.
+    pub fn get_array_layout(&self, array_type: &ArrayType) -> SequenceLayout {
+        let element_type = array_type.get_element_type();
+
+        SequenceLayout {
+            len: array_type.len(),
+            element_stride: Some(self.get_abi_size(&element_type)),
+            layout: self.get_type_layout(array_type),
+        }
+    }
+
+    /// Takes a snapshot of the layout of `vector_type`, whose elements are packed together, so may
+    /// not each start on a byte boundary.
+    pub fn get_vector_layout(&self, vector_type: &VectorType) -> SequenceLayout {
+        let element_bit_size = self.get_bit_size(&vector_type.get_element_type());
+
+        SequenceLayout {
+            len: vector_type.get_size(),
+            element_stride: Some(element_bit_size / 8).filter(|_| element_bit_size % 8 == 0),
+            layout: self.get_type_layout(vector_type),
+        }
+    }
+
+    fn get_type_layout(&self, type_: &dyn AnyType) -> TypeLayout {
+        TypeLayout {
+            size: self.get_abi_size(type_),
+            store_size: self.get_store_size(type_),
+            bit_size: self.get_bit_size(type_),
+            alignment: self.get_abi_alignment(type_),
+            preferred_alignment: self.get_preferred_alignment(type_),
+        }
+    }
 }
 
 impl Drop for TargetData {
@@ -1434,3 +2404,148 @@ impl Drop for TargetData {
         unsafe { LLVMDisposeTargetData(self.target_data) }
     }
 }
+
+/// Unused bytes between two fields of a struct, or before its first field.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct PaddingHole {
+    after_field: Option<u32>,
+    offset: u64,
+    size: u64,
+}
+
+impl PaddingHole {
+    /// Gets the index of the field the padding follows, or `None` if it precedes the first field.
+    pub fn get_after_field(&self) -> Option<u32> {
+        self.after_field
+    }
+
+    /// Gets the offset of the padding in bytes.
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Gets the size of the padding in bytes.
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// A snapshot of a struct's layout under a `TargetData`. Sizes and offsets are in bytes.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StructLayout {
+    size: u64,
+    alignment: u32,
+    preferred_alignment: u32,
+    is_packed: bool,
+    field_offsets: Vec<u64>,
+    field_sizes: Vec<u64>,
+    padding_holes: Vec<PaddingHole>,
+    trailing_padding: u64,
+}
+
+impl StructLayout {
+    /// Gets the size of the struct, including trailing padding, as allocated in memory.
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn get_alignment(&self) -> u32 {
+        self.alignment
+    }
+
+    pub fn get_preferred_alignment(&self) -> u32 {
+        self.preferred_alignment
+    }
+
+    pub fn is_packed(&self) -> bool {
+        self.is_packed
+    }
+
+    pub fn count_fields(&self) -> u32 {
+        self.field_offsets.len() as u32
+    }
+
+    pub fn get_field_offset(&self, index: u32) -> Option<u64> {
+        self.field_offsets.get(index as usize).copied()
+    }
+
+    pub fn get_field_offsets(&self) -> &[u64] {
+        &self.field_offsets
+    }
+
+    /// Gets the size a field is allocated, which includes any padding of its own type, such as an
+    /// `x86_fp80` being allocated 16 bytes.
+    pub fn get_field_size(&self, index: u32) -> Option<u64> {
+        self.field_sizes.get(index as usize).copied()
+    }
+
+    /// Gets the padding before or between fields, in order.
+    pub fn get_padding_holes(&self) -> &[PaddingHole] {
+        &self.padding_holes
+    }
+
+    /// Gets the padding after the last field which rounds the size up to the alignment.
+    pub fn get_trailing_padding(&self) -> u64 {
+        self.trailing_padding
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+struct TypeLayout {
+    size: u64,
+    store_size: u64,
+    bit_size: u64,
+    alignment: u32,
+    preferred_alignment: u32,
+}
+
+/// A snapshot of an array or vector type's layout under a `TargetData`. Sizes and offsets are in bytes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SequenceLayout {
+    len: u32,
+    // None for vectors whose elements aren't byte sized
+    element_stride: Option<u64>,
+    layout: TypeLayout,
+}
+
+impl SequenceLayout {
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the size of the type, including trailing padding, as allocated in memory.
+    pub fn get_size(&self) -> u64 {
+        self.layout.size
+    }
+
+    /// Gets the number of bytes a store of the type may overwrite, which excludes trailing padding.
+    pub fn get_store_size(&self) -> u64 {
+        self.layout.store_size
+    }
+
+    pub fn get_bit_size(&self) -> u64 {
+        self.layout.bit_size
+    }
+
+    pub fn get_alignment(&self) -> u32 {
+        self.layout.alignment
+    }
+
+    pub fn get_preferred_alignment(&self) -> u32 {
+        self.layout.preferred_alignment
+    }
+
+    /// Gets the offset of the element at `index`, or `None` if it is out of bounds or, for vectors
+    /// of elements smaller than a byte or not a whole number of bytes, doesn't start on a byte.
+    pub fn get_element_offset(&self, index: u32) -> Option<u64> {
+        if index >= self.len {
+            return None;
+        }
+
+        self.element_stride.map(|stride| stride * index as u64)
+    }
+}
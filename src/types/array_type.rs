@@ -5,7 +5,7 @@ use crate::AddressSpace;
 use crate::context::ContextRef;
 use crate::types::traits::AsTypeRef;
 use crate::types::{Type, BasicTypeEnum, PointerType, FunctionType};
-use crate::values::{AsValueRef, ArrayValue, IntValue};
+use crate::values::{AsValueRef, ArrayValue, GenericValue, IntValue};
 use crate::types::enums::BasicMetadataTypeEnum;
 
 /// An `ArrayType` is the type of contiguous constants or variables.
@@ -213,6 +213,29 @@ impl<'ctx> ArrayType<'ctx> {
         }
     }
 
+    /// Creates a array `GenericValue` from its elements, for use with interpreter `ExecutionEngine`s.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let i64_type = context.i64_type();
+    /// let array_type = i64_type.array_type(2);
+    /// let value = array_type.create_generic_value(vec![
+    ///     i64_type.create_generic_value(1, false),
+    ///     i64_type.create_generic_value(2, false),
+    /// ]);
+    ///
+    /// assert_eq!(value.get_elements().len(), 2);
+    /// ```
+    pub fn create_generic_value(self, values: Vec<GenericValue<'ctx>>) -> GenericValue<'ctx> {
+        assert_eq!(values.len(), self.len() as usize, "Incorrect number of elements for ArrayType");
+
+        GenericValue::new_aggregate(values)
+    }
+
     // SubType: ArrayType<BT> -> BT?
     /// Gets the element type of this `ArrayType`.
     ///
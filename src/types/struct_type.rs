@@ -10,7 +10,7 @@ use crate::AddressSpace;
 use crate::context::ContextRef;
 use crate::types::traits::AsTypeRef;
 use crate::types::{ArrayType, BasicTypeEnum, PointerType, FunctionType, Type};
-use crate::values::{ArrayValue, BasicValueEnum, StructValue, IntValue, AsValueRef};
+use crate::values::{ArrayValue, BasicValueEnum, GenericValue, StructValue, IntValue, AsValueRef};
 use crate::types::enums::BasicMetadataTypeEnum;
 
 /// A `StructType` is the type of a heterogeneous container of types.
@@ -350,6 +350,30 @@ impl<'ctx> StructType<'ctx> {
         }
     }
 
+    /// Creates a struct `GenericValue` from its field values, for use with interpreter `ExecutionEngine`s.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let f32_type = context.f32_type();
+    /// let i8_type = context.i8_type();
+    /// let struct_type = context.struct_type(&[f32_type.into(), i8_type.into()], false);
+    /// let value = struct_type.create_generic_value(vec![
+    ///     f32_type.create_generic_value(1.5),
+    ///     i8_type.create_generic_value(7, false),
+    /// ]);
+    ///
+    /// assert_eq!(value.get_elements().len(), 2);
+    /// ```
+    pub fn create_generic_value(self, values: Vec<GenericValue<'ctx>>) -> GenericValue<'ctx> {
+        assert_eq!(values.len(), self.count_fields() as usize, "Incorrect number of field values for StructType");
+
+        GenericValue::new_aggregate(values)
+    }
+
     // REVIEW: SubTypes should allow this to only be implemented for StructType<Opaque> one day
     // but would have to return StructType<Tys>. Maybe this is valid for non opaques, though
     // it might just override types?
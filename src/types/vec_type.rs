@@ -4,7 +4,7 @@ use llvm_sys::prelude::{LLVMTypeRef, LLVMValueRef};
 use crate::AddressSpace;
 use crate::context::ContextRef;
 use crate::types::{ArrayType, BasicTypeEnum, Type, traits::AsTypeRef, FunctionType, PointerType};
-use crate::values::{AsValueRef, ArrayValue, BasicValue, VectorValue, IntValue};
+use crate::values::{AsValueRef, ArrayValue, BasicValue, GenericValue, VectorValue, IntValue};
 use crate::types::enums::BasicMetadataTypeEnum;
 
 /// A `VectorType` is the type of a multiple value SIMD constant or variable.
@@ -154,6 +154,29 @@ impl<'ctx> VectorType<'ctx> {
         }
     }
 
+    /// Creates a vector `GenericValue` from its elements, for use with interpreter `ExecutionEngine`s.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let f64_type = context.f64_type();
+    /// let vector_type = f64_type.vec_type(2);
+    /// let value = vector_type.create_generic_value(vec![
+    ///     f64_type.create_generic_value(1.0),
+    ///     f64_type.create_generic_value(2.0),
+    /// ]);
+    ///
+    /// assert_eq!(value.get_elements().len(), 2);
+    /// ```
+    pub fn create_generic_value(self, values: Vec<GenericValue<'ctx>>) -> GenericValue<'ctx> {
+        assert_eq!(values.len(), self.get_size() as usize, "Incorrect number of elements for VectorType");
+
+        GenericValue::new_aggregate(values)
+    }
+
     // SubType: VectorType<BT> -> BT?
     /// Gets the element type of this `VectorType`.
     ///
@@ -8,10 +8,11 @@ use llvm_sys::prelude::{LLVMValueRef, LLVMBasicBlockRef};
 #[llvm_versions(7.0..=latest)]
 use llvm_sys::debuginfo::{LLVMGetSubprogram, LLVMSetSubprogram};
 
+use std::collections::HashMap;
 use std::ffi::CStr;
 use std::marker::PhantomData;
 use std::mem::forget;
-use std::fmt;
+use std::fmt::{self, Write};
 
 #[llvm_versions(3.9..=latest)]
 use crate::attributes::{Attribute, AttributeLoc};
@@ -19,10 +20,13 @@ use crate::basic_block::BasicBlock;
 #[llvm_versions(7.0..=latest)]
 use crate::debug_info::DISubprogram;
 use crate::module::Linkage;
-use crate::support::to_c_str;
+use crate::support::{escape_dot_string, to_c_str};
+#[llvm_versions(8.0..=latest)]
+use crate::targets::WasmFeature;
 use crate::types::{AnyType, FunctionType, PointerType};
 use crate::values::traits::{AnyValue, AsValueRef};
-use crate::values::{BasicValueEnum, GlobalValue, Value};
+use crate::values::inst_visitor;
+use crate::values::{BasicValueEnum, GlobalValue, InstVisitor, InstructionOpcode, InstructionValue, Value};
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash)]
 pub struct FunctionValue<'ctx> {
@@ -215,6 +219,152 @@ impl<'ctx> FunctionValue<'ctx> {
         }
     }
 
+    /// Prints the control flow graph of this function in the DOT (graphviz) format, with every
+    /// basic block listing its instructions. Unlike `view_function_cfg`, no external viewer is
+    /// launched, which makes it suitable for snapshot tests and headless environments.
+    ///
+    /// Edges leaving a conditional branch are labeled `T` and `F`, edges leaving a switch are
+    /// labeled with their case value (or `default`) and edges leaving an invoke are labeled
+    /// `normal` and `unwind`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("my_module");
+    /// let builder = context.create_builder();
+    /// let void_type = context.void_type();
+    /// let bool_type = context.bool_type();
+    /// let fn_type = void_type.fn_type(&[bool_type.into()], false);
+    /// let function = module.add_function("my_fn", fn_type, None);
+    /// let entry = context.append_basic_block(function, "entry");
+    /// let exit = context.append_basic_block(function, "exit");
+    /// let cond = function.get_first_param().unwrap().into_int_value();
+    ///
+    /// builder.position_at_end(entry);
+    /// builder.build_conditional_branch(cond, exit, exit);
+    /// builder.position_at_end(exit);
+    /// builder.build_return(None);
+    ///
+    /// let dot = function.cfg_only_to_dot();
+    ///
+    /// assert!(dot.contains("b0 -> b1 [label=\"T\"];"));
+    /// assert!(dot.contains("b0 -> b1 [label=\"F\"];"));
+    /// ```
+    #[llvm_versions(3.9..=latest)]
+    pub fn cfg_to_dot(self) -> String {
+        self.cfg_to_dot_string(true)
+    }
+
+    /// Prints the control flow graph of this function in the DOT (graphviz) format, with basic blocks
+    /// labeled only by their names. See `cfg_to_dot` for details.
+    #[llvm_versions(3.9..=latest)]
+    pub fn cfg_only_to_dot(self) -> String {
+        self.cfg_to_dot_string(false)
+    }
+
+    /// Walks over every instruction of this function in program order, calling the
+    /// corresponding hooks of `visitor`. Erasures scheduled through `DeferredEdits` are
+    /// applied once the whole function has been visited.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::values::{DeferredEdits, InstVisitor, InstructionValue};
+    ///
+    /// struct ReturnCounter(usize);
+    ///
+    /// impl<'ctx> InstVisitor<'ctx> for ReturnCounter {
+    ///     fn visit_return(&mut self, _instruction: InstructionValue<'ctx>, _edits: &mut DeferredEdits<'ctx>) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("my_module");
+    /// let builder = context.create_builder();
+    /// let void_type = context.void_type();
+    /// let fn_type = void_type.fn_type(&[], false);
+    /// let function = module.add_function("my_fn", fn_type, None);
+    ///
+    /// builder.position_at_end(context.append_basic_block(function, "entry"));
+    /// builder.build_return(None);
+    ///
+    /// let mut counter = ReturnCounter(0);
+    ///
+    /// function.visit(&mut counter);
+    ///
+    /// assert_eq!(counter.0, 1);
+    /// ```
+    pub fn visit<V: InstVisitor<'ctx>>(self, visitor: &mut V) {
+        inst_visitor::visit_function(self, visitor)
+    }
+
+    #[llvm_versions(3.9..=latest)]
+    fn cfg_to_dot_string(self, include_instructions: bool) -> String {
+        let mut dot = String::new();
+        let name = self.get_name().to_string_lossy();
+
+        writeln!(dot, "digraph \"CFG for '{}' function\" {{", escape_dot_string(&name)).unwrap();
+        writeln!(dot, "    node [shape=box];").unwrap();
+
+        self.write_cfg_dot(&mut dot, "", "    ", include_instructions);
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes the nodes and edges of this function's control flow graph to `dot`, prefixing
+    /// node identifiers with `node_prefix` so that several functions can share a graph.
+    #[llvm_versions(3.9..=latest)]
+    pub(crate) fn write_cfg_dot(self, dot: &mut String, node_prefix: &str, indent: &str, include_instructions: bool) {
+        let basic_blocks = self.get_basic_blocks();
+        let indices: HashMap<_, _> = basic_blocks.iter().enumerate().map(|(index, &basic_block)| (basic_block, index)).collect();
+
+        for (index, basic_block) in basic_blocks.iter().enumerate() {
+            let name = basic_block.get_name().to_string_lossy();
+            let mut label = if name.is_empty() {
+                format!("bb{}", index)
+            } else {
+                name.into_owned()
+            };
+
+            if include_instructions {
+                label.push_str(":\n");
+
+                let mut instruction = basic_block.get_first_instruction();
+
+                while let Some(current_instruction) = instruction {
+                    label.push_str(&current_instruction.print_to_string().to_string());
+                    label.push('\n');
+
+                    instruction = current_instruction.get_next_instruction();
+                }
+            }
+
+            writeln!(dot, "{}{}b{} [label=\"{}\"];", indent, node_prefix, index, escape_dot_string(&label)).unwrap();
+        }
+
+        for (index, basic_block) in basic_blocks.iter().enumerate() {
+            let terminator = match basic_block.get_terminator() {
+                Some(terminator) => terminator,
+                None => continue,
+            };
+
+            for (successor, label) in get_successors(terminator) {
+                let successor_index = indices[&successor];
+
+                match label {
+                    Some(label) => writeln!(dot, "{}{}b{} -> {}b{} [label=\"{}\"];", indent, node_prefix, index, node_prefix, successor_index, escape_dot_string(&label)),
+                    None => writeln!(dot, "{}{}b{} -> {}b{};", indent, node_prefix, index, node_prefix, successor_index),
+                }.unwrap();
+            }
+        }
+    }
+
     // TODO: Look for ways to prevent use after delete but maybe not possible
     pub unsafe fn delete(self) {
         LLVMDeleteFunction(self.as_value_ref())
@@ -518,6 +668,57 @@ impl<'ctx> FunctionValue<'ctx> {
             })
         }
     }
+
+    /// Sets the module this function is imported from when compiled to WebAssembly, which
+    /// defaults to "env". Only applies to functions without a body.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let module = context.create_module("wasm");
+    /// let fn_type = context.void_type().fn_type(&[context.i32_type().into()], false);
+    /// let log = module.add_function("log", fn_type, None);
+    ///
+    /// // Imported as "console" "log_i32"
+    /// log.set_wasm_import_module("console");
+    /// log.set_wasm_import_name("log_i32");
+    /// ```
+    #[llvm_versions(8.0..=latest)]
+    pub fn set_wasm_import_module(self, module: &str) {
+        let context = self.get_type().get_context();
+
+        self.add_attribute(AttributeLoc::Function, context.create_string_attribute("wasm-import-module", module));
+    }
+
+    /// Sets the name this function is imported by when compiled to WebAssembly, which defaults
+    /// to its own name. Only applies to functions without a body.
+    #[llvm_versions(8.0..=latest)]
+    pub fn set_wasm_import_name(self, name: &str) {
+        let context = self.get_type().get_context();
+
+        self.add_attribute(AttributeLoc::Function, context.create_string_attribute("wasm-import-name", name));
+    }
+
+    /// Exports this function under `name` when compiled to WebAssembly.
+    #[llvm_versions(11.0..=latest)]
+    pub fn set_wasm_export_name(self, name: &str) {
+        let context = self.get_type().get_context();
+
+        self.add_attribute(AttributeLoc::Function, context.create_string_attribute("wasm-export-name", name));
+    }
+
+    /// Enables `features` for this function when compiled to WebAssembly, replacing any target
+    /// features it already had. See also `Module::set_wasm_target_features`.
+    #[llvm_versions(8.0..=latest)]
+    pub fn set_wasm_target_features(self, features: &[WasmFeature]) {
+        let context = self.get_type().get_context();
+        let features = WasmFeature::to_features_string(features);
+
+        self.add_attribute(AttributeLoc::Function, context.create_string_attribute("target-features", &features));
+    }
 }
 
 impl AsValueRef for FunctionValue<'_> {
@@ -547,6 +748,44 @@ impl fmt::Debug for FunctionValue<'_> {
     }
 }
 
+/// Gets the successors of a terminator instruction along with a label describing when each is taken.
+#[llvm_versions(3.9..=latest)]
+fn get_successors(terminator: InstructionValue) -> Vec<(BasicBlock, Option<String>)> {
+    let num_operands = terminator.get_num_operands();
+    let get_block_operand = |index| terminator.get_operand(index).and_then(|operand| operand.right());
+
+    match terminator.get_opcode() {
+        // The destinations of a conditional branch are stored in reverse order
+        InstructionOpcode::Br if num_operands == 3 => vec![
+            (get_block_operand(2).expect("Branch should have a true destination"), Some("T".into())),
+            (get_block_operand(1).expect("Branch should have a false destination"), Some("F".into())),
+        ],
+        InstructionOpcode::Switch => {
+            let mut successors = vec![(get_block_operand(1).expect("Switch should have a default destination"), Some("default".into()))];
+
+            for case_index in (2..num_operands).step_by(2) {
+                let value = terminator.get_operand(case_index)
+                    .and_then(|operand| operand.left())
+                    .expect("Switch case should have a value")
+                    .into_int_value();
+                let label = match value.get_sign_extended_constant() {
+                    Some(constant) => constant.to_string(),
+                    None => value.print_to_string().to_string(),
+                };
+
+                successors.push((get_block_operand(case_index + 1).expect("Switch case should have a destination"), Some(label)));
+            }
+
+            successors
+        },
+        InstructionOpcode::Invoke => vec![
+            (get_block_operand(num_operands - 3).expect("Invoke should have a normal destination"), Some("normal".into())),
+            (get_block_operand(num_operands - 2).expect("Invoke should have an unwind destination"), Some("unwind".into())),
+        ],
+        _ => (0..num_operands).filter_map(get_block_operand).map(|basic_block| (basic_block, None)).collect(),
+    }
+}
+
 #[derive(Debug)]
 pub struct ParamValueIter<'ctx> {
     param_iter_value: LLVMValueRef,
@@ -1,14 +1,20 @@
 use libc::c_void;
 use llvm_sys::execution_engine::{LLVMCreateGenericValueOfPointer, LLVMDisposeGenericValue, LLVMGenericValueIntWidth, LLVMGenericValueRef, LLVMGenericValueToInt, LLVMGenericValueToFloat, LLVMGenericValueToPointer};
 
-use crate::types::{AsTypeRef, FloatType};
+use crate::targets::{ByteOrdering, TargetData};
+use crate::types::{AsTypeRef, BasicType, BasicTypeEnum, FloatType};
 
 use std::marker::PhantomData;
+use std::mem::take;
+use std::ptr;
 
 // SubTypes: GenericValue<IntValue, FloatValue, or PointerValue>
 #[derive(Debug)]
 pub struct GenericValue<'ctx> {
+    // Null for aggregates and vectors, which the C API cannot represent, in which case
+    // the members are held in elements instead.
     pub(crate) generic_value: LLVMGenericValueRef,
+    elements: Vec<GenericValue<'ctx>>,
     _phantom: PhantomData<&'ctx ()>,
 }
 
@@ -18,14 +24,29 @@ impl<'ctx> GenericValue<'ctx> {
 
         GenericValue {
             generic_value,
+            elements: Vec::new(),
             _phantom: PhantomData,
         }
     }
 
+    pub(crate) fn new_aggregate(elements: Vec<GenericValue<'ctx>>) -> Self {
+        GenericValue {
+            generic_value: ptr::null_mut(),
+            elements,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn scalar(&self) -> LLVMGenericValueRef {
+        assert!(!self.is_aggregate(), "GenericValue holds an aggregate or vector, not a scalar");
+
+        self.generic_value
+    }
+
     // SubType: GenericValue<IntValue> only
     pub fn int_width(self) -> u32 {
         unsafe {
-            LLVMGenericValueIntWidth(self.generic_value)
+            LLVMGenericValueIntWidth(self.scalar())
         }
     }
 
@@ -40,28 +61,255 @@ impl<'ctx> GenericValue<'ctx> {
     // SubType: impl only for GenericValue<IntValue>
     pub fn as_int(self, is_signed: bool) -> u64 {
         unsafe {
-            LLVMGenericValueToInt(self.generic_value, is_signed as i32)
+            LLVMGenericValueToInt(self.scalar(), is_signed as i32)
         }
     }
 
     // SubType: impl only for GenericValue<FloatValue>
     pub fn as_float(self, float_type: &FloatType<'ctx>) -> f64 {
         unsafe {
-            LLVMGenericValueToFloat(float_type.as_type_ref(), self.generic_value)
+            LLVMGenericValueToFloat(float_type.as_type_ref(), self.scalar())
         }
     }
 
     // SubType: impl only for GenericValue<PointerValue, T>
     // REVIEW: How safe is this really?
     pub unsafe fn into_pointer<T>(self) -> *mut T {
-        LLVMGenericValueToPointer(self.generic_value) as *mut T
+        LLVMGenericValueToPointer(self.scalar()) as *mut T
+    }
+
+    /// Determines whether this `GenericValue` holds the elements of a struct, array or vector
+    /// rather than a scalar.
+    pub fn is_aggregate(&self) -> bool {
+        self.generic_value.is_null()
+    }
+
+    /// Gets the elements of a struct, array or vector `GenericValue`. This is empty for scalars.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    ///
+    /// let context = Context::create();
+    /// let i32_type = context.i32_type();
+    /// let array_type = i32_type.array_type(2);
+    /// let array = array_type.create_generic_value(vec![
+    ///     i32_type.create_generic_value(1, false),
+    ///     i32_type.create_generic_value(2, false),
+    /// ]);
+    ///
+    /// assert!(array.is_aggregate());
+    /// assert_eq!(array.get_elements().len(), 2);
+    /// ```
+    pub fn get_elements(&self) -> &[GenericValue<'ctx>] {
+        &self.elements
+    }
+
+    /// Consumes a struct, array or vector `GenericValue`, returning its elements.
+    pub fn into_elements(mut self) -> Vec<GenericValue<'ctx>> {
+        take(&mut self.elements)
+    }
+
+    /// Encodes this `GenericValue` as a value of type `ty` in memory, as laid out by `target_data`.
+    /// The returned buffer is `ty`'s ABI size in length.
+    ///
+    /// Integers of up to 64 bits, `f32`s, `f64`s, pointers and aggregates and vectors of those
+    /// are supported. Vector elements must be a whole number of bytes in size.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use inkwell::context::Context;
+    /// use inkwell::targets::TargetData;
+    /// use inkwell::values::GenericValue;
+    ///
+    /// let context = Context::create();
+    /// let target_data = TargetData::create("e-i64:64");
+    /// let i16_type = context.i16_type();
+    /// let i64_type = context.i64_type();
+    /// let struct_type = context.struct_type(&[i16_type.into(), i64_type.into()], false);
+    /// let value = struct_type.create_generic_value(vec![
+    ///     i16_type.create_generic_value(1, false),
+    ///     i64_type.create_generic_value(2, false),
+    /// ]);
+    /// let bytes = value.to_bytes(struct_type, &target_data);
+    ///
+    /// assert_eq!(bytes, [1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+    ///
+    /// let value = GenericValue::from_bytes(struct_type, &bytes, &target_data);
+    ///
+    /// assert_eq!(value.into_elements().pop().unwrap().as_int(false), 2);
+    /// ```
+    pub fn to_bytes<T: BasicType<'ctx>>(&self, ty: T, target_data: &TargetData) -> Vec<u8> {
+        let ty = ty.as_basic_type_enum();
+        let mut bytes = vec![0; target_data.get_abi_size(&ty) as usize];
+
+        self.write_bytes(ty, target_data, &mut bytes);
+
+        bytes
+    }
+
+    /// Decodes a `GenericValue` of type `ty` from memory laid out by `target_data`. See
+    /// `GenericValue::to_bytes` for the supported types.
+    pub fn from_bytes<T: BasicType<'ctx>>(ty: T, bytes: &[u8], target_data: &TargetData) -> Self {
+        let ty = ty.as_basic_type_enum();
+
+        assert!(bytes.len() as u64 >= target_data.get_store_size(&ty), "Not enough bytes for type");
+
+        GenericValue::read_bytes(ty, target_data, bytes)
+    }
+
+    fn write_bytes(&self, ty: BasicTypeEnum<'ctx>, target_data: &TargetData, bytes: &mut [u8]) {
+        let byte_ordering = target_data.get_byte_ordering();
+
+        match ty {
+            BasicTypeEnum::IntType(int_type) => {
+                let size = target_data.get_store_size(&int_type) as usize;
+
+                assert!(size <= 8, "GenericValue only supports integers of up to 64 bits");
+
+                let value = unsafe { LLVMGenericValueToInt(self.scalar(), 0) };
+
+                write_uint(value, &mut bytes[..size], byte_ordering);
+            },
+            BasicTypeEnum::FloatType(float_type) => {
+                let value = unsafe { LLVMGenericValueToFloat(float_type.as_type_ref(), self.scalar()) };
+
+                match target_data.get_bit_size(&float_type) {
+                    32 => write_uint((value as f32).to_bits() as u64, &mut bytes[..4], byte_ordering),
+                    64 => write_uint(value.to_bits(), &mut bytes[..8], byte_ordering),
+                    _ => panic!("GenericValue only supports 32 and 64 bit floats"),
+                }
+            },
+            BasicTypeEnum::PointerType(ptr_type) => {
+                let size = target_data.get_store_size(&ptr_type) as usize;
+                let value = unsafe { LLVMGenericValueToPointer(self.scalar()) } as usize as u64;
+
+                write_uint(value, &mut bytes[..size], byte_ordering);
+            },
+            BasicTypeEnum::ArrayType(_) | BasicTypeEnum::StructType(_) | BasicTypeEnum::VectorType(_) => {
+                let layout = get_element_layout(ty, target_data);
+
+                assert!(self.is_aggregate(), "Expected an aggregate or vector GenericValue");
+                assert_eq!(self.elements.len(), layout.len(), "Incorrect number of elements in GenericValue");
+
+                for (element, (element_type, offset)) in self.elements.iter().zip(layout) {
+                    element.write_bytes(element_type, target_data, &mut bytes[offset as usize..]);
+                }
+            },
+        }
+    }
+
+    fn read_bytes(ty: BasicTypeEnum<'ctx>, target_data: &TargetData, bytes: &[u8]) -> Self {
+        let byte_ordering = target_data.get_byte_ordering();
+
+        match ty {
+            BasicTypeEnum::IntType(int_type) => {
+                let size = target_data.get_store_size(&int_type) as usize;
+
+                assert!(size <= 8, "GenericValue only supports integers of up to 64 bits");
+
+                int_type.create_generic_value(read_uint(&bytes[..size], byte_ordering), false)
+            },
+            BasicTypeEnum::FloatType(float_type) => {
+                let value = match target_data.get_bit_size(&float_type) {
+                    32 => f32::from_bits(read_uint(&bytes[..4], byte_ordering) as u32) as f64,
+                    64 => f64::from_bits(read_uint(&bytes[..8], byte_ordering)),
+                    _ => panic!("GenericValue only supports 32 and 64 bit floats"),
+                };
+
+                float_type.create_generic_value(value)
+            },
+            BasicTypeEnum::PointerType(ptr_type) => {
+                let size = target_data.get_store_size(&ptr_type) as usize;
+                let value = read_uint(&bytes[..size], byte_ordering) as usize as *mut c_void;
+
+                unsafe {
+                    GenericValue::new(LLVMCreateGenericValueOfPointer(value))
+                }
+            },
+            BasicTypeEnum::ArrayType(_) | BasicTypeEnum::StructType(_) | BasicTypeEnum::VectorType(_) => {
+                let elements = get_element_layout(ty, target_data)
+                    .into_iter()
+                    .map(|(element_type, offset)| GenericValue::read_bytes(element_type, target_data, &bytes[offset as usize..]))
+                    .collect();
+
+                GenericValue::new_aggregate(elements)
+            },
+        }
     }
 }
 
 impl Drop for GenericValue<'_> {
     fn drop(&mut self) {
+        if self.generic_value.is_null() {
+            return;
+        }
+
         unsafe {
             LLVMDisposeGenericValue(self.generic_value)
         }
     }
 }
+
+/// Gets the type and byte offset of each element of a struct, array or vector type as laid
+/// out in memory by `target_data`. Scalar types have no elements.
+pub(crate) fn get_element_layout<'ctx>(ty: BasicTypeEnum<'ctx>, target_data: &TargetData) -> Vec<(BasicTypeEnum<'ctx>, u64)> {
+    match ty {
+        BasicTypeEnum::StructType(struct_type) => {
+            struct_type.get_field_types()
+                .into_iter()
+                .enumerate()
+                .map(|(index, field_type)| {
+                    let offset = target_data.offset_of_element(&struct_type, index as u32)
+                        .expect("Field index should be in bounds");
+
+                    (field_type, offset)
+                })
+                .collect()
+        },
+        BasicTypeEnum::ArrayType(array_type) => {
+            let element_type = array_type.get_element_type();
+            let stride = target_data.get_abi_size(&element_type);
+
+            (0..array_type.len() as u64).map(|index| (element_type, index * stride)).collect()
+        },
+        BasicTypeEnum::VectorType(vector_type) => {
+            let element_type = vector_type.get_element_type();
+
+            assert!(target_data.get_bit_size(&element_type) % 8 == 0, "GenericValue only supports vectors of byte sized elements");
+
+            let stride = target_data.get_store_size(&element_type);
+
+            (0..vector_type.get_size() as u64).map(|index| (element_type, index * stride)).collect()
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn write_uint(value: u64, bytes: &mut [u8], byte_ordering: ByteOrdering) {
+    let len = bytes.len();
+
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        let shift = match byte_ordering {
+            ByteOrdering::LittleEndian => index,
+            ByteOrdering::BigEndian => len - 1 - index,
+        };
+
+        *byte = (value >> (shift * 8)) as u8;
+    }
+}
+
+fn read_uint(bytes: &[u8], byte_ordering: ByteOrdering) -> u64 {
+    let len = bytes.len();
+
+    bytes.iter().enumerate().fold(0, |value, (index, &byte)| {
+        let shift = match byte_ordering {
+            ByteOrdering::LittleEndian => index,
+            ByteOrdering::BigEndian => len - 1 - index,
+        };
+
+        value | (byte as u64) << (shift * 8)
+    })
+}
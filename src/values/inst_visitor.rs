@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+
+use crate::basic_block::BasicBlock;
+use crate::values::{FunctionValue, InstructionOpcode, InstructionValue};
+
+macro_rules! visit_hooks {
+    ($($(#[$attr:meta])* fn $method:ident => $fallback:ident;)*) => {
+        $(
+            $(#[$attr])*
+            fn $method(&mut self, instruction: InstructionValue<'ctx>, edits: &mut DeferredEdits<'ctx>) {
+                self.$fallback(instruction, edits)
+            }
+        )*
+    };
+}
+
+/// An `InstVisitor` walks over the instructions of a `FunctionValue` or `Module` (see
+/// `FunctionValue::visit` and `Module::visit`), calling a hook for every instruction depending on
+/// its opcode.
+///
+/// Every hook has a default implementation which delegates to a more general one, so only the hooks
+/// of interest need to be implemented. Opcode hooks (ie `visit_add`) delegate to category hooks
+/// (ie `visit_binary_operator`), which in turn delegate to `visit_instruction`, which does nothing
+/// by default.
+///
+/// Hooks may freely modify the IR, with the exception of erasing instructions other than the one
+/// being visited, which could invalidate the traversal. Instead, erasures should be scheduled through
+/// the `DeferredEdits` passed to every hook.
+///
+/// # Example
+///
+/// ```no_run
+/// use inkwell::context::Context;
+/// use inkwell::values::{DeferredEdits, InstVisitor, InstructionValue};
+///
+/// #[derive(Default)]
+/// struct LoadCounter {
+///     loads: usize,
+/// }
+///
+/// impl<'ctx> InstVisitor<'ctx> for LoadCounter {
+///     fn visit_load(&mut self, _instruction: InstructionValue<'ctx>, _edits: &mut DeferredEdits<'ctx>) {
+///         self.loads += 1;
+///     }
+/// }
+///
+/// let context = Context::create();
+/// let module = context.create_module("my_module");
+/// let mut counter = LoadCounter::default();
+///
+/// module.visit(&mut counter);
+///
+/// assert_eq!(counter.loads, 0);
+/// ```
+pub trait InstVisitor<'ctx> {
+    /// Called for every function, before any of its basic blocks are visited.
+    fn visit_function(&mut self, _function: FunctionValue<'ctx>) {}
+
+    /// Called for every basic block, before any of its instructions are visited.
+    fn visit_basic_block(&mut self, _basic_block: BasicBlock<'ctx>) {}
+
+    /// Called for every instruction whose more specific hooks aren't implemented.
+    fn visit_instruction(&mut self, _instruction: InstructionValue<'ctx>, _edits: &mut DeferredEdits<'ctx>) {}
+
+    visit_hooks! {
+        /// Called for binary operators, ie integer and floating point arithmetic, shifts and bitwise operations.
+        fn visit_binary_operator => visit_instruction;
+        /// Called for unary operators, ie `fneg`.
+        fn visit_unary_operator => visit_instruction;
+        /// Called for conversions between types.
+        fn visit_cast => visit_instruction;
+        /// Called for integer and floating point comparisons.
+        fn visit_cmp => visit_instruction;
+        /// Called for instructions which terminate a basic block.
+        fn visit_terminator => visit_instruction;
+
+        /// Called for `add` instructions.
+        fn visit_add => visit_binary_operator;
+        /// Called for `fadd` instructions.
+        fn visit_fadd => visit_binary_operator;
+        /// Called for `sub` instructions.
+        fn visit_sub => visit_binary_operator;
+        /// Called for `fsub` instructions.
+        fn visit_fsub => visit_binary_operator;
+        /// Called for `mul` instructions.
+        fn visit_mul => visit_binary_operator;
+        /// Called for `fmul` instructions.
+        fn visit_fmul => visit_binary_operator;
+        /// Called for `udiv` instructions.
+        fn visit_udiv => visit_binary_operator;
+        /// Called for `sdiv` instructions.
+        fn visit_sdiv => visit_binary_operator;
+        /// Called for `fdiv` instructions.
+        fn visit_fdiv => visit_binary_operator;
+        /// Called for `urem` instructions.
+        fn visit_urem => visit_binary_operator;
+        /// Called for `srem` instructions.
+        fn visit_srem => visit_binary_operator;
+        /// Called for `frem` instructions.
+        fn visit_frem => visit_binary_operator;
+        /// Called for `shl` instructions.
+        fn visit_shl => visit_binary_operator;
+        /// Called for `lshr` instructions.
+        fn visit_lshr => visit_binary_operator;
+        /// Called for `ashr` instructions.
+        fn visit_ashr => visit_binary_operator;
+        /// Called for `and` instructions.
+        fn visit_and => visit_binary_operator;
+        /// Called for `or` instructions.
+        fn visit_or => visit_binary_operator;
+        /// Called for `xor` instructions.
+        fn visit_xor => visit_binary_operator;
+
+        /// Called for `fneg` instructions.
+        fn visit_fneg => visit_unary_operator;
+
+        /// Called for `trunc` instructions.
+        fn visit_trunc => visit_cast;
+        /// Called for `zext` instructions.
+        fn visit_zext => visit_cast;
+        /// Called for `sext` instructions.
+        fn visit_sext => visit_cast;
+        /// Called for `fptoui` instructions.
+        fn visit_fp_to_ui => visit_cast;
+        /// Called for `fptosi` instructions.
+        fn visit_fp_to_si => visit_cast;
+        /// Called for `uitofp` instructions.
+        fn visit_ui_to_fp => visit_cast;
+        /// Called for `sitofp` instructions.
+        fn visit_si_to_fp => visit_cast;
+        /// Called for `fptrunc` instructions.
+        fn visit_fp_trunc => visit_cast;
+        /// Called for `fpext` instructions.
+        fn visit_fp_ext => visit_cast;
+        /// Called for `ptrtoint` instructions.
+        fn visit_ptr_to_int => visit_cast;
+        /// Called for `inttoptr` instructions.
+        fn visit_int_to_ptr => visit_cast;
+        /// Called for `bitcast` instructions.
+        fn visit_bitcast => visit_cast;
+        /// Called for `addrspacecast` instructions.
+        fn visit_addr_space_cast => visit_cast;
+
+        /// Called for `icmp` instructions.
+        fn visit_icmp => visit_cmp;
+        /// Called for `fcmp` instructions.
+        fn visit_fcmp => visit_cmp;
+
+        /// Called for `ret` instructions.
+        fn visit_return => visit_terminator;
+        /// Called for conditional and unconditional `br` instructions.
+        fn visit_br => visit_terminator;
+        /// Called for `switch` instructions.
+        fn visit_switch => visit_terminator;
+        /// Called for `indirectbr` instructions.
+        fn visit_indirect_br => visit_terminator;
+        /// Called for `invoke` instructions.
+        fn visit_invoke => visit_terminator;
+        /// Called for `callbr` instructions.
+        fn visit_call_br => visit_terminator;
+        /// Called for `resume` instructions.
+        fn visit_resume => visit_terminator;
+        /// Called for `unreachable` instructions.
+        fn visit_unreachable => visit_terminator;
+        /// Called for `catchret` instructions.
+        fn visit_catch_ret => visit_terminator;
+        /// Called for `catchswitch` instructions.
+        fn visit_catch_switch => visit_terminator;
+        /// Called for `cleanupret` instructions.
+        fn visit_cleanup_ret => visit_terminator;
+
+        /// Called for `alloca` instructions.
+        fn visit_alloca => visit_instruction;
+        /// Called for `load` instructions.
+        fn visit_load => visit_instruction;
+        /// Called for `store` instructions.
+        fn visit_store => visit_instruction;
+        /// Called for `getelementptr` instructions.
+        fn visit_gep => visit_instruction;
+        /// Called for `fence` instructions.
+        fn visit_fence => visit_instruction;
+        /// Called for `cmpxchg` instructions.
+        fn visit_atomic_cmpxchg => visit_instruction;
+        /// Called for `atomicrmw` instructions.
+        fn visit_atomic_rmw => visit_instruction;
+        /// Called for `phi` instructions.
+        fn visit_phi => visit_instruction;
+        /// Called for `call` instructions.
+        fn visit_call => visit_instruction;
+        /// Called for `select` instructions.
+        fn visit_select => visit_instruction;
+        /// Called for `va_arg` instructions.
+        fn visit_va_arg => visit_instruction;
+        /// Called for `extractelement` instructions.
+        fn visit_extract_element => visit_instruction;
+        /// Called for `insertelement` instructions.
+        fn visit_insert_element => visit_instruction;
+        /// Called for `shufflevector` instructions.
+        fn visit_shuffle_vector => visit_instruction;
+        /// Called for `extractvalue` instructions.
+        fn visit_extract_value => visit_instruction;
+        /// Called for `insertvalue` instructions.
+        fn visit_insert_value => visit_instruction;
+        /// Called for `landingpad` instructions.
+        fn visit_landing_pad => visit_instruction;
+        /// Called for `catchpad` instructions.
+        fn visit_catch_pad => visit_instruction;
+        /// Called for `cleanuppad` instructions.
+        fn visit_cleanup_pad => visit_instruction;
+        /// Called for `freeze` instructions.
+        fn visit_freeze => visit_instruction;
+        /// Called for instructions with the internal `UserOp1` opcode.
+        fn visit_user_op1 => visit_instruction;
+        /// Called for instructions with the internal `UserOp2` opcode.
+        fn visit_user_op2 => visit_instruction;
+    }
+}
+
+/// Modifications requested by an `InstVisitor` which are applied once the function being
+/// visited has been fully traversed.
+#[derive(Debug, Default)]
+pub struct DeferredEdits<'ctx> {
+    erased: Vec<InstructionValue<'ctx>>,
+    erased_set: HashSet<InstructionValue<'ctx>>,
+}
+
+impl<'ctx> DeferredEdits<'ctx> {
+    /// Schedules an instruction to be erased from its basic block. Instructions scheduled for
+    /// erasure are no longer visited, and are erased in the reverse order they were scheduled in
+    /// so that erasing a chain of instructions in program order is safe.
+    ///
+    /// Any remaining uses of the instruction outside of the erased instructions must be replaced
+    /// before the erasure is applied.
+    pub fn erase(&mut self, instruction: InstructionValue<'ctx>) {
+        if self.erased_set.insert(instruction) {
+            self.erased.push(instruction);
+        }
+    }
+
+    /// Determines whether an instruction has been scheduled for erasure.
+    pub fn is_erased(&self, instruction: InstructionValue<'ctx>) -> bool {
+        self.erased_set.contains(&instruction)
+    }
+
+    fn apply(self) {
+        for instruction in self.erased.into_iter().rev() {
+            instruction.erase_from_basic_block();
+        }
+    }
+}
+
+pub(crate) fn visit_function<'ctx, V: InstVisitor<'ctx> + ?Sized>(function: FunctionValue<'ctx>, visitor: &mut V) {
+    let mut edits = DeferredEdits::default();
+
+    visitor.visit_function(function);
+
+    let mut basic_block = function.get_first_basic_block();
+
+    while let Some(current_block) = basic_block {
+        visitor.visit_basic_block(current_block);
+
+        let mut instruction = current_block.get_first_instruction();
+
+        while let Some(current_instruction) = instruction {
+            // Fetched up front so that the current instruction may be moved or erased by the visitor
+            instruction = current_instruction.get_next_instruction();
+
+            if !edits.is_erased(current_instruction) {
+                dispatch(visitor, current_instruction, &mut edits);
+            }
+        }
+
+        basic_block = current_block.get_next_basic_block();
+    }
+
+    edits.apply();
+}
+
+fn dispatch<'ctx, V: InstVisitor<'ctx> + ?Sized>(visitor: &mut V, instruction: InstructionValue<'ctx>, edits: &mut DeferredEdits<'ctx>) {
+    match instruction.get_opcode() {
+        InstructionOpcode::Add => visitor.visit_add(instruction, edits),
+        InstructionOpcode::AddrSpaceCast => visitor.visit_addr_space_cast(instruction, edits),
+        InstructionOpcode::Alloca => visitor.visit_alloca(instruction, edits),
+        InstructionOpcode::And => visitor.visit_and(instruction, edits),
+        InstructionOpcode::AShr => visitor.visit_ashr(instruction, edits),
+        InstructionOpcode::AtomicCmpXchg => visitor.visit_atomic_cmpxchg(instruction, edits),
+        InstructionOpcode::AtomicRMW => visitor.visit_atomic_rmw(instruction, edits),
+        InstructionOpcode::BitCast => visitor.visit_bitcast(instruction, edits),
+        InstructionOpcode::Br => visitor.visit_br(instruction, edits),
+        InstructionOpcode::Call => visitor.visit_call(instruction, edits),
+        #[cfg(any(feature = "llvm9-0", feature = "llvm10-0", feature = "llvm11-0", feature = "llvm12-0", feature = "llvm13-0"))]
+        InstructionOpcode::CallBr => visitor.visit_call_br(instruction, edits),
+        #[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7")))]
+        InstructionOpcode::CatchPad => visitor.visit_catch_pad(instruction, edits),
+        #[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7")))]
+        InstructionOpcode::CatchRet => visitor.visit_catch_ret(instruction, edits),
+        #[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7")))]
+        InstructionOpcode::CatchSwitch => visitor.visit_catch_switch(instruction, edits),
+        #[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7")))]
+        InstructionOpcode::CleanupPad => visitor.visit_cleanup_pad(instruction, edits),
+        #[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7")))]
+        InstructionOpcode::CleanupRet => visitor.visit_cleanup_ret(instruction, edits),
+        InstructionOpcode::ExtractElement => visitor.visit_extract_element(instruction, edits),
+        InstructionOpcode::ExtractValue => visitor.visit_extract_value(instruction, edits),
+        #[cfg(any(feature = "llvm8-0", feature = "llvm9-0", feature = "llvm10-0", feature = "llvm11-0", feature = "llvm12-0", feature = "llvm13-0"))]
+        InstructionOpcode::FNeg => visitor.visit_fneg(instruction, edits),
+        InstructionOpcode::FAdd => visitor.visit_fadd(instruction, edits),
+        InstructionOpcode::FCmp => visitor.visit_fcmp(instruction, edits),
+        InstructionOpcode::FDiv => visitor.visit_fdiv(instruction, edits),
+        InstructionOpcode::Fence => visitor.visit_fence(instruction, edits),
+        InstructionOpcode::FMul => visitor.visit_fmul(instruction, edits),
+        InstructionOpcode::FPExt => visitor.visit_fp_ext(instruction, edits),
+        InstructionOpcode::FPToSI => visitor.visit_fp_to_si(instruction, edits),
+        InstructionOpcode::FPToUI => visitor.visit_fp_to_ui(instruction, edits),
+        InstructionOpcode::FPTrunc => visitor.visit_fp_trunc(instruction, edits),
+        #[cfg(any(feature = "llvm10-0", feature = "llvm11-0", feature = "llvm12-0", feature = "llvm13-0"))]
+        InstructionOpcode::Freeze => visitor.visit_freeze(instruction, edits),
+        InstructionOpcode::FRem => visitor.visit_frem(instruction, edits),
+        InstructionOpcode::FSub => visitor.visit_fsub(instruction, edits),
+        InstructionOpcode::GetElementPtr => visitor.visit_gep(instruction, edits),
+        InstructionOpcode::ICmp => visitor.visit_icmp(instruction, edits),
+        InstructionOpcode::IndirectBr => visitor.visit_indirect_br(instruction, edits),
+        InstructionOpcode::InsertElement => visitor.visit_insert_element(instruction, edits),
+        InstructionOpcode::InsertValue => visitor.visit_insert_value(instruction, edits),
+        InstructionOpcode::IntToPtr => visitor.visit_int_to_ptr(instruction, edits),
+        InstructionOpcode::Invoke => visitor.visit_invoke(instruction, edits),
+        InstructionOpcode::LandingPad => visitor.visit_landing_pad(instruction, edits),
+        InstructionOpcode::Load => visitor.visit_load(instruction, edits),
+        InstructionOpcode::LShr => visitor.visit_lshr(instruction, edits),
+        InstructionOpcode::Mul => visitor.visit_mul(instruction, edits),
+        InstructionOpcode::Or => visitor.visit_or(instruction, edits),
+        InstructionOpcode::Phi => visitor.visit_phi(instruction, edits),
+        InstructionOpcode::PtrToInt => visitor.visit_ptr_to_int(instruction, edits),
+        InstructionOpcode::Resume => visitor.visit_resume(instruction, edits),
+        InstructionOpcode::Return => visitor.visit_return(instruction, edits),
+        InstructionOpcode::SDiv => visitor.visit_sdiv(instruction, edits),
+        InstructionOpcode::Select => visitor.visit_select(instruction, edits),
+        InstructionOpcode::SExt => visitor.visit_sext(instruction, edits),
+        InstructionOpcode::Shl => visitor.visit_shl(instruction, edits),
+        InstructionOpcode::ShuffleVector => visitor.visit_shuffle_vector(instruction, edits),
+        InstructionOpcode::SIToFP => visitor.visit_si_to_fp(instruction, edits),
+        InstructionOpcode::SRem => visitor.visit_srem(instruction, edits),
+        InstructionOpcode::Store => visitor.visit_store(instruction, edits),
+        InstructionOpcode::Sub => visitor.visit_sub(instruction, edits),
+        InstructionOpcode::Switch => visitor.visit_switch(instruction, edits),
+        InstructionOpcode::Trunc => visitor.visit_trunc(instruction, edits),
+        InstructionOpcode::UDiv => visitor.visit_udiv(instruction, edits),
+        InstructionOpcode::UIToFP => visitor.visit_ui_to_fp(instruction, edits),
+        InstructionOpcode::Unreachable => visitor.visit_unreachable(instruction, edits),
+        InstructionOpcode::URem => visitor.visit_urem(instruction, edits),
+        InstructionOpcode::UserOp1 => visitor.visit_user_op1(instruction, edits),
+        InstructionOpcode::UserOp2 => visitor.visit_user_op2(instruction, edits),
+        InstructionOpcode::VAArg => visitor.visit_va_arg(instruction, edits),
+        InstructionOpcode::Xor => visitor.visit_xor(instruction, edits),
+        InstructionOpcode::ZExt => visitor.visit_zext(instruction, edits),
+    }
+}
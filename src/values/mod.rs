@@ -11,6 +11,8 @@ mod float_value;
 mod fn_value;
 mod generic_value;
 mod global_value;
+#[deny(missing_docs)]
+mod inst_visitor;
 mod instruction_value;
 mod int_value;
 mod metadata_value;
@@ -29,9 +31,11 @@ pub use crate::values::enums::{AnyValueEnum, AggregateValueEnum, BasicValueEnum,
 pub use crate::values::float_value::FloatValue;
 pub use crate::values::fn_value::FunctionValue;
 pub use crate::values::generic_value::GenericValue;
+pub(crate) use crate::values::generic_value::get_element_layout;
 pub use crate::values::global_value::GlobalValue;
 #[llvm_versions(7.0..=latest)]
 pub use crate::values::global_value::UnnamedAddress;
+pub use crate::values::inst_visitor::{DeferredEdits, InstVisitor};
 pub use crate::values::instruction_value::{InstructionValue, InstructionOpcode};
 pub use crate::values::int_value::IntValue;
 pub use crate::values::metadata_value::{MetadataValue, FIRST_CUSTOM_METADATA_KIND_ID};
@@ -7,8 +7,25 @@ extern crate inkwell_internals;
 #[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7", feature = "llvm3-8")))]
 mod test_attributes;
 mod test_basic_block;
+#[cfg(all(
+    feature = "target-bpf",
+    not(any(
+        feature = "llvm3-6",
+        feature = "llvm3-7",
+        feature = "llvm3-8",
+        feature = "llvm3-9",
+        feature = "llvm4-0",
+        feature = "llvm5-0",
+        feature = "llvm6-0",
+        feature = "llvm7-0"
+    ))
+))]
+mod test_bpf;
 mod test_builder;
+#[cfg(not(any(feature = "llvm3-6", feature = "llvm3-7", feature = "llvm3-8")))]
+mod test_call_graph;
 mod test_context;
+mod test_data_layout;
 #[cfg(not(any(
     feature = "llvm3-6",
     feature = "llvm3-7",
@@ -20,11 +37,33 @@ mod test_context;
 )))]
 mod test_debug_info;
 mod test_execution_engine;
+#[cfg(any(feature = "target-nvptx", feature = "target-amdgpu"))]
+mod test_gpu;
 mod test_instruction_values;
+mod test_ir_macro;
+#[cfg(unix)]
+mod test_memory_manager;
 mod test_module;
+mod test_object_cache;
 mod test_object_file;
 mod test_passes;
+mod test_pattern_match;
+mod test_symbolizer;
 mod test_targets;
 mod test_tari_example;
 mod test_types;
 mod test_values;
+#[cfg(all(
+    feature = "target-webassembly",
+    not(any(
+        feature = "llvm3-6",
+        feature = "llvm3-7",
+        feature = "llvm3-8",
+        feature = "llvm3-9",
+        feature = "llvm4-0",
+        feature = "llvm5-0",
+        feature = "llvm6-0",
+        feature = "llvm7-0"
+    ))
+))]
+mod test_wasm;
@@ -0,0 +1,106 @@
+use inkwell::{AddressSpace, OptimizationLevel};
+use inkwell::bpf::{BpfHelper, BpfMapDefinition, BpfMapType, BpfObjectBuilder, ProgramSection};
+use inkwell::context::Context;
+use inkwell::module::Module;
+use inkwell::object_file::ObjectFile;
+use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetTriple};
+
+fn write_bpf_object(module: &Module) -> ObjectFile {
+    Target::initialize_bpf(&InitializationConfig::default());
+
+    let triple = TargetTriple::create("bpfel");
+    let target = Target::from_triple(&triple).unwrap();
+    let target_machine = target
+        .create_target_machine(&triple, "generic", "", OptimizationLevel::Default, RelocMode::Default, CodeModel::Default)
+        .unwrap();
+
+    target_machine
+        .write_to_memory_buffer(module, FileType::Object)
+        .unwrap()
+        .create_object_file()
+        .unwrap()
+}
+
+fn get_section_contents(object_file: &ObjectFile, name: &str) -> Option<Vec<u8>> {
+    object_file
+        .get_sections()
+        .find(|section| section.get_name().and_then(|name| name.to_str().ok()) == Some(name))
+        .map(|section| section.get_contents().to_vec())
+}
+
+#[test]
+fn test_bpf_sections() {
+    let context = Context::create();
+    let module = context.create_module("count_packets");
+    let builder = context.create_builder();
+    let bpf_object = BpfObjectBuilder::new(&module, "count_packets.c");
+    let i8_ptr_type = context.i8_type().ptr_type(AddressSpace::Generic);
+    let i32_type = context.i32_type();
+
+    bpf_object.set_license("GPL");
+
+    let counts = bpf_object.add_map("counts", &BpfMapDefinition::new(BpfMapType::Hash, 4, 8, 128));
+
+    bpf_object.add_map("events", &BpfMapDefinition::new(BpfMapType::RingBuf, 0, 0, 4096));
+
+    let program = bpf_object.add_program("count_packets", ProgramSection::Xdp);
+
+    builder.position_at_end(context.append_basic_block(program, "entry"));
+
+    let key = builder.build_alloca(i32_type, "key");
+
+    builder.build_store(key, i32_type.const_zero());
+
+    let map = builder.build_pointer_cast(counts.as_pointer_value(), i8_ptr_type, "map");
+    let key = builder.build_pointer_cast(key, i8_ptr_type, "key");
+    let lookup_type = i8_ptr_type.fn_type(&[i8_ptr_type.into(), i8_ptr_type.into()], false);
+
+    bpf_object.build_helper_call(&builder, BpfHelper::MapLookupElem, lookup_type, &[map.into(), key.into()], "count");
+    builder.build_return(Some(&i32_type.const_int(2, false)));
+    bpf_object.finalize();
+
+    assert!(module.verify().is_ok());
+
+    let object_file = write_bpf_object(&module);
+    let program = get_section_contents(&object_file, "xdp").unwrap();
+
+    // The lookup is a call instruction whose immediate is the helper's ID, rather than a relocation
+    assert!(program.chunks(8).any(|instruction| instruction[0] == 0x85 && instruction[4..] == [1, 0, 0, 0]));
+
+    // Each map's struct has a pointer per field: four for "counts", and two for "events", which has no key or value
+    assert_eq!(get_section_contents(&object_file, ".maps").unwrap().len(), 48);
+    assert_eq!(get_section_contents(&object_file, "license").unwrap(), b"GPL\0");
+    assert!(get_section_contents(&object_file, ".BTF").is_some());
+    assert!(get_section_contents(&object_file, ".eh_frame").is_none());
+}
+
+#[test]
+fn test_bpf_program_sections() {
+    assert_eq!(ProgramSection::Xdp.get_name(), "xdp");
+    assert_eq!(ProgramSection::Kprobe("do_sys_open").get_name(), "kprobe/do_sys_open");
+    assert_eq!(ProgramSection::Tracepoint("syscalls", "sys_enter_openat").get_name(), "tracepoint/syscalls/sys_enter_openat");
+    assert_eq!(ProgramSection::Custom("lsm/file_open").get_name(), "lsm/file_open");
+    assert_eq!(BpfMapType::RingBuf.get_id(), 27);
+    assert_eq!(BpfHelper::Other(200).get_id(), 200);
+
+    let definition = BpfMapDefinition::new(BpfMapType::LpmTrie, 8, 4, 256).with_flags(1);
+
+    assert_eq!(definition.get_map_type(), BpfMapType::LpmTrie);
+    assert_eq!(definition.get_flags(), 1);
+}
+
+#[test]
+fn test_bpf_builders_share_module() {
+    let context = Context::create();
+    let module = context.create_module("two_objects");
+    let first = BpfObjectBuilder::new(&module, "first.c");
+    let second = BpfObjectBuilder::new(&module, "second.c");
+
+    first.add_map("first", &BpfMapDefinition::new(BpfMapType::Array, 4, 8, 1));
+    second.add_map("second", &BpfMapDefinition::new(BpfMapType::Array, 4, 8, 1));
+    first.finalize();
+    second.finalize();
+
+    assert!(module.get_flag("Debug Info Version").is_some());
+    assert!(module.verify().is_ok());
+}
@@ -1,4 +1,4 @@
-use inkwell::{AddressSpace, AtomicOrdering, AtomicRMWBinOp, OptimizationLevel};
+use inkwell::{AddressSpace, AtomicOrdering, AtomicRMWBinOp, IntPredicate, OptimizationLevel};
 use inkwell::context::Context;
 use inkwell::values::BasicValue;
 use inkwell::values::CallableValue;
@@ -1224,3 +1224,272 @@ fn test_safe_struct_gep() {
     assert!(builder.build_struct_gep(struct_ptr, 1, "struct_gep").is_ok());
     assert!(builder.build_struct_gep(struct_ptr, 2, "struct_gep").is_err());
 }
+
+#[test]
+fn test_build_if_then_else() {
+    let context = Context::create();
+    let module = context.create_module("if_then_else");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let fn_type = i32_type.fn_type(&[i32_type.into(), i32_type.into()], false);
+
+    // Here we're going to create a function that looks roughly like:
+    // fn max(lhs: i32, rhs: i32) -> i32 {
+    //     if lhs > rhs { lhs } else { rhs }
+    // }
+
+    let function = module.add_function("max", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+    let lhs = function.get_first_param().unwrap().into_int_value();
+    let rhs = function.get_nth_param(1).unwrap().into_int_value();
+
+    builder.position_at_end(entry);
+
+    let cmp = builder.build_int_compare(IntPredicate::SGT, lhs, rhs, "cmp");
+    let merged = builder.build_if_then_else(cmp, |_| vec![lhs.into()], |_| vec![rhs.into()], "max").unwrap();
+
+    assert_eq!(merged.len(), 1);
+    assert!(merged[0].as_instruction_value().is_some());
+    assert_eq!(function.count_basic_blocks(), 4);
+
+    builder.build_return(Some(&merged[0]));
+
+    // Here we're going to create a function that looks roughly like:
+    // fn abs(value: i32) -> i32 {
+    //     if value < 0 { return -value; }
+    //     value
+    // }
+
+    let fn_type = i32_type.fn_type(&[i32_type.into()], false);
+    let function = module.add_function("abs", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+    let value = function.get_first_param().unwrap().into_int_value();
+
+    builder.position_at_end(entry);
+
+    let cmp = builder.build_int_compare(IntPredicate::SLT, value, i32_type.const_zero(), "cmp");
+    let merged = builder.build_if_then_else(cmp, |builder| {
+        let negated = builder.build_int_neg(value, "negated");
+
+        builder.build_return(Some(&negated));
+
+        vec![negated.into()]
+    }, |_| vec![value.into()], "abs").unwrap();
+
+    // Only the else branch falls through, so no phi is needed
+    assert_eq!(merged, vec![value.as_basic_value_enum()]);
+
+    builder.build_return(Some(&merged[0]));
+
+    assert!(module.verify().is_ok());
+
+    let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+
+    unsafe {
+        let max = execution_engine.get_function::<unsafe extern "C" fn(i32, i32) -> i32>("max").unwrap();
+
+        assert_eq!(max.call(1, 2), 2);
+        assert_eq!(max.call(5, -3), 5);
+
+        let abs = execution_engine.get_function::<unsafe extern "C" fn(i32) -> i32>("abs").unwrap();
+
+        assert_eq!(abs.call(-4), 4);
+        assert_eq!(abs.call(9), 9);
+    }
+}
+
+#[test]
+fn test_build_if_then_else_errors() {
+    let context = Context::create();
+    let module = context.create_module("if_then_else");
+    let builder = context.create_builder();
+    let bool_type = context.bool_type();
+    let i32_type = context.i32_type();
+    let i64_type = context.i64_type();
+    let cond = bool_type.const_int(1, false);
+
+    assert!(builder.build_if_then_else(cond, |_| vec![], |_| vec![], "if").is_err());
+
+    let fn_type = context.void_type().fn_type(&[], false);
+    let function = module.add_function("mismatch", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+
+    builder.position_at_end(entry);
+
+    let result = builder.build_if_then_else(cond, |_| vec![i32_type.const_zero().into()], |_| vec![], "count");
+
+    assert!(result.is_err());
+
+    let result = builder.build_if_then_else(cond, |_| vec![i32_type.const_zero().into()], |_| vec![i64_type.const_zero().into()], "type");
+
+    assert!(result.is_err());
+
+    // Blocks built by the branches themselves are removed along with the construct's own
+    let result = builder.build_if_then_else(cond, |builder| {
+        let slot = builder.build_alloca(i32_type, "slot");
+
+        builder.build_if_then_else(cond, |_| vec![slot.into()], |_| vec![slot.into()], "nested").unwrap()
+    }, |_| vec![i64_type.const_zero().into()], "nested_type");
+
+    assert!(result.is_err());
+    assert_eq!(function.count_basic_blocks(), 1);
+    assert!(entry.get_terminator().is_none());
+    assert_eq!(builder.get_insert_block(), Some(entry));
+
+    let ret = builder.build_return(None);
+
+    // Neither a terminated block nor a position before an existing instruction can be built upon
+    assert!(builder.build_if_then(cond, |_| {}, "terminated").is_err());
+
+    builder.position_before(&ret);
+
+    assert!(builder.build_loop(|_, _| {}, "before").is_err());
+    assert_eq!(function.count_basic_blocks(), 1);
+    assert_eq!(entry.get_first_instruction(), Some(ret));
+    assert!(function.verify(false));
+}
+
+#[test]
+fn test_build_loops() {
+    let context = Context::create();
+    let module = context.create_module("loops");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let fn_type = i32_type.fn_type(&[i32_type.into()], false);
+    let zero = i32_type.const_zero();
+    let one = i32_type.const_int(1, false);
+
+    // Here we're going to create a function that looks roughly like:
+    // fn sum(end: i32) -> i32 {
+    //     let mut sum = 0;
+    //     for i in 0..end {
+    //         if i == 5 { continue; }
+    //         sum += i;
+    //     }
+    //     sum
+    // }
+
+    let function = module.add_function("sum", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+    let end = function.get_first_param().unwrap().into_int_value();
+
+    builder.position_at_end(entry);
+
+    let sum = builder.build_alloca(i32_type, "sum");
+
+    builder.build_store(sum, zero);
+    builder.build_counted_loop(zero, end, one, |builder, index, blocks| {
+        let is_five = builder.build_int_compare(IntPredicate::EQ, index, i32_type.const_int(5, false), "is_five");
+
+        builder.build_if_then(is_five, |builder| {
+            builder.build_unconditional_branch(blocks.get_continue_block());
+        }, "skip").unwrap();
+
+        let current = builder.build_load(sum, "current").into_int_value();
+        let next = builder.build_int_add(current, index, "next");
+
+        builder.build_store(sum, next);
+    }, "for").unwrap();
+
+    let result = builder.build_load(sum, "result");
+
+    builder.build_return(Some(&result));
+
+    // Here we're going to create a function that looks roughly like:
+    // fn log2(mut value: i32) -> i32 {
+    //     let mut count = 0;
+    //     while value > 1 {
+    //         value /= 2;
+    //         count += 1;
+    //     }
+    //     count
+    // }
+
+    let function = module.add_function("log2", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+    let arg = function.get_first_param().unwrap();
+
+    builder.position_at_end(entry);
+
+    let value = builder.build_alloca(i32_type, "value");
+    let count = builder.build_alloca(i32_type, "count");
+
+    builder.build_store(value, arg);
+    builder.build_store(count, zero);
+    builder.build_while_loop(|builder| {
+        let current = builder.build_load(value, "current").into_int_value();
+
+        builder.build_int_compare(IntPredicate::SGT, current, one, "cmp")
+    }, |builder, _| {
+        let current = builder.build_load(value, "current").into_int_value();
+        let halved = builder.build_int_signed_div(current, i32_type.const_int(2, false), "halved");
+        let current_count = builder.build_load(count, "current_count").into_int_value();
+        let next_count = builder.build_int_add(current_count, one, "next_count");
+
+        builder.build_store(value, halved);
+        builder.build_store(count, next_count);
+    }, "while").unwrap();
+
+    let result = builder.build_load(count, "result");
+
+    builder.build_return(Some(&result));
+
+    // Here we're going to create a function that looks roughly like:
+    // fn first_multiple_of_seven(mut value: i32) -> i32 {
+    //     loop {
+    //         if value % 7 == 0 { break; }
+    //         value += 1;
+    //     }
+    //     value
+    // }
+
+    let function = module.add_function("first_multiple_of_seven", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+    let arg = function.get_first_param().unwrap();
+
+    builder.position_at_end(entry);
+
+    let value = builder.build_alloca(i32_type, "value");
+
+    builder.build_store(value, arg);
+    builder.build_loop(|builder, blocks| {
+        let current = builder.build_load(value, "current").into_int_value();
+        let rem = builder.build_int_signed_rem(current, i32_type.const_int(7, false), "rem");
+        let is_multiple = builder.build_int_compare(IntPredicate::EQ, rem, zero, "is_multiple");
+
+        builder.build_if_then(is_multiple, |builder| {
+            builder.build_unconditional_branch(blocks.get_break_block());
+        }, "check").unwrap();
+
+        let next = builder.build_int_add(current, one, "next");
+
+        builder.build_store(value, next);
+    }, "loop").unwrap();
+
+    let result = builder.build_load(value, "result");
+
+    builder.build_return(Some(&result));
+
+    assert!(module.verify().is_ok());
+
+    let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+
+    unsafe {
+        let sum = execution_engine.get_function::<unsafe extern "C" fn(i32) -> i32>("sum").unwrap();
+
+        assert_eq!(sum.call(0), 0);
+        assert_eq!(sum.call(5), 10);
+        assert_eq!(sum.call(7), 16);
+
+        let log2 = execution_engine.get_function::<unsafe extern "C" fn(i32) -> i32>("log2").unwrap();
+
+        assert_eq!(log2.call(1), 0);
+        assert_eq!(log2.call(8), 3);
+        assert_eq!(log2.call(100), 6);
+
+        let first_multiple_of_seven = execution_engine.get_function::<unsafe extern "C" fn(i32) -> i32>("first_multiple_of_seven").unwrap();
+
+        assert_eq!(first_multiple_of_seven.call(7), 7);
+        assert_eq!(first_multiple_of_seven.call(8), 14);
+    }
+}
@@ -0,0 +1,144 @@
+use inkwell::AddressSpace;
+use inkwell::call_graph::CallGraph;
+use inkwell::context::Context;
+use inkwell::module::Linkage;
+use inkwell::values::CallableValue;
+
+use std::convert::TryFrom;
+
+#[test]
+fn test_call_graph() {
+    let context = Context::create();
+    let module = context.create_module("call_graph");
+    let builder = context.create_builder();
+    let void_type = context.void_type();
+    let fn_type = void_type.fn_type(&[], false);
+    let fn_ptr_type = fn_type.ptr_type(AddressSpace::Generic);
+
+    let puts = module.add_function("puts", fn_type, None);
+    let even = module.add_function("even", fn_type, Some(Linkage::Internal));
+    let odd = module.add_function("odd", fn_type, Some(Linkage::Internal));
+    let unused = module.add_function("unused", fn_type, Some(Linkage::Internal));
+    let callback = module.add_function("callback", fn_type, Some(Linkage::Internal));
+    let main = module.add_function("main", fn_type, None);
+
+    let global = module.add_global(fn_ptr_type, None, "callback_ptr");
+
+    global.set_initializer(&callback.as_global_value().as_pointer_value());
+
+    for &function in &[unused, callback] {
+        builder.position_at_end(context.append_basic_block(function, "entry"));
+        builder.build_return(None);
+    }
+
+    // even and odd call each other
+    builder.position_at_end(context.append_basic_block(even, "entry"));
+    builder.build_call(odd, &[], "call");
+    builder.build_return(None);
+
+    builder.position_at_end(context.append_basic_block(odd, "entry"));
+    builder.build_call(even, &[], "call");
+    builder.build_call(puts, &[], "call");
+    builder.build_return(None);
+
+    builder.position_at_end(context.append_basic_block(main, "entry"));
+    builder.build_call(even, &[], "call");
+    builder.build_call(puts, &[], "call");
+    builder.build_call(puts, &[], "call");
+
+    let fn_ptr = builder.build_load(global.as_pointer_value(), "fn_ptr").into_pointer_value();
+
+    builder.build_call(CallableValue::try_from(fn_ptr).unwrap(), &[], "call");
+    builder.build_return(None);
+
+    assert!(module.verify().is_ok());
+
+    let call_graph = CallGraph::new(&module);
+
+    assert_eq!(call_graph.get_functions(), &[puts, even, odd, unused, callback, main]);
+    assert_eq!(call_graph.get_callees(main), vec![even, puts]);
+    assert_eq!(call_graph.get_callees(odd), vec![even, puts]);
+    assert!(call_graph.get_callees(callback).is_empty());
+    assert_eq!(call_graph.get_callers(puts), vec![odd, main]);
+    assert_eq!(call_graph.get_callers(even), vec![odd, main]);
+    assert!(call_graph.get_callers(main).is_empty());
+
+    assert!(call_graph.is_address_taken(callback));
+    assert!(!call_graph.is_address_taken(even));
+    assert!(!call_graph.is_address_taken(puts));
+    assert!(call_graph.has_indirect_calls(main));
+    assert!(!call_graph.has_indirect_calls(odd));
+
+    assert!(call_graph.is_recursive(even));
+    assert!(call_graph.is_recursive(odd));
+    assert!(!call_graph.is_recursive(main));
+
+    assert_eq!(call_graph.get_entry_points(), vec![callback, main]);
+    assert_eq!(call_graph.get_unreachable_functions(), vec![unused]);
+    assert_eq!(call_graph.get_sccs(), vec![vec![puts], vec![even, odd], vec![unused], vec![callback], vec![main]]);
+
+    let dot = call_graph.to_dot();
+
+    assert!(dot.starts_with("digraph \"Call graph for 'call_graph'\" {\n"));
+    assert!(dot.contains("    f0 [label=\"puts\", style=\"dashed\"];\n"));
+    assert!(dot.contains("    f4 [label=\"callback\", style=\"bold\"];\n"));
+    assert!(dot.contains("    f5 [label=\"main\"];\n"));
+    assert!(dot.contains("    f1 -> f2;\n"));
+    assert!(dot.contains("    f2 -> f1;\n"));
+    assert!(dot.contains("    f5 -> f0;\n"));
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn test_call_graph_of_other_module() {
+    let context = Context::create();
+    let module = context.create_module("call_graph");
+    let other_module = context.create_module("other");
+    let void_type = context.void_type();
+    let fn_type = void_type.fn_type(&[], false);
+    let function = other_module.add_function("function", fn_type, None);
+
+    let call_graph = CallGraph::new(&module);
+
+    assert!(call_graph.get_functions().is_empty());
+    assert!(call_graph.get_callees(function).is_empty());
+    assert!(call_graph.get_callers(function).is_empty());
+    assert!(!call_graph.is_recursive(function));
+    assert!(!call_graph.is_address_taken(function));
+    assert!(call_graph.get_sccs().is_empty());
+    assert_eq!(call_graph.to_dot(), "digraph \"Call graph for 'call_graph'\" {\n    node [shape=box];\n}\n");
+}
+
+#[test]
+fn test_call_graph_through_pointer_casts() {
+    let context = Context::create();
+    let module = context.create_module("call_graph");
+    let builder = context.create_builder();
+    let void_type = context.void_type();
+    let i32_type = context.i32_type();
+    let fn_type = void_type.fn_type(&[], false);
+    let cast_fn_ptr_type = void_type.fn_type(&[i32_type.into()], false).ptr_type(AddressSpace::Generic);
+
+    let called = module.add_function("called", fn_type, None);
+    let stored = module.add_function("stored", fn_type, None);
+    let main = module.add_function("main", fn_type, None);
+
+    // A cast of stored escapes through a global, whereas a cast of called is only ever called
+    let global = module.add_global(cast_fn_ptr_type, None, "stored_ptr");
+
+    global.set_initializer(&stored.as_global_value().as_pointer_value().const_cast(cast_fn_ptr_type));
+
+    builder.position_at_end(context.append_basic_block(main, "entry"));
+
+    let cast_callee = called.as_global_value().as_pointer_value().const_cast(cast_fn_ptr_type);
+
+    builder.build_call(CallableValue::try_from(cast_callee).unwrap(), &[i32_type.const_zero().into()], "call");
+    builder.build_return(None);
+
+    let call_graph = CallGraph::new(&module);
+
+    assert_eq!(call_graph.get_callees(main), vec![called]);
+    assert!(!call_graph.is_address_taken(called));
+    assert!(call_graph.is_address_taken(stored));
+    assert!(!call_graph.has_indirect_calls(main));
+}
@@ -0,0 +1,136 @@
+use inkwell::OptimizationLevel;
+use inkwell::data_layout::{Alignment, DataLayout, DataLayoutSpec, FunctionPointerAlignment, Mangling, PointerLayout};
+use inkwell::targets::{ByteOrdering, CodeModel, InitializationConfig, RelocMode, Target, TargetData, TargetTriple};
+
+fn assert_data_layout_round_trips(triple: &TargetTriple) {
+    let target = Target::from_triple(triple).unwrap();
+    let target_machine = target
+        .create_target_machine(triple, "", "", OptimizationLevel::Default, RelocMode::Default, CodeModel::Default)
+        .unwrap_or_else(|| panic!("{} has no target machine", triple));
+    let data_layout = target_machine.get_target_data().get_data_layout();
+    let data_layout = data_layout.as_str().to_str().unwrap();
+    let spec = DataLayoutSpec::parse(data_layout).unwrap();
+
+    assert_eq!(spec.to_string(), data_layout, "{} doesn't round trip", triple);
+    assert_eq!(spec.build().as_str().to_str(), Ok(data_layout));
+}
+
+#[test]
+fn test_data_layout_spec_round_trips_targets() {
+    Target::initialize_all(&InitializationConfig::default());
+
+    // Layouts which depend on the OS or environment, for those targets which are enabled
+    let triples = [
+        "x86_64-pc-linux-gnu",
+        "i686-pc-windows-msvc",
+        "x86_64-apple-macosx10.15",
+        "aarch64-unknown-linux-gnu",
+        "aarch64-apple-ios",
+        "armv7-unknown-linux-gnueabihf",
+        "thumbv7em-none-eabihf",
+        "mips-unknown-linux-gnu",
+        "mips64el-unknown-linux-gnuabi64",
+        "powerpc64le-unknown-linux-gnu",
+        "powerpc-unknown-linux-gnu",
+        "s390x-unknown-linux-gnu",
+        "sparcv9-unknown-linux-gnu",
+        "nvptx64-nvidia-cuda",
+        "amdgcn-amd-amdhsa",
+    ];
+
+    for triple in triples.iter() {
+        let triple = TargetTriple::create(triple);
+
+        if Target::from_triple(&triple).is_ok() {
+            assert_data_layout_round_trips(&triple);
+        }
+    }
+
+    // Every enabled target, each of whose names is an architecture apart from x86's
+    let mut target = Target::get_first();
+    let mut checked = 0;
+
+    while let Some(current) = target {
+        let arch = match current.get_name().to_str().unwrap() {
+            "x86-64" => "x86_64",
+            "x86" => "i686",
+            arch => arch,
+        };
+
+        assert_data_layout_round_trips(&TargetTriple::create(&format!("{}-unknown-unknown", arch)));
+
+        target = current.get_next();
+        checked += 1;
+    }
+
+    assert!(checked > 0);
+}
+
+#[test]
+fn test_data_layout_spec_getters() {
+    let data_layout = DataLayout::create("E-m:e-p:32:32-p3:16:16:16:8-Fi8-i64:64-v128:64:128-a:0:32-n8:16:32-S64-P1-A5-G3-ni:7:8");
+    let spec = data_layout.get_spec().unwrap();
+
+    assert_eq!(spec.get_byte_ordering(), ByteOrdering::BigEndian);
+    assert_eq!(spec.get_mangling(), Some(Mangling::Elf));
+    assert_eq!(spec.get_pointer_layout(0), PointerLayout::new(32, Alignment::new(32)));
+    // Address spaces without their own layout use address space 0's
+    assert_eq!(spec.get_pointer_layout(1).get_size(), 32);
+    assert_eq!(spec.get_pointer_layout(3).get_size(), 16);
+    assert_eq!(spec.get_pointer_layout(3).get_index_size(), 8);
+    assert_eq!(spec.get_function_pointer_alignment(), Some(FunctionPointerAlignment::Independent(8)));
+    assert_eq!(spec.get_integer_alignment(64), Some(Alignment::new(64)));
+    assert_eq!(spec.get_integer_alignment(32).unwrap().get_abi(), 32);
+    assert_eq!(spec.get_integer_alignment(128), None);
+    assert_eq!(spec.get_vector_alignment(128).unwrap().get_abi(), 64);
+    assert_eq!(spec.get_vector_alignment(128).unwrap().get_preferred(), 128);
+    assert_eq!(spec.get_float_alignment(64).unwrap().get_abi(), 64);
+    assert_eq!(spec.get_aggregate_alignment(), Alignment::new(0).with_preferred(32));
+    assert_eq!(spec.get_native_integer_widths(), &[8, 16, 32]);
+    assert_eq!(spec.get_stack_alignment(), Some(64));
+    assert_eq!(spec.get_program_address_space(), 1);
+    assert_eq!(spec.get_alloca_address_space(), 5);
+    assert_eq!(spec.get_globals_address_space(), 3);
+    assert_eq!(spec.get_non_integral_address_spaces(), &[7, 8]);
+
+    let defaults = DataLayoutSpec::new();
+
+    assert_eq!(defaults.get_byte_ordering(), ByteOrdering::LittleEndian);
+    assert_eq!(defaults.get_mangling(), None);
+    assert_eq!(defaults.get_pointer_layout(0), PointerLayout::new(64, Alignment::new(64)));
+    assert_eq!(defaults.get_integer_alignment(64), Some(Alignment::new(32).with_preferred(64)));
+    assert_eq!(defaults.get_stack_alignment(), None);
+    assert!(defaults.get_native_integer_widths().is_empty());
+    assert_eq!(defaults.to_string(), "");
+}
+
+#[test]
+fn test_data_layout_spec_builder() {
+    let spec = DataLayoutSpec::new()
+        .byte_ordering(ByteOrdering::LittleEndian)
+        .mangling(Mangling::Elf)
+        .pointer_layout(0, PointerLayout::new(32, Alignment::new(32)))
+        .integer_alignment(64, Alignment::new(64))
+        .native_integer_widths(&[32, 64])
+        .stack_alignment(128);
+
+    assert_eq!(spec.to_string(), "e-m:e-p:32:32-i64:64-n32:64-S128");
+
+    let target_data = TargetData::create(&spec.to_string());
+
+    assert_eq!(target_data.get_pointer_byte_size(None), 4);
+    assert_eq!(target_data.get_byte_ordering(), ByteOrdering::LittleEndian);
+    assert_eq!(spec.build(), target_data.get_data_layout());
+
+    // Existing specifications are replaced in place
+    let spec = spec
+        .pointer_layout(0, PointerLayout::new(64, Alignment::new(64)).with_index_size(32))
+        .byte_ordering(ByteOrdering::BigEndian)
+        .vector_alignment(128, Alignment::new(64).with_preferred(128));
+
+    assert_eq!(spec.to_string(), "E-m:e-p:64:64:64:32-i64:64-n32:64-S128-v128:64:128");
+    assert_eq!(spec.get_pointer_layout(0).get_index_size(), 32);
+    assert!(DataLayoutSpec::parse("e-q").is_err());
+    assert!(DataLayoutSpec::parse("e-p:x:32").is_err());
+    assert!(DataLayoutSpec::parse("e-m:z").is_err());
+}
@@ -3,6 +3,7 @@ use inkwell::debug_info::{
     AsDIScope, DIFlags, DIFlagsConstants, DISubprogram, DWARFEmissionKind, DWARFSourceLanguage,
 };
 use inkwell::module::FlagBehavior;
+use inkwell::AddressSpace;
 
 #[test]
 fn test_smoke() {
@@ -373,3 +374,51 @@ fn test_global_expressions() {
     // therefore, it's currently not possible to test that the data was set without generating the IR
     assert!(gv.print_to_string().to_string().contains("!dbg"), format!("expected !dbg but generated gv was {}",gv.print_to_string()));
 }
+
+#[test]
+fn test_pointer_and_array_types() {
+    let context = Context::create();
+    let module = context.create_module("bin");
+
+    let (dibuilder, _compile_unit) = module.create_debug_info_builder(
+        true,
+        DWARFSourceLanguage::C,
+        "source_file",
+        ".",
+        "my llvm compiler frontend",
+        false,
+        "",
+        0,
+        "",
+        DWARFEmissionKind::Full,
+        0,
+        false,
+        false,
+        #[cfg(any(feature = "llvm11-0", feature = "llvm12-0", feature = "llvm13-0"))]
+        "",
+        #[cfg(any(feature = "llvm11-0", feature = "llvm12-0", feature = "llvm13-0"))]
+        "",
+    );
+
+    let int_type = dibuilder
+        .create_basic_type(
+            "int",
+            32,
+            0x05,
+            #[cfg(not(feature = "llvm7-0"))]
+            DIFlags::PUBLIC,
+        )
+        .unwrap();
+    let matrix_type = dibuilder.create_array_type(int_type.as_type(), 32 * 12, 32, &[0..3, 0..4]);
+    let pointer_type = dibuilder.create_pointer_type("", Some(matrix_type.as_type()), 64, 64, AddressSpace::Generic);
+    let void_pointer_type = dibuilder.create_pointer_type("", None, 64, 64, AddressSpace::Generic);
+
+    assert_eq!(matrix_type.as_type().get_size_in_bits(), 384);
+    assert_eq!(matrix_type.as_type().get_align_in_bits(), 32);
+    assert_eq!(pointer_type.as_type().get_size_in_bits(), 64);
+    assert_eq!(void_pointer_type.as_type().get_align_in_bits(), 64);
+
+    dibuilder.finalize();
+
+    assert!(module.verify().is_ok());
+}
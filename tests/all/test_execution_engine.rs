@@ -1,8 +1,12 @@
 use inkwell::{AddressSpace, OptimizationLevel, IntPredicate};
 use inkwell::context::Context;
-use inkwell::execution_engine::FunctionLookupError;
-use inkwell::values::BasicValue;
-use inkwell::targets::{InitializationConfig, Target};
+use inkwell::execution_engine::{FunctionLookupError, MCJITOptions};
+use inkwell::module::Module;
+use inkwell::values::{BasicValue, FunctionValue, GenericValue};
+use inkwell::targets::{CodeModel, InitializationConfig, Target, TargetData};
+
+use std::cell::Cell;
+use std::rc::Rc;
 
 type Thunk = unsafe extern "C" fn();
 
@@ -106,6 +110,498 @@ fn test_jit_execution_engine() {
     assert_eq!(ret, 42, "unexpected main return code: {}", ret);
 }
 
+#[test]
+fn test_run_function_as_main_with_env() {
+    let context = Context::create();
+    let module = context.create_module("greeter");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let i64_type = context.i64_type();
+    let i8_ptr_type = context.i8_type().ptr_type(AddressSpace::Generic);
+    let i8_ptr_ptr_type = i8_ptr_type.ptr_type(AddressSpace::Generic);
+    let strlen = module.add_function("strlen", i64_type.fn_type(&[i8_ptr_type.into()], false), None);
+    let main_type = i32_type.fn_type(&[i32_type.into(), i8_ptr_ptr_type.into(), i8_ptr_ptr_type.into()], false);
+    let main = module.add_function("main", main_type, None);
+    let entry = context.append_basic_block(main, "entry");
+    let empty = context.append_basic_block(main, "empty");
+    let found = context.append_basic_block(main, "found");
+    let single = context.append_basic_block(main, "single");
+    let multiple = context.append_basic_block(main, "multiple");
+
+    // Returns the length of envp's only variable plus argc, 100 if envp is empty or 200 if it has more
+    builder.position_at_end(entry);
+
+    let argc = main.get_nth_param(0).unwrap().into_int_value();
+    let envp = main.get_nth_param(2).unwrap().into_pointer_value();
+    let first = builder.build_load(envp, "first").into_pointer_value();
+    let is_empty = builder.build_is_null(first, "is_empty");
+
+    builder.build_conditional_branch(is_empty, empty, found);
+    builder.position_at_end(empty);
+    builder.build_return(Some(&i32_type.const_int(100, false)));
+    builder.position_at_end(found);
+
+    let second = unsafe { builder.build_in_bounds_gep(envp, &[i64_type.const_int(1, false)], "second") };
+    let second = builder.build_load(second, "second").into_pointer_value();
+    let is_single = builder.build_is_null(second, "is_single");
+
+    builder.build_conditional_branch(is_single, single, multiple);
+    builder.position_at_end(multiple);
+    builder.build_return(Some(&i32_type.const_int(200, false)));
+    builder.position_at_end(single);
+
+    let len = builder.build_call(strlen, &[first.into()], "len")
+        .try_as_basic_value()
+        .left()
+        .unwrap()
+        .into_int_value();
+    let len = builder.build_int_truncate(len, i32_type, "len");
+    let code = builder.build_int_add(len, argc, "code");
+
+    builder.build_return(Some(&code));
+
+    assert!(module.verify().is_ok());
+
+    Target::initialize_native(&InitializationConfig::default()).expect("Failed to initialize native target");
+
+    let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).expect("Could not create Execution Engine");
+
+    // "GREETING=hello" and one argument
+    let code = unsafe {
+        execution_engine.run_function_as_main_with_env(main, &["greeter"], &[("GREETING", "hello")])
+    };
+
+    assert_eq!(code, 15);
+
+    let code = unsafe {
+        execution_engine.run_function_as_main_with_env(main, &["greeter", "loudly"], &[("GREETING", "hi")])
+    };
+
+    assert_eq!(code, 13);
+
+    let code = unsafe {
+        execution_engine.run_function_as_main_with_env(main, &["greeter"], &[])
+    };
+
+    assert_eq!(code, 100);
+
+    let code = unsafe {
+        execution_engine.run_function_as_main_with_env(main, &["greeter"], &[("LANG", "C"), ("GREETING", "hi")])
+    };
+
+    assert_eq!(code, 200);
+}
+
+#[test]
+fn test_add_closure() {
+    let context = Context::create();
+    let module = context.create_module("counter");
+    let builder = context.create_builder();
+    let i64_type = context.i64_type();
+    let fn_type = i64_type.fn_type(&[i64_type.into()], false);
+    let bump = module.add_function("bump", fn_type, None);
+    let unused = module.add_function("unused", context.void_type().fn_type(&[], false), None);
+    let twice = module.add_function("twice", fn_type, None);
+    let entry = context.append_basic_block(twice, "entry");
+
+    // Calls bump twice, returning the second result
+    builder.position_at_end(entry);
+
+    let by = twice.get_first_param().unwrap();
+
+    builder.build_call(bump, &[by.into()], "first");
+
+    let second = builder.build_call(bump, &[by.into()], "second")
+        .try_as_basic_value()
+        .left()
+        .unwrap();
+
+    builder.build_return(Some(&second));
+
+    Target::initialize_native(&InitializationConfig::default()).expect("Failed to initialize native target");
+
+    let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+    let calls = Rc::new(Cell::new(0));
+    let bump_calls = calls.clone();
+    let mut total = 0;
+
+    assert_eq!(execution_engine.add_closure(twice, |_: u64| 0u64), Err("Function already has a body"));
+    assert_eq!(execution_engine.add_closure(bump, |a: u64, b: u64| a + b), Err("Closure and function take different numbers of arguments"));
+    assert_eq!(execution_engine.add_closure(bump, |by: f64| by as u64), Err("Closure and function have different argument or return types"));
+    assert_eq!(execution_engine.add_closure(bump, |by: u32| by as u64), Err("Closure and function have different argument or return types"));
+    assert_eq!(execution_engine.add_closure(bump, |_: u64| ()), Err("Closure and function have different argument or return types"));
+    assert_eq!(execution_engine.add_closure(bump, |by: *const u8| by as u64), Err("Closure and function have different argument or return types"));
+
+    execution_engine.add_closure(bump, move |by: u64| {
+        bump_calls.set(bump_calls.get() + 1);
+        total += by;
+        total
+    }).unwrap();
+    execution_engine.add_closure(unused, || ()).unwrap();
+
+    assert!(module.verify().is_ok());
+
+    let twice = unsafe {
+        execution_engine.get_function::<unsafe extern "C" fn(u64) -> u64>("twice").unwrap()
+    };
+
+    unsafe {
+        assert_eq!(twice.call(3), 6);
+        assert_eq!(twice.call(1), 8);
+    }
+
+    assert_eq!(calls.get(), 4);
+
+    // The closure is kept alive by the JitFunction until it too is dropped. The module holds
+    // a reference to the ExecutionEngine as well
+    drop(execution_engine);
+    drop(module);
+
+    assert_eq!(Rc::strong_count(&calls), 2);
+
+    drop(twice);
+
+    assert_eq!(Rc::strong_count(&calls), 1);
+}
+
+#[test]
+fn test_mcjit_options() {
+    let context = Context::create();
+    let module = context.create_module("far_away");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let function = module.add_function("triple", i32_type.fn_type(&[i32_type.into()], false), None);
+    let entry = context.append_basic_block(function, "entry");
+
+    builder.position_at_end(entry);
+
+    let value = function.get_first_param().unwrap().into_int_value();
+    let tripled = builder.build_int_mul(value, i32_type.const_int(3, false), "tripled");
+
+    builder.build_return(Some(&tripled));
+
+    let options = MCJITOptions::new()
+        .opt_level(OptimizationLevel::Aggressive)
+        .code_model(CodeModel::Large)
+        .no_frame_pointer_elim(true)
+        .enable_fast_isel(true);
+    let execution_engine = module.create_mcjit_execution_engine(options).unwrap();
+
+    assert!(module.create_mcjit_execution_engine(MCJITOptions::default()).is_err());
+
+    unsafe {
+        let triple = execution_engine.get_function::<unsafe extern "C" fn(u32) -> u32>("triple").unwrap();
+
+        assert_eq!(triple.call(14), 42);
+    }
+}
+
+#[llvm_versions(8.0..=latest)]
+#[test]
+fn test_jit_event_listeners() {
+    use inkwell::execution_engine::JitEventListener;
+
+    let gdb = JitEventListener::create_gdb_registration_listener();
+
+    // Listeners are singletons
+    assert_eq!(gdb, JitEventListener::create_gdb_registration_listener());
+    assert_eq!(JitEventListener::create_perf_listener(), JitEventListener::create_perf_listener());
+}
+
+#[cfg(feature = "experimental")]
+#[llvm_versions(8.0..=11.0)]
+#[test]
+fn test_orc_jit_event_listeners() {
+    use inkwell::execution_engine::JitEventListener;
+    use inkwell::execution_engine::experimental::Orc;
+    use inkwell::targets::{FileType, RelocMode, TargetMachine};
+
+    let context = Context::create();
+    let module = context.create_module("profiled");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let function = module.add_function("inkwell_profiled_function", i32_type.fn_type(&[], false), None);
+
+    builder.position_at_end(context.append_basic_block(function, "entry"));
+    builder.build_return(Some(&i32_type.const_int(5, false)));
+
+    Target::initialize_native(&InitializationConfig::default()).expect("Failed to initialize native target");
+
+    let triple = TargetMachine::get_default_triple();
+    let target = Target::from_triple(&triple).unwrap();
+    let create_target_machine = || target.create_target_machine(&triple, "", "", OptimizationLevel::None, RelocMode::Default, CodeModel::JITDefault).unwrap();
+    let object = create_target_machine().write_to_memory_buffer(&module, FileType::Object).unwrap();
+    let orc = Orc::create(create_target_machine());
+    let gdb = JitEventListener::create_gdb_registration_listener();
+
+    orc.register_jit_event_listener(gdb);
+
+    // Whether perf is supported depends on how LLVM was built. If so, it writes a jitdump file naming the function
+    let jitdump_dir = std::env::temp_dir().join(format!("inkwell_jitdump_{}", std::process::id()));
+
+    // perf's listener picks its directory when first created
+    std::env::set_var("JITDUMPDIR", &jitdump_dir);
+
+    let perf = JitEventListener::create_perf_listener();
+
+    if let Some(perf) = perf {
+        orc.register_jit_event_listener(perf);
+    }
+
+    orc.add_object_file(object).unwrap();
+
+    let function: unsafe extern "C" fn() -> u32 = unsafe { std::mem::transmute(orc.get_symbol_address("inkwell_profiled_function").unwrap()) };
+
+    assert_eq!(unsafe { function() }, 5);
+
+    orc.unregister_jit_event_listener(gdb);
+
+    fn find_jitdumps(dir: &std::path::Path, jitdumps: &mut Vec<Vec<u8>>) {
+        for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                find_jitdumps(&path, jitdumps);
+            } else if path.extension().map_or(false, |extension| extension == "dump") {
+                jitdumps.push(std::fs::read(path).unwrap());
+            }
+        }
+    }
+
+    if perf.is_some() {
+        let mut jitdumps = Vec::new();
+
+        find_jitdumps(&jitdump_dir, &mut jitdumps);
+
+        let name = b"inkwell_profiled_function";
+
+        assert!(jitdumps.iter().any(|jitdump| jitdump.windows(name.len()).any(|window| window == name)));
+
+        std::fs::remove_dir_all(jitdump_dir).unwrap();
+    }
+}
+
+#[test]
+fn test_add_closure_requires_jit() {
+    let context = Context::create();
+    let module = context.create_module("interpreted");
+    let callback = module.add_function("callback", context.void_type().fn_type(&[], false), None);
+    let execution_engine = module.create_interpreter_execution_engine().unwrap();
+
+    assert_eq!(execution_engine.add_closure(callback, || ()), Err("Closures can only be called from JIT ExecutionEngines"));
+}
+
+#[test]
+fn test_indirect_stub() {
+    fn define_add<'ctx>(context: &'ctx Context, name: &str, addend: u64) -> (Module<'ctx>, FunctionValue<'ctx>) {
+        let module = context.create_module(name);
+        let builder = context.create_builder();
+        let i64_type = context.i64_type();
+        let function = module.add_function("add", i64_type.fn_type(&[i64_type.into()], false), None);
+
+        builder.position_at_end(context.append_basic_block(function, "entry"));
+
+        let sum = builder.build_int_add(function.get_first_param().unwrap().into_int_value(), i64_type.const_int(addend, false), "sum");
+
+        builder.build_return(Some(&sum));
+
+        (module, function)
+    }
+
+    Target::initialize_native(&InitializationConfig::default()).expect("Failed to initialize native target");
+
+    let context = Context::create();
+    let i64_type = context.i64_type();
+    let fn_type = i64_type.fn_type(&[i64_type.into()], false);
+
+    // Calls the stub, which is resolved by name once both modules are loaded
+    let caller_module = context.create_module("caller");
+    let builder = context.create_builder();
+    let add = caller_module.add_function("add", fn_type, None);
+    let call_add = caller_module.add_function("call_add", fn_type, None);
+
+    builder.position_at_end(context.append_basic_block(call_add, "entry"));
+
+    let sum = builder.build_call(add, &[call_add.get_first_param().unwrap().into()], "sum")
+        .try_as_basic_value()
+        .left()
+        .unwrap();
+
+    builder.build_return(Some(&sum));
+
+    let execution_engine = caller_module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+    let (module, function) = define_add(&context, "add_one", 1);
+    let mut stub = execution_engine.add_indirect_stub("add", module, function).unwrap();
+
+    assert_eq!(stub.get_name(), "add");
+    assert_eq!(stub.get_module().get_name().to_str(), Ok("add_one"));
+
+    let call_add = unsafe {
+        execution_engine.get_function::<unsafe extern "C" fn(u64) -> u64>("call_add").unwrap()
+    };
+    let stub_fn: unsafe extern "C" fn(u64) -> u64 = unsafe { std::mem::transmute(stub.get_address()) };
+    let first_target = stub.get_target_address();
+
+    unsafe {
+        assert_eq!(call_add.call(1), 2);
+        assert_eq!(stub_fn(1), 2);
+    }
+
+    let (module, function) = define_add(&context, "add_ten", 10);
+
+    execution_engine.redefine_indirect_stub(&mut stub, module, function).unwrap();
+
+    assert_ne!(stub.get_target_address(), first_target);
+    assert_eq!(stub.get_module().get_name().to_str(), Ok("add_ten"));
+
+    // Existing pointers to the stub now reach the new definition
+    unsafe {
+        assert_eq!(call_add.call(1), 11);
+        assert_eq!(stub_fn(1), 11);
+    }
+
+    let void_module = context.create_module("void");
+    let void_function = void_module.add_function("void", context.void_type().fn_type(&[], false), None);
+
+    builder.position_at_end(context.append_basic_block(void_function, "entry"));
+    builder.build_return(None);
+
+    assert_eq!(execution_engine.redefine_indirect_stub(&mut stub, void_module, void_function), Err("Function type does not match the indirect stub's"));
+
+    let (module, _) = define_add(&context, "add_two", 2);
+    let (_other_module, function) = define_add(&context, "add_three", 3);
+
+    assert_eq!(execution_engine.redefine_indirect_stub(&mut stub, module, function), Err("Function is not defined in module"));
+}
+
+#[test]
+fn test_indirect_stub_requires_jit() {
+    let context = Context::create();
+    let module = context.create_module("interpreted");
+    let function = module.add_function("stubbed", context.void_type().fn_type(&[], false), None);
+    let builder = context.create_builder();
+
+    builder.position_at_end(context.append_basic_block(function, "entry"));
+    builder.build_return(None);
+
+    let main_module = context.create_module("main");
+    let execution_engine = main_module.create_interpreter_execution_engine().unwrap();
+
+    assert_eq!(execution_engine.add_indirect_stub("stubbed", module, function).err(), Some("Indirect stubs can only be used with JIT ExecutionEngines"));
+}
+
+#[cfg(feature = "experimental")]
+#[llvm_versions(7.0..=11.0)]
+#[test]
+fn test_orc_add_object_files() {
+    use inkwell::execution_engine::experimental::{LoadObjectError, Orc};
+    use inkwell::memory_buffer::MemoryBuffer;
+    use inkwell::module::Linkage;
+    use inkwell::targets::{FileType, RelocMode, TargetMachine};
+
+    use crate::test_object_file::push_archive_member;
+
+    Target::initialize_native(&InitializationConfig::default()).expect("Failed to initialize native target");
+
+    let triple = TargetMachine::get_default_triple();
+    let target = Target::from_triple(&triple).unwrap();
+    let create_target_machine = || target.create_target_machine(&triple, "", "", OptimizationLevel::None, RelocMode::Default, CodeModel::JITDefault).unwrap();
+    let target_machine = create_target_machine();
+    let context = Context::create();
+    let builder = context.create_builder();
+    let i64_type = context.i64_type();
+    let fn_type = i64_type.fn_type(&[i64_type.into()], false);
+
+    // runtime.o defines double, which user.o calls
+    let runtime = context.create_module("runtime");
+    let double = runtime.add_function("double", fn_type, None);
+
+    builder.position_at_end(context.append_basic_block(double, "entry"));
+
+    let x = double.get_first_param().unwrap().into_int_value();
+
+    builder.build_return(Some(&builder.build_int_add(x, x, "doubled")));
+
+    let user = context.create_module("user");
+    let double = user.add_function("double", fn_type, None);
+    let quadruple = user.add_function("quadruple", fn_type, None);
+
+    builder.position_at_end(context.append_basic_block(quadruple, "entry"));
+
+    let x = quadruple.get_first_param().unwrap();
+    let doubled = builder.build_call(double, &[x.into()], "doubled").try_as_basic_value().left().unwrap();
+    let quadrupled = builder.build_call(double, &[doubled.into()], "quadrupled").try_as_basic_value().left().unwrap();
+
+    builder.build_return(Some(&quadrupled));
+
+    let runtime_object = target_machine.write_to_memory_buffer(&runtime, FileType::Object).unwrap();
+    let user_object = target_machine.write_to_memory_buffer(&user, FileType::Object).unwrap();
+
+    // user.o can't be loaded before its dependency
+    let orc = Orc::create(create_target_machine());
+
+    match orc.add_object_file(MemoryBuffer::create_from_memory_range_copy(user_object.as_slice(), "user.o")) {
+        Err(LoadObjectError::Llvm(err)) => assert!(err.to_string().contains("double")),
+        result => panic!("Expected unresolved symbols, found {:?}", result),
+    }
+
+    assert!(orc.get_symbol_address("quadruple").is_err());
+
+    let mut archive = b"!<arch>\n".to_vec();
+
+    push_archive_member(&mut archive, "runtime.o/", runtime_object.as_slice());
+    push_archive_member(&mut archive, "user.o/", user_object.as_slice());
+
+    let handles = orc.add_archive(&MemoryBuffer::create_from_memory_range_copy(&archive, "libruntime.a")).unwrap();
+
+    assert_eq!(handles.len(), 2);
+
+    let quadruple: unsafe extern "C" fn(u64) -> u64 = unsafe { std::mem::transmute(orc.get_symbol_address("quadruple").unwrap()) };
+
+    assert_eq!(unsafe { quadruple(3) }, 12);
+
+    // Static functions of the same name don't clash, within a batch or with those already loaded
+    let fresh_orc = Orc::create(create_target_machine());
+    let add_static_helper_object = |name: &str, value: u64| {
+        let module = context.create_module(name);
+        let helper = module.add_function("helper", fn_type, Some(Linkage::Internal));
+
+        builder.position_at_end(context.append_basic_block(helper, "entry"));
+        builder.build_return(Some(&i64_type.const_int(value, false)));
+
+        let function = module.add_function(name, fn_type, None);
+
+        builder.position_at_end(context.append_basic_block(function, "entry"));
+
+        let x = function.get_first_param().unwrap();
+        let value = builder.build_call(helper, &[x.into()], "value").try_as_basic_value().left().unwrap();
+
+        builder.build_return(Some(&value));
+
+        target_machine.write_to_memory_buffer(&module, FileType::Object).unwrap()
+    };
+    let mut static_archive = b"!<arch>\n".to_vec();
+
+    push_archive_member(&mut static_archive, "one.o/", add_static_helper_object("one", 1).as_slice());
+    push_archive_member(&mut static_archive, "two.o/", add_static_helper_object("two", 2).as_slice());
+
+    assert_eq!(fresh_orc.add_archive(&MemoryBuffer::create_from_memory_range_copy(&static_archive, "libstatic.a")).unwrap().len(), 2);
+    assert!(fresh_orc.add_object_file(add_static_helper_object("three", 3)).is_ok());
+
+    let two: unsafe extern "C" fn(u64) -> u64 = unsafe { std::mem::transmute(fresh_orc.get_symbol_address("two").unwrap()) };
+
+    assert_eq!(unsafe { two(0) }, 2);
+
+    // A batch is loaded all or nothing, so four.o is unloaded again along with user.o, which can't be linked
+    let user_object = MemoryBuffer::create_from_memory_range_copy(user_object.as_slice(), "user.o");
+
+    assert!(fresh_orc.add_object_files(vec![user_object, add_static_helper_object("four", 4)]).is_err());
+    assert!(fresh_orc.get_symbol_address("four").is_err());
+
+    assert_eq!(orc.add_archive(&MemoryBuffer::create_from_memory_range_copy(b"!<arch>\nnonsense", "bad.a")), Err(LoadObjectError::InvalidObject));
+    assert_eq!(orc.add_object_file(MemoryBuffer::create_from_memory_range_copy(b"nonsense", "bad.o")), Err(LoadObjectError::InvalidObject));
+}
+
 // #[test]
 // fn test_execution_engine_empty_module() {
 //     let context = Context::create();
@@ -131,6 +627,163 @@ fn test_interpreter_execution_engine() {
     assert!(module.create_interpreter_execution_engine().is_ok());
 }
 
+#[test]
+fn test_interpreter_aggregate_generic_values() {
+    let context = Context::create();
+    let module = context.create_module("aggregates");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let i64_type = context.i64_type();
+    let f64_type = context.f64_type();
+    let pair_type = context.struct_type(&[f64_type.into(), i32_type.into()], false);
+    let vector_type = i32_type.vec_type(2);
+    let array_type = i64_type.array_type(2);
+
+    // { double, i32 } @make_pair(i32 %a, double %b)
+    let fn_type = pair_type.fn_type(&[i32_type.into(), f64_type.into()], false);
+    let make_pair = module.add_function("make_pair", fn_type, None);
+    let entry = context.append_basic_block(make_pair, "entry");
+
+    builder.position_at_end(entry);
+
+    let a = make_pair.get_first_param().unwrap();
+    let b = make_pair.get_last_param().unwrap();
+    let pair = builder.build_insert_value(pair_type.get_undef(), b, 0, "pair").unwrap();
+    let pair = builder.build_insert_value(pair, a, 1, "pair").unwrap();
+
+    builder.build_return(Some(&pair));
+
+    // i64 @sum(<2 x i32> %v, [2 x i64] %a)
+    let fn_type = i64_type.fn_type(&[vector_type.into(), array_type.into()], false);
+    let sum = module.add_function("sum", fn_type, None);
+    let entry = context.append_basic_block(sum, "entry");
+
+    builder.position_at_end(entry);
+
+    let vector = sum.get_first_param().unwrap().into_vector_value();
+    let array = sum.get_last_param().unwrap().into_array_value();
+    let mut total = i64_type.const_zero();
+
+    for index in 0..2 {
+        let element = builder.build_extract_element(vector, i32_type.const_int(index, false), "element").into_int_value();
+        let element = builder.build_int_s_extend(element, i64_type, "element");
+        let item = builder.build_extract_value(array, index as u32, "item").unwrap().into_int_value();
+
+        total = builder.build_int_add(total, element, "total");
+        total = builder.build_int_add(total, item, "total");
+    }
+
+    builder.build_return(Some(&total));
+
+    assert!(module.verify().is_ok());
+
+    let execution_engine = module.create_interpreter_execution_engine().unwrap();
+
+    let a = i32_type.create_generic_value(-7i32 as u64, true);
+    let b = f64_type.create_generic_value(2.5);
+    let pair = unsafe { execution_engine.run_function(make_pair, &[&a, &b]) };
+
+    assert!(pair.is_aggregate());
+
+    let mut elements = pair.into_elements().into_iter();
+
+    assert_eq!(elements.next().unwrap().as_float(&f64_type), 2.5);
+    assert_eq!(elements.next().unwrap().as_int(true) as i64, -7);
+    assert!(elements.next().is_none());
+
+    let vector = vector_type.create_generic_value(vec![
+        i32_type.create_generic_value(-1i32 as u64, true),
+        i32_type.create_generic_value(2, false),
+    ]);
+    let array = array_type.create_generic_value(vec![
+        i64_type.create_generic_value(10, false),
+        i64_type.create_generic_value(20, false),
+    ]);
+    let total = unsafe { execution_engine.run_function(sum, &[&vector, &array]) };
+
+    assert!(!total.is_aggregate());
+    assert_eq!(total.as_int(true), 31);
+
+    // The temporary thunks should have been cleaned up
+    assert!(module.get_function("make_pair.inkwell_thunk").is_none());
+    assert!(module.get_function("sum.inkwell_thunk").is_none());
+    assert_eq!(unsafe { execution_engine.try_run_function(sum, &[&vector]) }.unwrap_err(), "Incorrect number of arguments");
+}
+
+#[test]
+fn test_jit_aggregate_generic_values() {
+    Target::initialize_native(&InitializationConfig::default()).expect("Failed to initialize native target");
+
+    let context = Context::create();
+    let module = context.create_module("aggregates");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let vector_type = i32_type.vec_type(2);
+
+    let fn_type = i32_type.fn_type(&[vector_type.into()], false);
+    let first = module.add_function("first", fn_type, None);
+    let entry = context.append_basic_block(first, "entry");
+
+    builder.position_at_end(entry);
+
+    let vector = first.get_first_param().unwrap().into_vector_value();
+    let element = builder.build_extract_element(vector, i32_type.const_zero(), "element");
+
+    builder.build_return(Some(&element));
+
+    let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+    let vector = vector_type.create_generic_value(vec![
+        i32_type.create_generic_value(1, false),
+        i32_type.create_generic_value(2, false),
+    ]);
+
+    assert!(unsafe { execution_engine.try_run_function(first, &[&vector]) }.is_err());
+}
+
+#[test]
+fn test_generic_value_bytes_round_trip() {
+    let context = Context::create();
+    let i8_type = context.i8_type();
+    let i16_type = context.i16_type();
+    let f32_type = context.f32_type();
+    let inner_type = context.struct_type(&[i8_type.into(), f32_type.into()], false);
+    let array_type = inner_type.array_type(2);
+    let outer_type = context.struct_type(&[i16_type.into(), array_type.into()], false);
+
+    let create_inner = |byte: u64, float: f64| inner_type.create_generic_value(vec![
+        i8_type.create_generic_value(byte, false),
+        f32_type.create_generic_value(float),
+    ]);
+    let value = outer_type.create_generic_value(vec![
+        i16_type.create_generic_value(0x0102, false),
+        array_type.create_generic_value(vec![create_inner(3, 1.5), create_inner(4, -0.25)]),
+    ]);
+
+    for &(data_layout, ref expected) in &[
+        ("e-f32:32", [2u8, 1, 0, 0, 3, 0, 0, 0, 0, 0, 0xc0, 0x3f, 4, 0, 0, 0, 0, 0, 0x80, 0xbe]),
+        ("E-f32:32", [1u8, 2, 0, 0, 3, 0, 0, 0, 0x3f, 0xc0, 0, 0, 4, 0, 0, 0, 0xbe, 0x80, 0, 0]),
+    ] {
+        let target_data = TargetData::create(data_layout);
+        let bytes = value.to_bytes(outer_type, &target_data);
+
+        assert_eq!(bytes, expected);
+
+        let decoded = GenericValue::from_bytes(outer_type, &bytes, &target_data);
+
+        assert_eq!(decoded.to_bytes(outer_type, &target_data), bytes);
+
+        let mut fields = decoded.into_elements().into_iter();
+
+        assert_eq!(fields.next().unwrap().as_int(false), 0x0102);
+
+        let items = fields.next().unwrap().into_elements();
+        let last = items.into_iter().last().unwrap().into_elements();
+
+        assert_eq!(last.len(), 2);
+        assert_eq!(last.into_iter().last().unwrap().as_float(&f32_type), -0.25);
+    }
+}
+
 
 #[test]
 fn test_add_remove_module() {
@@ -0,0 +1,104 @@
+use inkwell::OptimizationLevel;
+use inkwell::context::Context;
+use inkwell::gpu::{Dimension, GpuTarget, GLOBAL_ADDRESS_SPACE, SHARED_ADDRESS_SPACE};
+use inkwell::module::{Linkage, Module};
+use inkwell::targets::{CodeModel, FileType, RelocMode, Target, TargetTriple};
+use inkwell::values::FunctionValue;
+
+const BLOCK_SIZE: u32 = 256;
+
+// Writes each thread's index through shared memory to its slot of a global buffer
+fn add_kernel<'ctx>(context: &'ctx Context, module: &Module<'ctx>, gpu_target: GpuTarget) -> FunctionValue<'ctx> {
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let fn_type = context.void_type().fn_type(&[i32_type.ptr_type(GLOBAL_ADDRESS_SPACE).into()], false);
+    let kernel = module.add_function("write_thread_ids", fn_type, None);
+    let tile_type = i32_type.array_type(BLOCK_SIZE);
+    let tile = module.add_global(tile_type, Some(SHARED_ADDRESS_SPACE), "tile");
+
+    tile.set_linkage(Linkage::Internal);
+    tile.set_initializer(&tile_type.get_undef());
+    gpu_target.mark_kernel(module, kernel);
+    builder.position_at_end(context.append_basic_block(kernel, "entry"));
+
+    let thread_index = gpu_target.build_thread_index(context, module, &builder, Dimension::X);
+    let block_index = gpu_target.build_block_index(context, module, &builder, Dimension::X);
+    let zero = i32_type.const_zero();
+    let slot = unsafe { builder.build_gep(tile.as_pointer_value(), &[zero, thread_index], "slot") };
+
+    builder.build_store(slot, thread_index);
+
+    let value = builder.build_load(slot, "value");
+    let block_offset = builder.build_int_mul(block_index, i32_type.const_int(BLOCK_SIZE as u64, false), "block_offset");
+    let index = builder.build_int_add(block_offset, thread_index, "index");
+    let out = kernel.get_first_param().unwrap().into_pointer_value();
+    let element = unsafe { builder.build_gep(out, &[index], "element") };
+
+    builder.build_store(element, value);
+    builder.build_return(None);
+
+    assert!(module.verify().is_ok());
+
+    kernel
+}
+
+fn write_assembly(module: &Module, triple: &str, cpu: &str) -> String {
+    let triple = TargetTriple::create(triple);
+    let target = Target::from_triple(&triple).unwrap();
+    let target_machine = target
+        .create_target_machine(&triple, cpu, "", OptimizationLevel::Default, RelocMode::Default, CodeModel::Default)
+        .unwrap();
+    let assembly = target_machine.write_to_memory_buffer(module, FileType::Assembly).unwrap();
+
+    String::from_utf8(assembly.as_slice().to_vec()).unwrap()
+}
+
+#[cfg(feature = "target-nvptx")]
+#[test]
+fn test_nvptx_kernel() {
+    use inkwell::targets::InitializationConfig;
+
+    Target::initialize_nvptx(&InitializationConfig::default());
+
+    let context = Context::create();
+    let module = context.create_module("test_nvptx_kernel");
+
+    add_kernel(&context, &module, GpuTarget::Nvptx);
+
+    assert_eq!(module.get_global_metadata_size("nvvm.annotations"), 1);
+
+    let assembly = write_assembly(&module, "nvptx64-nvidia-cuda", "sm_50");
+
+    assert!(assembly.contains(".entry write_thread_ids"));
+    assert!(assembly.contains("%tid.x"));
+    assert!(assembly.contains("%ctaid.x"));
+    assert!(assembly.contains(".shared"));
+    assert!(assembly.contains("st.shared"));
+    assert!(assembly.contains("st.global"));
+}
+
+// Earlier versions default to code object v2, whose metadata is spelled differently
+#[cfg(feature = "target-amdgpu")]
+#[llvm_versions(11.0..=latest)]
+#[test]
+fn test_amdgpu_kernel() {
+    use inkwell::gpu::set_amdgpu_flat_work_group_size;
+    use inkwell::targets::InitializationConfig;
+
+    Target::initialize_amd_gpu(&InitializationConfig::default());
+
+    let context = Context::create();
+    let module = context.create_module("test_amdgpu_kernel");
+    let kernel = add_kernel(&context, &module, GpuTarget::AmdGpu);
+
+    set_amdgpu_flat_work_group_size(kernel, 1, BLOCK_SIZE);
+
+    assert_eq!(kernel.get_call_conventions(), 91);
+
+    let assembly = write_assembly(&module, "amdgcn-amd-amdhsa", "gfx900");
+
+    assert!(assembly.contains(".amdhsa_kernel write_thread_ids"));
+    assert!(assembly.contains("ds_write"));
+    assert!(assembly.contains("global_store_dword"));
+    assert!(assembly.contains(".max_flat_workgroup_size: 256"));
+}
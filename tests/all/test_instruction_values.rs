@@ -1,5 +1,6 @@
+use inkwell::basic_block::BasicBlock;
 use inkwell::context::Context;
-use inkwell::values::{BasicValue, InstructionOpcode::*};
+use inkwell::values::{BasicValue, FunctionValue, InstructionOpcode::*};
 use inkwell::{AddressSpace, AtomicOrdering, AtomicRMWBinOp, FloatPredicate, IntPredicate};
 
 #[test]
@@ -499,3 +500,118 @@ fn test_metadata_kinds() {
         md_string.into(),
     ]);
 }
+
+#[test]
+fn test_inst_visitor() {
+    use inkwell::values::{AnyValue, DeferredEdits, InstVisitor, InstructionValue};
+
+    #[derive(Default)]
+    struct Counter {
+        functions: usize,
+        basic_blocks: usize,
+        binary_operators: usize,
+        subs: usize,
+        cmps: usize,
+        terminators: usize,
+        others: usize,
+    }
+
+    impl<'ctx> InstVisitor<'ctx> for Counter {
+        fn visit_function(&mut self, _function: FunctionValue<'ctx>) {
+            self.functions += 1;
+        }
+
+        fn visit_basic_block(&mut self, _basic_block: BasicBlock<'ctx>) {
+            self.basic_blocks += 1;
+        }
+
+        fn visit_instruction(&mut self, _instruction: InstructionValue<'ctx>, _edits: &mut DeferredEdits<'ctx>) {
+            self.others += 1;
+        }
+
+        fn visit_binary_operator(&mut self, _instruction: InstructionValue<'ctx>, _edits: &mut DeferredEdits<'ctx>) {
+            self.binary_operators += 1;
+        }
+
+        fn visit_sub(&mut self, _instruction: InstructionValue<'ctx>, _edits: &mut DeferredEdits<'ctx>) {
+            self.subs += 1;
+        }
+
+        fn visit_cmp(&mut self, _instruction: InstructionValue<'ctx>, _edits: &mut DeferredEdits<'ctx>) {
+            self.cmps += 1;
+        }
+
+        fn visit_terminator(&mut self, _instruction: InstructionValue<'ctx>, _edits: &mut DeferredEdits<'ctx>) {
+            self.terminators += 1;
+        }
+    }
+
+    struct AddZeroFolder;
+
+    impl<'ctx> InstVisitor<'ctx> for AddZeroFolder {
+        fn visit_add(&mut self, instruction: InstructionValue<'ctx>, edits: &mut DeferredEdits<'ctx>) {
+            let lhs = instruction.get_operand(0).unwrap().left().unwrap().into_int_value();
+            let rhs = instruction.get_operand(1).unwrap().left().unwrap().into_int_value();
+
+            if rhs.get_zero_extended_constant() == Some(0) {
+                instruction.as_any_value_enum().into_int_value().replace_all_uses_with(lhs);
+                edits.erase(instruction);
+            }
+        }
+    }
+
+    let context = Context::create();
+    let module = context.create_module("ivs");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let fn_type = i32_type.fn_type(&[i32_type.into(), i32_type.into()], false);
+
+    module.add_function("declaration", fn_type, None);
+
+    let function = module.add_function("my_fn", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+    let exit = context.append_basic_block(function, "exit");
+    let x = function.get_nth_param(0).unwrap().into_int_value();
+    let y = function.get_nth_param(1).unwrap().into_int_value();
+
+    builder.position_at_end(entry);
+
+    let sum = builder.build_int_add(x, i32_type.const_zero(), "sum");
+    let difference = builder.build_int_sub(sum, y, "difference");
+    let product = builder.build_int_mul(difference, y, "product");
+    let ptr = builder.build_alloca(i32_type, "ptr");
+
+    builder.build_store(ptr, product);
+    builder.build_int_compare(IntPredicate::EQ, product, y, "cmp");
+    builder.build_unconditional_branch(exit);
+    builder.position_at_end(exit);
+    builder.build_return(Some(&product));
+
+    let mut counter = Counter::default();
+
+    module.visit(&mut counter);
+
+    assert_eq!(counter.functions, 2);
+    assert_eq!(counter.basic_blocks, 2);
+    assert_eq!(counter.binary_operators, 2);
+    assert_eq!(counter.subs, 1);
+    assert_eq!(counter.cmps, 1);
+    assert_eq!(counter.terminators, 2);
+    assert_eq!(counter.others, 2);
+
+    function.visit(&mut AddZeroFolder);
+
+    assert!(module.verify().is_ok());
+
+    let first_instruction = entry.get_first_instruction().unwrap();
+
+    assert_eq!(first_instruction.get_opcode(), Sub);
+    assert_eq!(first_instruction.get_operand(0).unwrap().left().unwrap(), x.as_basic_value_enum());
+
+    let mut counter = Counter::default();
+
+    function.visit(&mut counter);
+
+    assert_eq!(counter.functions, 1);
+    assert_eq!(counter.binary_operators, 1);
+}
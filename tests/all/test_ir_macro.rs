@@ -0,0 +1,100 @@
+use inkwell::context::Context;
+use inkwell::execution_engine::JitFunction;
+use inkwell::values::InstructionOpcode;
+use inkwell::{ir, OptimizationLevel};
+
+#[test]
+fn test_ir_macro_arithmetic() {
+    type SumFunc = unsafe extern "C" fn(u32) -> u32;
+
+    let context = Context::create();
+    let module = context.create_module("ir");
+    let i64_type = context.i64_type();
+    let one = context.i32_type().const_int(1, false);
+
+    let (add_one, sum_to) = ir!(&context, &module;
+        fn add_one(i32 %a) -> i32 {
+            %sum = add %a, {one};
+            ret %sum;
+        }
+
+        fn sum_to(i32 %n) -> i32 {
+        entry:
+            %is_zero = icmp eq %n, 0;
+            br %is_zero, label %exit, label %loop;
+        loop:
+            %i = phi i32 [0, %entry], [%next, %loop];
+            %total = phi {i64_type} [0, %entry], [%new_total, %loop];
+            %next = call @add_one(%i);
+            %wide = zext %next to i64;
+            %new_total = add %total, %wide;
+            %done = icmp uge %next, %n;
+            br %done, label %exit, label %loop;
+        exit:
+            %result = phi i64 [0, %entry], [%new_total, %loop];
+            %narrow = trunc %result to i32;
+            ret %narrow;
+        }
+    );
+
+    assert_eq!(add_one.get_name().to_str(), Ok("add_one"));
+    assert_eq!(sum_to.count_basic_blocks(), 3);
+    assert!(module.verify().is_ok());
+
+    let first_instruction = add_one.get_first_basic_block().unwrap().get_first_instruction().unwrap();
+
+    assert_eq!(first_instruction.get_opcode(), InstructionOpcode::Add);
+    assert_eq!(add_one.get_first_param().unwrap().into_int_value().get_name().to_str(), Ok("a"));
+
+    let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+
+    unsafe {
+        let sum_to: JitFunction<SumFunc> = execution_engine.get_function("sum_to").unwrap();
+
+        assert_eq!(sum_to.call(0), 0);
+        assert_eq!(sum_to.call(4), 10);
+        assert_eq!(sum_to.call(10), 55);
+    }
+}
+
+#[test]
+fn test_ir_macro_memory_and_floats() {
+    type ScaleFunc = unsafe extern "C" fn(f64, u32) -> f64;
+
+    let context = Context::create();
+    let module = context.create_module("ir");
+
+    let scale = ir!(&context, &module;
+        extern fn llvm_fabs_f64(double) -> double;
+
+        fn scale(double %x, i32 %flags) -> double {
+            %negate = icmp ne %flags, 0;
+            %slot = alloca double;
+            store %x, %slot;
+            %loaded = load %slot;
+            %doubled = fmul %loaded, 2.0;
+            %negated = fneg %doubled;
+            %result = select %negate, %negated, %doubled;
+            %is_small = fcmp olt %result, 1.0;
+            br %is_small, label %small, label %big;
+        small:
+            ret 1.0;
+        big:
+            ret %result;
+        }
+    );
+
+    assert_eq!(module.get_function("llvm_fabs_f64").unwrap().count_basic_blocks(), 0);
+    assert!(module.verify().is_ok());
+    assert_eq!(scale.get_params().len(), 2);
+
+    let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+
+    unsafe {
+        let scale: JitFunction<ScaleFunc> = execution_engine.get_function("scale").unwrap();
+
+        assert_eq!(scale.call(3.0, 0), 6.0);
+        assert_eq!(scale.call(3.0, 1), 1.0);
+        assert_eq!(scale.call(0.25, 0), 1.0);
+    }
+}
@@ -0,0 +1,122 @@
+use inkwell::OptimizationLevel;
+use inkwell::context::Context;
+use inkwell::execution_engine::{JitFunction, MCJITOptions};
+use inkwell::memory_manager::MCJITMemoryManager;
+use libc::{c_int, c_void, MAP_ANON, MAP_FAILED, MAP_PRIVATE, PROT_EXEC, PROT_READ, PROT_WRITE};
+
+use std::cell::RefCell;
+use std::ptr;
+use std::rc::Rc;
+
+#[derive(Debug, Default)]
+struct Usage {
+    code_bytes: usize,
+    finalized: usize,
+    dropped: bool,
+}
+
+// Maps every section read-write, only applying each section's final protection, which is never
+// both writable and executable, once finalized
+struct WxMemoryManager {
+    usage: Rc<RefCell<Usage>>,
+    regions: Vec<(*mut c_void, usize, c_int)>,
+}
+
+impl WxMemoryManager {
+    fn map(&mut self, size: usize, alignment: u32, protection: c_int) -> *mut u8 {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+
+        assert!(alignment as usize <= page_size);
+
+        let len = (size.max(1) + page_size - 1) / page_size * page_size;
+        let addr = unsafe { libc::mmap(ptr::null_mut(), len, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANON, -1, 0) };
+
+        if addr == MAP_FAILED {
+            return ptr::null_mut();
+        }
+
+        self.regions.push((addr, len, protection));
+
+        addr as *mut u8
+    }
+}
+
+unsafe impl MCJITMemoryManager for WxMemoryManager {
+    fn allocate_code_section(&mut self, size: usize, alignment: u32, _section_id: u32, _section_name: &str) -> *mut u8 {
+        self.usage.borrow_mut().code_bytes += size;
+        self.map(size, alignment, PROT_READ | PROT_EXEC)
+    }
+
+    fn allocate_data_section(&mut self, size: usize, alignment: u32, _section_id: u32, _section_name: &str, is_read_only: bool) -> *mut u8 {
+        let protection = if is_read_only { PROT_READ } else { PROT_READ | PROT_WRITE };
+
+        self.map(size, alignment, protection)
+    }
+
+    fn finalize_memory(&mut self) -> Result<(), String> {
+        self.usage.borrow_mut().finalized += 1;
+
+        for &(addr, len, protection) in &self.regions {
+            if unsafe { libc::mprotect(addr, len, protection) } != 0 {
+                return Err("Unable to protect JIT memory".into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for WxMemoryManager {
+    fn drop(&mut self) {
+        for &(addr, len, _) in &self.regions {
+            unsafe {
+                libc::munmap(addr, len);
+            }
+        }
+
+        self.usage.borrow_mut().dropped = true;
+    }
+}
+
+#[test]
+fn test_mcjit_memory_manager() {
+    let context = Context::create();
+    let module = context.create_module("tenant");
+    let builder = context.create_builder();
+    let i64_type = context.i64_type();
+    let fn_type = i64_type.fn_type(&[i64_type.into(), i64_type.into()], false);
+    let function = module.add_function("add", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+
+    builder.position_at_end(entry);
+
+    let lhs = function.get_first_param().unwrap().into_int_value();
+    let rhs = function.get_last_param().unwrap().into_int_value();
+    let sum = builder.build_int_add(lhs, rhs, "sum");
+
+    builder.build_return(Some(&sum));
+
+    let usage = Rc::new(RefCell::new(Usage::default()));
+    let memory_manager = WxMemoryManager {
+        usage: usage.clone(),
+        regions: Vec::new(),
+    };
+    let execution_engine = module.create_mcjit_execution_engine(MCJITOptions::new().opt_level(OptimizationLevel::None).memory_manager(memory_manager)).unwrap();
+
+    assert_eq!(usage.borrow().code_bytes, 0);
+
+    unsafe {
+        let add: JitFunction<unsafe extern "C" fn(u64, u64) -> u64> = execution_engine.get_function("add").unwrap();
+
+        assert_eq!(add.call(2, 40), 42);
+    }
+
+    assert!(usage.borrow().code_bytes > 0);
+    assert!(usage.borrow().finalized > 0);
+    assert!(!usage.borrow().dropped);
+
+    drop(execution_engine);
+    drop(module);
+
+    assert!(usage.borrow().dropped);
+}
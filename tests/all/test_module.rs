@@ -483,3 +483,43 @@ fn test_double_ee_from_same_module() {
 
     assert!(module.create_interpreter_execution_engine().is_err());
 }
+
+#[llvm_versions(3.9..=latest)]
+#[test]
+fn test_cfg_to_dot() {
+    let context = Context::create();
+    let module = context.create_module("cfg");
+    let builder = context.create_builder();
+    let void_type = context.void_type();
+    let fn_type = void_type.fn_type(&[], false);
+
+    module.add_function("declaration", fn_type, None);
+
+    let function = module.add_function("jump", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+    let exit = context.append_basic_block(function, "exit");
+
+    builder.position_at_end(entry);
+    builder.build_unconditional_branch(exit);
+    builder.position_at_end(exit);
+    builder.build_return(None);
+
+    let expected = "\
+digraph \"CFG for 'cfg' module\" {
+    node [shape=box];
+    subgraph cluster_f1 {
+        label=\"jump\";
+        f1b0 [label=\"entry\"];
+        f1b1 [label=\"exit\"];
+        f1b0 -> f1b1;
+    }
+}
+";
+
+    assert_eq!(module.cfg_only_to_dot(), expected);
+
+    let dot = module.cfg_to_dot();
+
+    assert!(dot.contains("        f1b0 [label=\"entry:\\l  br label %exit\\l\"];\n"));
+    assert!(dot.contains("        f1b1 [label=\"exit:\\l  ret void\\l\"];\n"));
+}
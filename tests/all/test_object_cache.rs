@@ -0,0 +1,144 @@
+use inkwell::OptimizationLevel;
+use inkwell::context::Context;
+use inkwell::memory_buffer::MemoryBuffer;
+use inkwell::module::Module;
+use inkwell::object_cache::{compile_with_cache, DirectoryObjectCache, ObjectCache, ObjectCacheKey};
+use inkwell::targets::{CodeModel, InitializationConfig, RelocMode, Target, TargetMachine};
+
+use std::collections::HashMap;
+use std::env::temp_dir;
+use std::fs;
+use std::process;
+
+#[derive(Default)]
+struct CountingObjectCache {
+    objects: HashMap<ObjectCacheKey, Vec<u8>>,
+    hits: usize,
+    compiled: usize,
+}
+
+impl ObjectCache for CountingObjectCache {
+    fn get_object(&mut self, key: &ObjectCacheKey) -> Option<MemoryBuffer> {
+        let object = self.objects.get(key)?;
+
+        self.hits += 1;
+
+        Some(MemoryBuffer::create_from_memory_range_copy(object, "cached"))
+    }
+
+    fn notify_object_compiled(&mut self, key: &ObjectCacheKey, object: &MemoryBuffer) {
+        self.compiled += 1;
+        self.objects.insert(*key, object.as_slice().to_vec());
+    }
+}
+
+fn create_target_machine(opt_level: OptimizationLevel) -> TargetMachine {
+    Target::initialize_native(&InitializationConfig::default()).expect("Failed to initialize native target");
+
+    let triple = TargetMachine::get_default_triple();
+    let target = Target::from_triple(&triple).unwrap();
+
+    target.create_target_machine(&triple, "", "", opt_level, RelocMode::Default, CodeModel::JITDefault).unwrap()
+}
+
+fn create_answer_module<'ctx>(context: &'ctx Context, answer: u64) -> Module<'ctx> {
+    let module = context.create_module("answer");
+    let builder = context.create_builder();
+    let i64_type = context.i64_type();
+    let function = module.add_function("answer", i64_type.fn_type(&[], false), None);
+
+    builder.position_at_end(context.append_basic_block(function, "entry"));
+    builder.build_return(Some(&i64_type.const_int(answer, false)));
+
+    module
+}
+
+#[test]
+fn test_object_cache_key() {
+    let context = Context::create();
+    let target_machine = create_target_machine(OptimizationLevel::Default);
+    let module = create_answer_module(&context, 42);
+    let key = ObjectCacheKey::new(&module, &target_machine);
+
+    assert_eq!(key, ObjectCacheKey::new(&create_answer_module(&context, 42), &target_machine));
+    assert_ne!(key, ObjectCacheKey::new(&create_answer_module(&context, 41), &target_machine));
+    assert_ne!(key, ObjectCacheKey::new(&module, &create_target_machine(OptimizationLevel::Aggressive)));
+    assert_eq!(key.to_string().len(), 32);
+}
+
+#[test]
+fn test_compile_with_cache() {
+    let context = Context::create();
+    let target_machine = create_target_machine(OptimizationLevel::Default);
+    let mut cache = CountingObjectCache::default();
+    let first = compile_with_cache(&mut cache, &create_answer_module(&context, 42), &target_machine).unwrap();
+
+    assert_eq!((cache.hits, cache.compiled), (0, 1));
+
+    let second = compile_with_cache(&mut cache, &create_answer_module(&context, 42), &target_machine).unwrap();
+
+    assert_eq!((cache.hits, cache.compiled), (1, 1));
+    assert_eq!(first.as_slice(), second.as_slice());
+
+    compile_with_cache(&mut cache, &create_answer_module(&context, 41), &target_machine).unwrap();
+
+    assert_eq!((cache.hits, cache.compiled), (1, 2));
+}
+
+#[test]
+fn test_directory_object_cache() {
+    let mut directory = temp_dir();
+
+    directory.push(format!("inkwell_object_cache_{}", process::id()));
+
+    let context = Context::create();
+    let target_machine = create_target_machine(OptimizationLevel::None);
+    let module = create_answer_module(&context, 42);
+    let key = ObjectCacheKey::new(&module, &target_machine);
+    let mut cache = DirectoryObjectCache::new(&directory).unwrap();
+
+    assert!(cache.get_object(&key).is_none());
+
+    let object = compile_with_cache(&mut cache, &module, &target_machine).unwrap();
+
+    assert!(cache.get_object_path(&key).exists());
+
+    // A fresh cache over the same directory, as on the next run, reloads the object
+    let mut cache = DirectoryObjectCache::new(&directory).unwrap();
+    let cached_object = cache.get_object(&key).unwrap();
+
+    assert_eq!(cached_object.as_slice(), object.as_slice());
+    assert!(cached_object.create_object_file().is_ok());
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[cfg(feature = "experimental")]
+#[llvm_versions(7.0..=11.0)]
+#[test]
+fn test_orc_object_cache() {
+    use inkwell::execution_engine::experimental::Orc;
+
+    let context = Context::create();
+    let orc = Orc::create(create_target_machine(OptimizationLevel::None));
+    let target_machine = create_target_machine(OptimizationLevel::None);
+    let mut cache = CountingObjectCache::default();
+    let module = create_answer_module(&context, 42);
+
+    orc.add_module_with_object_cache(&module, &target_machine, &mut cache).unwrap();
+
+    let answer: unsafe extern "C" fn() -> u64 = unsafe { std::mem::transmute(orc.get_symbol_address("answer").unwrap()) };
+
+    assert_eq!(unsafe { answer() }, 42);
+
+    // The cached object is loaded into a second JIT without being compiled again
+    let orc = Orc::create(create_target_machine(OptimizationLevel::None));
+
+    orc.add_module_with_object_cache(&module, &target_machine, &mut cache).unwrap();
+
+    let answer: unsafe extern "C" fn() -> u64 = unsafe { std::mem::transmute(orc.get_symbol_address("answer").unwrap()) };
+
+    assert_eq!(unsafe { answer() }, 42);
+    assert_eq!((cache.hits, cache.compiled), (1, 1));
+    assert!(orc.get_symbol_address("question").is_err());
+}
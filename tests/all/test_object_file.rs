@@ -244,3 +244,219 @@ fn test_section_contains_nul() {
     }
     assert!(has_section_test);
 }
+
+#[test]
+fn test_sections_outlive_iterator() {
+    let target_machine = get_native_target_machine();
+
+    let context = Context::create();
+    let module = context.create_module("test_sections_outlive_iterator");
+
+    for (name, value) in &[("first", 1), ("second", 2)] {
+        let gv = module.add_global(context.i32_type(), None, name);
+
+        gv.set_initializer(&context.i32_type().const_int(*value, false));
+        gv.set_section(name);
+    }
+
+    apply_target_to_module(&target_machine, &module);
+
+    let memory_buffer = target_machine
+        .write_to_memory_buffer(&module, FileType::Object)
+        .unwrap();
+    let object_file = memory_buffer.create_object_file().unwrap();
+
+    // Each section stays valid after the iterator has moved past it
+    let sections: Vec<_> = object_file.get_sections().collect();
+    let first = sections.iter().find(|section| section.get_name().and_then(|name| name.to_str().ok()) == Some("first")).unwrap();
+    let second = sections.iter().find(|section| section.get_name().and_then(|name| name.to_str().ok()) == Some("second")).unwrap();
+
+    assert_eq!(first.get_contents(), 1u32.to_ne_bytes());
+    assert_eq!(second.get_contents(), 2u32.to_ne_bytes());
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_symbol_sections_and_relocation_targets() {
+    use self::inkwell::object_file::{SectionKind, SymbolKind};
+    use self::inkwell::targets::TargetTriple;
+
+    Target::initialize_x86(&InitializationConfig::default());
+
+    let triple = TargetTriple::create("x86_64-pc-linux-gnu");
+    let target = Target::from_triple(&triple).unwrap();
+    let target_machine = target
+        .create_target_machine(&triple, "x86-64", "", OptimizationLevel::None, RelocMode::PIC, CodeModel::Default)
+        .unwrap();
+
+    let context = Context::create();
+    let module = context.create_module("test_symbol_sections");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let external = module.add_function("external", i32_type.fn_type(&[], false), None);
+    let caller = module.add_function("caller", i32_type.fn_type(&[], false), None);
+    let zeroed = module.add_global(i32_type, None, "zeroed");
+    let counter = module.add_global(i32_type, None, "counter");
+    let limit = module.add_global(i32_type, None, "limit");
+
+    zeroed.set_initializer(&i32_type.const_zero());
+    counter.set_initializer(&i32_type.const_int(1, false));
+    limit.set_initializer(&i32_type.const_int(2, false));
+    limit.set_constant(true);
+
+    builder.position_at_end(context.append_basic_block(caller, "entry"));
+
+    let result = builder.build_call(external, &[], "result").try_as_basic_value().left().unwrap();
+
+    builder.build_return(Some(&result));
+
+    let memory_buffer = target_machine
+        .write_to_memory_buffer(&module, FileType::Object)
+        .unwrap();
+    let object_file = memory_buffer.create_object_file().unwrap();
+    let symbols: Vec<_> = object_file.get_symbols().collect();
+    let symbol = |name: &str| symbols.iter()
+        .find(|symbol| symbol.get_name().and_then(|symbol_name| symbol_name.to_str().ok()) == Some(name))
+        .unwrap();
+
+    assert!(symbol("external").is_undefined());
+    assert_eq!(symbol("external").get_kind(), SymbolKind::Undefined);
+    assert_eq!(symbol("caller").get_kind(), SymbolKind::Text);
+    assert_eq!(symbol("caller").get_section().unwrap().get_name().unwrap().to_str(), Ok(".text"));
+    assert!(symbol("caller").get_section().unwrap().is_text());
+    assert_eq!(symbol("zeroed").get_section().unwrap().get_kind(), SectionKind::Bss);
+    assert!(symbol("zeroed").get_section().unwrap().get_contents().is_empty());
+    assert_eq!(symbol("counter").get_section().unwrap().get_kind(), SectionKind::Data);
+    assert_eq!(symbol("limit").get_section().unwrap().get_kind(), SectionKind::ReadOnlyData);
+    assert_eq!(symbol("limit").get_kind(), SymbolKind::Data);
+
+    let text = symbol("caller").get_section().unwrap();
+    let call = text.get_relocations()
+        .find(|relocation| relocation.get_symbol().and_then(|symbol| symbol.get_name()).and_then(|name| name.to_str().ok()) == Some("external"))
+        .expect("Expected a relocation for the call to external");
+
+    assert!(call.get_symbol().unwrap().is_undefined());
+    assert!(call.get_type().1.to_bytes().starts_with(b"R_X86_64_"));
+}
+
+#[llvm_versions(9.0..=latest)]
+fn write_x86_64_object(triple: &str, function_name: &str) -> Vec<u8> {
+    use self::inkwell::targets::TargetTriple;
+
+    Target::initialize_x86(&InitializationConfig::default());
+
+    let triple = TargetTriple::create(triple);
+    let target = Target::from_triple(&triple).unwrap();
+    let target_machine = target
+        .create_target_machine(&triple, "x86-64", "", OptimizationLevel::None, RelocMode::Default, CodeModel::Default)
+        .unwrap();
+    let context = Context::create();
+    let module = context.create_module(function_name);
+    let builder = context.create_builder();
+    let function = module.add_function(function_name, context.void_type().fn_type(&[], false), None);
+
+    builder.position_at_end(context.append_basic_block(function, "entry"));
+    builder.build_return(None);
+
+    target_machine.write_to_memory_buffer(&module, FileType::Object).unwrap().as_slice().to_vec()
+}
+
+// Appends a GNU ar member header and its contents to archive. name is written as is, so member names
+// should end with '/'
+#[cfg(any(
+    feature = "llvm9-0",
+    feature = "llvm10-0",
+    feature = "llvm11-0",
+    feature = "llvm12-0",
+    feature = "llvm13-0",
+    all(feature = "experimental", any(feature = "llvm7-0", feature = "llvm8-0"))
+))]
+pub(crate) fn push_archive_member(archive: &mut Vec<u8>, name: &str, contents: &[u8]) {
+    archive.extend(format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", name, 0, 0, 0, 644, contents.len()).bytes());
+    archive.extend(contents);
+
+    if contents.len() % 2 == 1 {
+        archive.push(b'\n');
+    }
+}
+
+#[llvm_versions(9.0..=latest)]
+#[test]
+fn test_binary_archive() {
+    use self::inkwell::memory_buffer::MemoryBuffer;
+    use self::inkwell::object_file::{Binary, BinaryType};
+
+    let first = write_x86_64_object("x86_64-pc-linux-gnu", "first");
+    let second = write_x86_64_object("x86_64-pc-linux-gnu", "second");
+    let mut archive = b"!<arch>\n".to_vec();
+
+    // A GNU symbol table, which isn't a member, followed by the objects
+    push_archive_member(&mut archive, "/", b"\0\0\0\0");
+    push_archive_member(&mut archive, "first.o/", &first);
+    push_archive_member(&mut archive, "second.o/", &second);
+
+    let binary = Binary::create(MemoryBuffer::create_from_memory_range_copy(&archive, "libtest.a"), None).unwrap();
+
+    assert_eq!(binary.get_type(), BinaryType::Archive);
+    assert!(binary.get_universal_slice("x86_64").is_err());
+
+    let members = binary.get_archive_members().unwrap();
+
+    assert_eq!(members.iter().map(|member| member.get_name()).collect::<Vec<_>>(), ["first.o", "second.o"]);
+    assert_eq!(members[0].get_contents(), &first[..]);
+
+    let member_binary = members[1].create_binary(None).unwrap();
+
+    assert_eq!(member_binary.get_type(), BinaryType::ELF64L);
+    assert_eq!(member_binary.get_archive_members(), Err("Binary is not an archive"));
+
+    let object_file = member_binary.into_object_file().unwrap();
+
+    assert!(object_file.get_symbols().any(|symbol| symbol.get_name().and_then(|name| name.to_str().ok()) == Some("second")));
+    assert!(binary.into_object_file().is_err());
+
+    let bitcode = Context::create().create_module("bitcode").write_bitcode_to_memory();
+
+    assert!(Binary::create(MemoryBuffer::create_from_memory_range_copy(bitcode.as_slice(), "bitcode.bc"), None).is_err());
+
+    let context = Context::create();
+    let binary = Binary::create(bitcode, Some(&context)).unwrap();
+
+    assert_eq!(binary.get_type(), BinaryType::IR);
+}
+
+#[llvm_versions(9.0..=latest)]
+#[test]
+fn test_binary_universal() {
+    use self::inkwell::memory_buffer::MemoryBuffer;
+    use self::inkwell::object_file::{Binary, BinaryType};
+
+    const CPU_TYPE_X86_64: u32 = 0x0100_0007;
+    const CPU_SUBTYPE_X86_64_ALL: u32 = 3;
+    const SLICE_OFFSET: u32 = 0x1000;
+
+    let object = write_x86_64_object("x86_64-apple-macosx10.15.0", "sliced");
+    let mut universal = Vec::new();
+
+    // A fat header and a single fat_arch, all big endian, followed by the page aligned slice
+    for field in &[0xcafe_babe, 1, CPU_TYPE_X86_64, CPU_SUBTYPE_X86_64_ALL, SLICE_OFFSET, object.len() as u32, 12] {
+        universal.extend(u32::to_be_bytes(*field).iter());
+    }
+
+    universal.resize(SLICE_OFFSET as usize, 0);
+    universal.extend(object.iter());
+
+    let binary = Binary::create(MemoryBuffer::create_from_memory_range_copy(&universal, "universal"), None).unwrap();
+
+    assert_eq!(binary.get_type(), BinaryType::MachOUniversalBinary);
+    assert!(binary.get_archive_members().is_err());
+    assert!(binary.get_universal_slice("arm64").is_err());
+
+    let slice = binary.get_universal_slice("x86_64").unwrap();
+
+    // The slice doesn't borrow from the universal binary
+    drop(binary);
+
+    assert_eq!(slice.get_type(), BinaryType::MachO64L);
+    assert_eq!(slice.as_slice(), &object[..]);
+}
@@ -0,0 +1,97 @@
+use inkwell::context::Context;
+use inkwell::pattern_match::*;
+use inkwell::values::BasicValue;
+use inkwell::{AddressSpace, IntPredicate};
+
+#[test]
+fn test_binary_patterns() {
+    let context = Context::create();
+    let module = context.create_module("pm");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let fn_type = i32_type.fn_type(&[i32_type.into(), i32_type.into()], false);
+    let function = module.add_function("my_fn", fn_type, None);
+    let x = function.get_nth_param(0).unwrap().into_int_value();
+    let y = function.get_nth_param(1).unwrap().into_int_value();
+
+    builder.position_at_end(context.append_basic_block(function, "entry"));
+
+    let add_zero = builder.build_int_add(x, i32_type.const_zero(), "add_zero");
+    let add_xy = builder.build_int_add(x, y, "add_xy");
+    let mul = builder.build_int_mul(i32_type.const_int(3, false), add_xy, "mul");
+    let not = builder.build_xor(i32_type.const_all_ones(), y, "not");
+    let neg = builder.build_int_sub(i32_type.const_zero(), x, "neg");
+    let zext = builder.build_int_z_extend(x, context.i64_type(), "zext");
+
+    builder.build_return(Some(&mul));
+
+    assert!(match_value(add_zero, m_add(m_any(), m_const_int(0))));
+    assert!(match_value(add_zero, m_add(m_specific(x), m_zero())));
+    assert!(!match_value(add_zero, m_sub(m_any(), m_any())));
+    assert!(!match_value(add_xy, m_add(m_any(), m_const_int(0))));
+    assert!(!match_value(x, m_add(m_any(), m_any())));
+
+    let mut lhs = None;
+    let mut rhs = None;
+
+    assert!(match_value(add_xy, m_add(m_value(&mut lhs), m_value(&mut rhs))));
+    assert_eq!(lhs.unwrap(), x);
+    assert_eq!(rhs.unwrap(), y);
+
+    // Non commutative patterns require operands in order
+    assert!(!match_value(mul, m_mul(m_specific(add_xy), m_const_int(3))));
+
+    let mut constant = None;
+    let mut sum = None;
+
+    assert!(match_value(mul, m_c_mul(m_both(m_value(&mut sum), m_add(m_any(), m_any())), m_constant_int(&mut constant))));
+    assert_eq!(sum.unwrap(), add_xy);
+    assert_eq!(constant.unwrap().get_zero_extended_constant(), Some(3));
+
+    let mut operand = None;
+
+    assert!(match_value(not, m_not(m_value(&mut operand))));
+    assert_eq!(operand.unwrap(), y);
+    assert!(match_value(neg, m_neg(m_specific(x))));
+    assert!(match_value(zext, m_zext(m_specific(x))));
+    assert!(!match_value(zext, m_sext(m_any())));
+    assert!(match_value(add_zero, m_either(m_sub(m_any(), m_any()), m_add(m_any(), m_any()))));
+}
+
+#[test]
+fn test_instruction_patterns() {
+    let context = Context::create();
+    let module = context.create_module("pm");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let i32_ptr_type = i32_type.ptr_type(AddressSpace::Generic);
+    let fn_type = i32_type.fn_type(&[i32_ptr_type.into()], false);
+    let function = module.add_function("my_fn", fn_type, None);
+    let ptr = function.get_first_param().unwrap().into_pointer_value();
+
+    builder.position_at_end(context.append_basic_block(function, "entry"));
+
+    let load = builder.build_load(ptr, "load").into_int_value();
+    let cmp = builder.build_int_compare(IntPredicate::EQ, load, i32_type.const_int(42, false), "cmp");
+    let select = builder.build_select(cmp, load, i32_type.const_zero(), "select").into_int_value();
+    let store = builder.build_store(ptr, select);
+
+    builder.build_return(Some(&select));
+
+    assert!(match_value(cmp, m_icmp(IntPredicate::EQ, m_load(m_specific(ptr)), m_const_int(42))));
+    assert!(!match_value(cmp, m_icmp(IntPredicate::NE, m_any(), m_any())));
+
+    let mut predicate = None;
+    let mut instruction = None;
+
+    assert!(match_value(cmp, m_any_icmp(&mut predicate, m_instruction(&mut instruction), m_any())));
+    assert_eq!(predicate, Some(IntPredicate::EQ));
+    assert_eq!(instruction, load.as_instruction_value());
+
+    assert!(match_value(select, m_select(m_specific(cmp), m_specific(load), m_zero())));
+
+    assert!(match_instruction(store, m_store(m_specific(select), m_specific(ptr))));
+    assert!(!match_instruction(store, m_store(m_specific(load), m_specific(ptr))));
+    assert!(match_instruction(load.as_instruction_value().unwrap(), m_load(m_any())));
+    assert!(!match_instruction(store, m_any()));
+}
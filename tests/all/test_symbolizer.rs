@@ -0,0 +1,253 @@
+use inkwell::OptimizationLevel;
+use inkwell::context::Context;
+use inkwell::module::Module;
+use inkwell::object_file::ObjectFile;
+use inkwell::symbolizer::Symbolizer;
+use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple};
+
+fn write_x86_64_object(module: &Module) -> ObjectFile {
+    Target::initialize_x86(&InitializationConfig::default());
+
+    let triple = TargetTriple::create("x86_64-pc-linux-gnu");
+    let target = Target::from_triple(&triple).unwrap();
+    let target_machine = target
+        .create_target_machine(&triple, "x86-64", "", OptimizationLevel::None, RelocMode::PIC, CodeModel::Default)
+        .unwrap();
+
+    target_machine
+        .write_to_memory_buffer(module, FileType::Object)
+        .unwrap()
+        .create_object_file()
+        .unwrap()
+}
+
+fn add_constant_functions(context: &Context, module: &Module) {
+    let builder = context.create_builder();
+    let i64_type = context.i64_type();
+
+    for (name, value) in &[("first", 1), ("second", 2)] {
+        let function = module.add_function(name, i64_type.fn_type(&[], false), None);
+
+        builder.position_at_end(context.append_basic_block(function, "entry"));
+        builder.build_return(Some(&i64_type.const_int(*value, false)));
+    }
+}
+
+#[test]
+fn test_symbolize_object_file() {
+    let context = Context::create();
+    let module = context.create_module("test_symbolize_object_file");
+
+    add_constant_functions(&context, &module);
+
+    let object_file = write_x86_64_object(&module);
+    let symbolizer = Symbolizer::from_object_file(&object_file).unwrap();
+    let symbol = object_file
+        .get_symbols()
+        .find(|symbol| symbol.get_name().and_then(|name| name.to_str().ok()) == Some("second"))
+        .unwrap();
+
+    let first = symbolizer.symbolize(0).unwrap();
+
+    assert_eq!(first.get_function_name(), "first");
+    assert_eq!(first.get_offset(), 0);
+    assert!(first.get_location().is_none());
+
+    let second = symbolizer.symbolize(symbol.get_address() + 1).unwrap();
+
+    assert_eq!(second.get_function_name(), "second");
+    assert_eq!(second.get_offset(), 1);
+    assert!(symbolizer.symbolize(symbol.get_address() + symbol.size()).is_none());
+}
+
+#[test]
+fn test_symbolize_execution_engine() {
+    let context = Context::create();
+    let module = context.create_module("test_symbolize_execution_engine");
+
+    add_constant_functions(&context, &module);
+
+    let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+    let symbolizer = Symbolizer::from_execution_engine(&execution_engine, &module);
+    let address = execution_engine.get_function_address("second").unwrap() as u64;
+
+    let second = symbolizer.symbolize(address + 2).unwrap();
+
+    assert_eq!(second.get_function_name(), "second");
+    assert_eq!(second.get_offset(), 2);
+    assert!(symbolizer.symbolize(0).is_none());
+}
+
+// Adds a function "crash", with debug info placing its return at crash.c:42:7
+#[llvm_versions(7.0..=latest)]
+fn add_crash_function(context: &Context, module: &Module) {
+    use inkwell::debug_info::{AsDIScope, DIFlags, DIFlagsConstants, DWARFEmissionKind, DWARFSourceLanguage};
+    use inkwell::module::FlagBehavior;
+
+    let builder = context.create_builder();
+    let i64_type = context.i64_type();
+
+    module.add_basic_value_flag("Debug Info Version", FlagBehavior::Warning, context.i32_type().const_int(3, false));
+
+    let (dibuilder, compile_unit) = module.create_debug_info_builder(
+        true,
+        DWARFSourceLanguage::C,
+        "crash.c",
+        "/src",
+        "inkwell",
+        false,
+        "",
+        0,
+        "",
+        DWARFEmissionKind::Full,
+        0,
+        false,
+        false,
+        #[cfg(any(feature = "llvm11-0", feature = "llvm12-0", feature = "llvm13-0"))]
+        "",
+        #[cfg(any(feature = "llvm11-0", feature = "llvm12-0", feature = "llvm13-0"))]
+        "",
+    );
+    let subroutine_type = dibuilder.create_subroutine_type(compile_unit.get_file(), None, &[], DIFlags::PUBLIC);
+    let subprogram = dibuilder.create_function(
+        compile_unit.as_debug_info_scope(),
+        "crash",
+        None,
+        compile_unit.get_file(),
+        41,
+        subroutine_type,
+        false,
+        true,
+        41,
+        DIFlags::PUBLIC,
+        false,
+    );
+    let function = module.add_function("crash", i64_type.fn_type(&[], false), None);
+
+    function.set_subprogram(subprogram);
+    builder.position_at_end(context.append_basic_block(function, "entry"));
+
+    let location = dibuilder.create_debug_location(context, 42, 7, subprogram.as_debug_info_scope(), None);
+
+    builder.set_current_debug_location(context, location);
+    builder.build_return(Some(&i64_type.const_int(42, false)));
+    dibuilder.finalize();
+
+    assert!(module.verify().is_ok());
+}
+
+#[llvm_versions(7.0..=latest)]
+#[test]
+fn test_symbolize_debug_line() {
+    let context = Context::create();
+    let module = context.create_module("test_symbolize_debug_line");
+
+    add_crash_function(&context, &module);
+
+    let object_file = write_x86_64_object(&module);
+    let symbolizer = Symbolizer::from_object_file(&object_file).unwrap();
+    let symbol = object_file
+        .get_symbols()
+        .find(|symbol| symbol.get_name().and_then(|name| name.to_str().ok()) == Some("crash"))
+        .unwrap();
+
+    // The final ret carries the return's location, whereas the start may belong to the prologue
+    let symbolized = symbolizer.symbolize(symbol.get_address() + symbol.size() - 1).unwrap();
+    let location = symbolized.get_location().unwrap();
+
+    assert_eq!(symbolized.get_function_name(), "crash");
+    assert!(location.get_file().ends_with("crash.c"));
+    assert_eq!(location.get_line(), 42);
+    assert_eq!(location.get_column(), 7);
+}
+
+#[llvm_versions(11.0..=latest)]
+#[test]
+fn test_symbolize_relocatable_dwarf_5() {
+    use inkwell::module::FlagBehavior;
+
+    let context = Context::create();
+    let module = context.create_module("test_symbolize_relocatable_dwarf_5");
+
+    module.add_basic_value_flag("Dwarf Version", FlagBehavior::Warning, context.i32_type().const_int(5, false));
+    add_crash_function(&context, &module);
+
+    let object_file = write_x86_64_object(&module);
+
+    assert_eq!(
+        Symbolizer::from_object_file(&object_file).unwrap_err(),
+        "DWARF 5 line tables which name files through relocations are not supported"
+    );
+}
+
+// Other object formats decorate symbol names, which then don't match the ExecutionEngine's
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[llvm_versions(7.0..=latest)]
+#[test]
+fn test_symbolize_execution_engine_object() {
+    Target::initialize_native(&InitializationConfig::default()).unwrap();
+
+    let context = Context::create();
+    let module = context.create_module("test_symbolize_execution_engine_object");
+
+    add_crash_function(&context, &module);
+    add_constant_functions(&context, &module);
+
+    // Compiled as MCJIT compiles it, which defaults to the large code model on x86-64
+    let triple = TargetMachine::get_default_triple();
+    let target_machine = Target::from_triple(&triple)
+        .unwrap()
+        .create_target_machine(&triple, "", "", OptimizationLevel::None, RelocMode::Default, CodeModel::Large)
+        .unwrap();
+    let object_file = target_machine
+        .write_to_memory_buffer(&module, FileType::Object)
+        .unwrap()
+        .create_object_file()
+        .unwrap();
+    let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+    let symbolizer = Symbolizer::from_execution_engine_object(&execution_engine, &module, &object_file).unwrap();
+    let address = execution_engine.get_function_address("second").unwrap() as u64;
+
+    let second = symbolizer.symbolize(address + 1).unwrap();
+
+    assert_eq!(second.get_function_name(), "second");
+    assert_eq!(second.get_offset(), 1);
+    assert!(second.get_location().is_none());
+
+    let crash = unsafe { execution_engine.get_function::<unsafe extern "C" fn() -> u64>("crash") }.unwrap();
+
+    assert_eq!(unsafe { crash.call() }, 42);
+
+    let address = execution_engine.get_function_address("crash").unwrap() as u64;
+    let symbolized = (address..address + 64)
+        .filter_map(|address| symbolizer.symbolize(address))
+        .take_while(|symbolized| symbolized.get_function_name() == "crash")
+        .last()
+        .unwrap();
+    let location = symbolized.get_location().unwrap();
+
+    assert!(location.get_file().ends_with("crash.c"));
+    assert_eq!(location.get_line(), 42);
+    assert_eq!(location.get_column(), 7);
+    assert!(Symbolizer::from_execution_engine_object(&execution_engine, &context.create_module("empty"), &object_file).is_err());
+
+    // An object whose functions are laid out differently doesn't describe the loaded code
+    let reordered = context.create_module("reordered");
+    let builder = context.create_builder();
+    let i64_type = context.i64_type();
+
+    for name in &["second", "first"] {
+        let function = reordered.add_function(name, i64_type.fn_type(&[], false), None);
+
+        builder.position_at_end(context.append_basic_block(function, "entry"));
+        builder.build_return(Some(&i64_type.const_zero()));
+    }
+
+    let reordered_object = target_machine
+        .write_to_memory_buffer(&reordered, FileType::Object)
+        .unwrap()
+        .create_object_file()
+        .unwrap();
+
+    assert!(Symbolizer::from_execution_engine_object(&execution_engine, &module, &reordered_object).is_err());
+}
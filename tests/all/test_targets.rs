@@ -383,3 +383,251 @@ fn test_write_target_machine_to_memory_buffer() {
     assert!(string.contains("my_module"));
     assert!(string.contains(".section"));
 }
+
+#[llvm_versions(3.9..=latest)]
+#[test]
+fn test_target_machine_options() {
+    use inkwell::attributes::AttributeLoc;
+    use inkwell::targets::{FramePointer, ModuleCodeGenOptions, TargetMachineOptions};
+
+    Target::initialize_x86(&InitializationConfig::default());
+
+    let triple = TargetTriple::create("x86_64-pc-linux-gnu");
+    let target = Target::from_triple(&triple).unwrap();
+    let options = TargetMachineOptions::new()
+        .cpu("x86-64")
+        .features("+avx2")
+        .opt_level(OptimizationLevel::None)
+        .reloc_mode(RelocMode::PIC);
+    let module_options = ModuleCodeGenOptions::new()
+        .frame_pointer(FramePointer::All)
+        .soft_float(true);
+    let target_machine = target.create_target_machine_from_options(&triple, &options).unwrap();
+
+    assert_eq!(target_machine.get_cpu().to_str(), Ok("x86-64"));
+    assert_eq!(target_machine.get_feature_string().to_str(), Ok("+avx2"));
+
+    let context = Context::create();
+    let module = context.create_module("attributes");
+    let builder = context.create_builder();
+    let void_type = context.void_type();
+    let defined = module.add_function("defined", void_type.fn_type(&[], false), None);
+    let declared = module.add_function("declared", void_type.fn_type(&[], false), None);
+
+    builder.position_at_end(context.append_basic_block(defined, "entry"));
+    builder.build_return(None);
+    module.set_triple(&triple);
+
+    module_options.apply_to_module(&module);
+    // Applying the options again doesn't change anything
+    module_options.apply_to_module(&module);
+
+    assert!(module.verify().is_ok());
+    assert_eq!(defined.count_attributes(AttributeLoc::Function), FRAME_POINTER_ATTRIBUTES.len() as u32 + 1);
+
+    for (key, value) in FRAME_POINTER_ATTRIBUTES {
+        let attribute = defined.get_string_attribute(AttributeLoc::Function, key).unwrap();
+
+        assert_eq!(attribute.get_string_value().to_str(), Ok(*value));
+    }
+
+    let soft_float = defined.get_string_attribute(AttributeLoc::Function, "use-soft-float").unwrap();
+
+    assert_eq!(soft_float.get_string_value().to_str(), Ok("true"));
+    // Declarations are left to the modules defining them
+    assert_eq!(declared.count_attributes(AttributeLoc::Function), 0);
+    assert!(target_machine.write_to_memory_buffer(&module, FileType::Object).is_ok());
+}
+
+#[llvm_versions(10.0..=latest)]
+const FRAME_POINTER_ATTRIBUTES: &[(&str, &str)] = &[("frame-pointer", "all")];
+#[llvm_versions(3.9..=9.0)]
+const FRAME_POINTER_ATTRIBUTES: &[(&str, &str)] = &[("no-frame-pointer-elim", "true")];
+
+#[test]
+fn test_target_triple_components() {
+    use inkwell::targets::{Arch, Environment, ObjectFormat, OperatingSystem, Vendor};
+
+    let triple = TargetTriple::create("armv7a-unknown-linux-gnueabihf");
+
+    assert_eq!(triple.get_arch(), Arch::Arm);
+    assert_eq!(triple.get_sub_arch(), "v7a");
+    assert_eq!(triple.get_vendor(), Vendor::Unknown);
+    assert_eq!(triple.get_os(), OperatingSystem::Linux);
+    assert_eq!(triple.get_environment(), Environment::GnuEabiHf);
+    assert_eq!(triple.get_object_format(), ObjectFormat::Elf);
+    assert_eq!(triple.pointer_width(), Some(32));
+    assert!(triple.is_linux());
+
+    let triple = TargetTriple::create("x86_64-apple-macosx10.15.0");
+
+    assert_eq!(triple.get_vendor(), Vendor::Apple);
+    assert_eq!(triple.get_os(), OperatingSystem::MacOSX);
+    assert_eq!(triple.get_os_version(), "10.15.0");
+    assert_eq!(triple.get_environment(), Environment::Unknown);
+    assert_eq!(triple.get_object_format(), ObjectFormat::MachO);
+    assert!(triple.is_darwin());
+    assert!(!triple.is_windows());
+
+    let triple = TargetTriple::create("i686-pc-windows-msvc-elf");
+
+    assert_eq!(triple.get_arch(), Arch::X86);
+    assert_eq!(triple.get_environment(), Environment::Msvc);
+    assert_eq!(triple.get_object_format(), ObjectFormat::Elf);
+    assert!(triple.is_windows());
+
+    // Components are found wherever they are, and MinGW and Cygwin triples target Windows
+    let triple = TargetTriple::create("x86_64-linux-gnu");
+
+    assert_eq!(triple.get_vendor(), Vendor::Unknown);
+    assert_eq!(triple.get_os(), OperatingSystem::Linux);
+    assert_eq!(triple.get_environment(), Environment::Gnu);
+
+    let triple = TargetTriple::create("x86_64-w64-mingw32");
+
+    assert_eq!(triple.get_os(), OperatingSystem::Windows);
+    assert_eq!(triple.get_environment(), Environment::Gnu);
+    assert_eq!(triple.get_object_format(), ObjectFormat::Coff);
+    assert!(triple.is_windows());
+
+    let triple = TargetTriple::create("i686-pc-cygwin");
+
+    assert_eq!(triple.get_os(), OperatingSystem::Windows);
+    assert_eq!(triple.get_environment(), Environment::Cygnus);
+    assert_eq!(triple.get_object_format(), ObjectFormat::Coff);
+    assert_eq!(TargetTriple::create("i686-pc-win32").get_environment(), Environment::Msvc);
+    assert_eq!(TargetTriple::create("x86_64-w64-mingw32-elf").get_object_format(), ObjectFormat::Elf);
+
+    let triple = TargetTriple::create("wasm32-unknown-unknown");
+
+    assert!(triple.is_wasm());
+    assert_eq!(triple.get_os(), OperatingSystem::Unknown);
+    assert_eq!(triple.get_object_format(), ObjectFormat::Wasm);
+    assert_eq!(triple.pointer_width(), Some(32));
+
+    assert_eq!(TargetTriple::create("x86_64-pc-linux-gnu").pointer_width(), Some(64));
+    assert_eq!(TargetTriple::create("x86_64-pc-linux-gnux32").pointer_width(), Some(32));
+    assert_eq!(TargetTriple::create("avr-unknown-unknown").pointer_width(), Some(16));
+    assert_eq!(TargetTriple::create("nonsense").get_arch(), Arch::Unknown);
+    assert_eq!(TargetTriple::create("nonsense").pointer_width(), None);
+}
+
+#[test]
+fn test_target_triple_builder() {
+    use inkwell::targets::{Arch, Environment, ObjectFormat, OperatingSystem, TargetTripleBuilder, Vendor};
+
+    let triple = TargetTripleBuilder::new(Arch::X86_64)
+        .vendor(Vendor::Pc)
+        .os(OperatingSystem::Windows)
+        .environment(Environment::Msvc)
+        .build();
+
+    assert_eq!(triple, TargetTriple::create("x86_64-pc-windows-msvc"));
+    assert_eq!(triple.get_object_format(), ObjectFormat::Coff);
+
+    let builder = TargetTripleBuilder::new(Arch::Thumb)
+        .sub_arch("v7em")
+        .os(OperatingSystem::Unknown)
+        .environment(Environment::EabiHf);
+
+    assert_eq!(builder.build(), TargetTriple::create("thumbv7em-unknown-unknown-eabihf"));
+    assert_eq!(builder.build().get_sub_arch(), "v7em");
+
+    let triple = TargetTripleBuilder::new(Arch::AArch64)
+        .vendor(Vendor::Apple)
+        .os(OperatingSystem::IOS)
+        .os_version("14.0")
+        .object_format(ObjectFormat::MachO)
+        .build();
+
+    assert_eq!(triple, TargetTriple::create("aarch64-apple-ios14.0-macho"));
+    assert_eq!(triple.get_os_version(), "14.0");
+    assert_eq!(TargetTripleBuilder::new(Arch::Wasm32).build(), TargetTriple::create("wasm32-unknown-unknown"));
+}
+
+#[llvm_versions(7.0..=latest)]
+#[test]
+fn test_target_triple_equivalence() {
+    let triple = TargetTriple::create("x86_64-unknown-linux-gnu");
+
+    assert!(triple.is_equivalent_to(&TargetTriple::create("amd64-linux-gnu")));
+    assert!(triple.is_equivalent_to(&TargetTriple::create("x86_64-linux-gnu")));
+    assert!(!triple.is_equivalent_to(&TargetTriple::create("x86_64-unknown-linux-musl")));
+    assert!(!triple.is_equivalent_to(&TargetTriple::create("i686-unknown-linux-gnu")));
+}
+
+#[test]
+fn test_struct_layout() {
+    let context = Context::create();
+    let target_data = TargetData::create("e-m:e-i64:64-f80:128-n8:16:32:64-S128");
+    let i8_type = context.i8_type();
+    let i16_type = context.i16_type();
+    let i64_type = context.i64_type();
+    let struct_type = context.struct_type(&[i8_type.into(), i64_type.into(), i16_type.into(), context.x86_f80_type().into()], false);
+    let layout = target_data.get_struct_layout(&struct_type).unwrap();
+
+    assert_eq!(layout.get_size(), 48);
+    assert_eq!(layout.get_alignment(), 16);
+    assert!(!layout.is_packed());
+    assert_eq!(layout.count_fields(), 4);
+    assert_eq!(layout.get_field_offsets(), &[0, 8, 16, 32]);
+    assert_eq!(layout.get_field_offset(1), target_data.offset_of_element(&struct_type, 1));
+    assert_eq!(layout.get_field_offset(4), None);
+    // An x86_fp80 stores 10 bytes but is allocated 16
+    assert_eq!(layout.get_field_size(3), Some(16));
+
+    let holes = layout.get_padding_holes();
+
+    assert_eq!(holes.len(), 2);
+    assert_eq!((holes[0].get_after_field(), holes[0].get_offset(), holes[0].get_size()), (Some(0), 1, 7));
+    assert_eq!((holes[1].get_after_field(), holes[1].get_offset(), holes[1].get_size()), (Some(2), 18, 14));
+    assert_eq!(layout.get_trailing_padding(), 0);
+
+    let packed_type = context.struct_type(&[i8_type.into(), i64_type.into(), i16_type.into()], true);
+    let packed_layout = target_data.get_struct_layout(&packed_type).unwrap();
+
+    assert!(packed_layout.is_packed());
+    assert_eq!(packed_layout.get_size(), 11);
+    assert_eq!(packed_layout.get_alignment(), 1);
+    assert_eq!(packed_layout.get_field_offsets(), &[0, 1, 9]);
+    assert!(packed_layout.get_padding_holes().is_empty());
+
+    let tail_padded_type = context.struct_type(&[i64_type.into(), i8_type.into()], false);
+
+    assert_eq!(target_data.get_struct_layout(&tail_padded_type).unwrap().get_trailing_padding(), 7);
+    assert!(target_data.get_struct_layout(&context.opaque_struct_type("opaque")).is_none());
+}
+
+#[test]
+fn test_sequence_layout() {
+    let context = Context::create();
+    let target_data = TargetData::create("e-p:64:64-p3:32:32-p5:16:16-i64:64-n32:64");
+    let i8_type = context.i8_type();
+
+    // Address spaces without a pointer layout of their own use address space 0's
+    for &(address_space, size, alignment) in &[(AddressSpace::Generic, 24, 8), (AddressSpace::Global, 24, 8), (AddressSpace::Shared, 12, 4), (AddressSpace::Local, 6, 2)] {
+        let array_type = i8_type.ptr_type(address_space).array_type(3);
+        let layout = target_data.get_array_layout(&array_type);
+
+        assert_eq!(layout.len(), 3);
+        assert_eq!(layout.get_size(), size);
+        assert_eq!(layout.get_alignment(), alignment);
+        assert_eq!(layout.get_element_offset(2), Some(size / 3 * 2));
+        assert_eq!(layout.get_element_offset(3), None);
+    }
+
+    let vector_layout = target_data.get_vector_layout(&context.i32_type().vec_type(3));
+
+    assert_eq!(vector_layout.get_bit_size(), 96);
+    assert_eq!(vector_layout.get_store_size(), 12);
+    assert_eq!(vector_layout.get_size(), 16);
+    assert_eq!(vector_layout.get_alignment(), 16);
+    assert_eq!(vector_layout.get_element_offset(2), Some(8));
+
+    // Vector elements are packed, so i1s don't start on bytes
+    let bool_vector_layout = target_data.get_vector_layout(&context.bool_type().vec_type(4));
+
+    assert_eq!(bool_vector_layout.get_bit_size(), 4);
+    assert_eq!(bool_vector_layout.get_store_size(), 1);
+    assert_eq!(bool_vector_layout.get_element_offset(1), None);
+}
@@ -1211,3 +1211,59 @@ fn test_constant_expression() {
     assert!(expr.is_const());
     assert!(!expr.is_constant_int());
 }
+
+#[llvm_versions(3.9..=latest)]
+#[test]
+fn test_cfg_to_dot() {
+    let context = Context::create();
+    let module = context.create_module("cfg");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let fn_type = i32_type.fn_type(&[i32_type.into()], false);
+    let function = module.add_function("classify", fn_type, None);
+    let entry = context.append_basic_block(function, "entry");
+    let switch = context.append_basic_block(function, "switch");
+    let one = context.append_basic_block(function, "one");
+    let other = context.append_basic_block(function, "");
+    let arg = function.get_first_param().unwrap().into_int_value();
+
+    builder.position_at_end(entry);
+
+    let is_negative = builder.build_int_compare(inkwell::IntPredicate::SLT, arg, i32_type.const_zero(), "is_negative");
+
+    builder.build_conditional_branch(is_negative, other, switch);
+    builder.position_at_end(switch);
+    builder.build_switch(arg, other, &[(i32_type.const_int(1, false), one), (i32_type.const_all_ones(), other)]);
+    builder.position_at_end(one);
+    builder.build_return(Some(&i32_type.const_int(1, false)));
+    builder.position_at_end(other);
+    builder.build_return(Some(&i32_type.const_zero()));
+
+    let expected = "\
+digraph \"CFG for 'classify' function\" {
+    node [shape=box];
+    b0 [label=\"entry\"];
+    b1 [label=\"switch\"];
+    b2 [label=\"one\"];
+    b3 [label=\"bb3\"];
+    b0 -> b3 [label=\"T\"];
+    b0 -> b1 [label=\"F\"];
+    b1 -> b3 [label=\"default\"];
+    b1 -> b2 [label=\"1\"];
+    b1 -> b3 [label=\"-1\"];
+}
+";
+
+    assert_eq!(function.cfg_only_to_dot(), expected);
+
+    let dot = function.cfg_to_dot();
+
+    assert!(dot.contains("    b0 [label=\"entry:\\l  %is_negative = icmp slt i32 %0, 0\\l  br i1 %is_negative, label %1, label %switch\\l\"];\n"));
+    assert!(dot.contains("    b2 [label=\"one:\\l  ret i32 1\\l\"];\n"));
+    assert!(dot.contains("    b0 -> b3 [label=\"T\"];\n"));
+
+    // Declarations have no control flow graph
+    let declaration = module.add_function("declaration", fn_type, None);
+
+    assert_eq!(declaration.cfg_only_to_dot(), "digraph \"CFG for 'declaration' function\" {\n    node [shape=box];\n}\n");
+}
@@ -0,0 +1,111 @@
+use inkwell::OptimizationLevel;
+use inkwell::context::Context;
+use inkwell::module::Module;
+use inkwell::object_file::ObjectFile;
+use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetTriple};
+
+fn write_wasm_object(module: &Module) -> ObjectFile {
+    Target::initialize_webassembly(&InitializationConfig::default());
+
+    let triple = TargetTriple::create("wasm32-unknown-unknown");
+    let target = Target::from_triple(&triple).unwrap();
+    let target_machine = target
+        .create_target_machine(&triple, "generic", "", OptimizationLevel::Default, RelocMode::Default, CodeModel::Default)
+        .unwrap();
+
+    module.set_triple(&triple);
+
+    target_machine
+        .write_to_memory_buffer(module, FileType::Object)
+        .unwrap()
+        .create_object_file()
+        .unwrap()
+}
+
+// Standard sections are named by their kind, such as "IMPORT", and custom sections by their own name
+fn get_section_contents(object_file: &ObjectFile, name: &str) -> Option<Vec<u8>> {
+    object_file
+        .get_sections()
+        .find(|section| section.get_name().and_then(|name| name.to_str().ok()) == Some(name))
+        .map(|section| section.get_contents().to_vec())
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+// Defines "run", which calls the imported "log"
+fn create_logging_module(context: &Context) -> Module {
+    let module = context.create_module("wasm");
+    let builder = context.create_builder();
+    let i32_type = context.i32_type();
+    let log = module.add_function("log", context.void_type().fn_type(&[i32_type.into()], false), None);
+    let run = module.add_function("run", context.void_type().fn_type(&[], false), None);
+
+    log.set_wasm_import_module("console");
+    log.set_wasm_import_name("log_i32");
+    builder.position_at_end(context.append_basic_block(run, "entry"));
+    builder.build_call(log, &[i32_type.const_int(42, false).into()], "");
+    builder.build_return(None);
+
+    module
+}
+
+#[test]
+fn test_wasm_imports_and_custom_sections() {
+    let context = Context::create();
+    let module = create_logging_module(&context);
+
+    module.add_wasm_custom_section("build_id", b"inkwell-build-0042");
+
+    assert!(module.verify().is_ok());
+
+    let object_file = write_wasm_object(&module);
+    let imports = get_section_contents(&object_file, "IMPORT").unwrap();
+
+    assert!(contains(&imports, b"console"));
+    assert!(contains(&imports, b"log_i32"));
+    assert!(contains(&get_section_contents(&object_file, "build_id").unwrap(), b"inkwell-build-0042"));
+    assert!(object_file.get_symbols().any(|symbol| symbol.get_name().and_then(|name| name.to_str().ok()) == Some("run")));
+}
+
+#[llvm_versions(9.0..=latest)]
+#[test]
+fn test_wasm_target_features() {
+    use inkwell::attributes::AttributeLoc;
+    use inkwell::targets::WasmFeature;
+
+    assert_eq!(WasmFeature::to_features_string(&[WasmFeature::Simd128, WasmFeature::BulkMemory]), "+simd128,+bulk-memory");
+
+    let context = Context::create();
+    let module = create_logging_module(&context);
+
+    module.set_wasm_target_features(&[WasmFeature::Simd128, WasmFeature::BulkMemory]);
+
+    let run = module.get_function("run").unwrap();
+    let log = module.get_function("log").unwrap();
+    let features = run.get_string_attribute(AttributeLoc::Function, "target-features").unwrap();
+
+    assert_eq!(features.get_string_value().to_str(), Ok("+simd128,+bulk-memory"));
+    // Declarations have no code to enable features for
+    assert!(log.get_string_attribute(AttributeLoc::Function, "target-features").is_none());
+
+    let object_file = write_wasm_object(&module);
+    let target_features = get_section_contents(&object_file, "target_features").unwrap();
+
+    assert!(contains(&target_features, b"simd128"));
+    assert!(contains(&target_features, b"bulk-memory"));
+}
+
+#[llvm_versions(11.0..=latest)]
+#[test]
+fn test_wasm_export_name() {
+    let context = Context::create();
+    let module = create_logging_module(&context);
+
+    module.get_function("run").unwrap().set_wasm_export_name("start_here");
+
+    let object_file = write_wasm_object(&module);
+
+    assert!(contains(&get_section_contents(&object_file, "EXPORT").unwrap(), b"start_here"));
+}